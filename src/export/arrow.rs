@@ -0,0 +1,1045 @@
+//! Arrow column schemas and record batch builders for flattened MRF data
+//! 
+//! Each builder accumulates rows from the crate's `types` structs and produces
+//! an Arrow [`RecordBatch`] with a stable schema. The same schemas are used for
+//! every export format so that outputs can be combined freely.
+//! 
+//! Every table starts with a `file_id` column identifying the source file, so
+//! rows from many files can share one dataset and be joined back together.
+//! 
+//! # Streaming
+//! 
+//! [`rate_batches`], [`allowed_amount_batches`], [`provider_group_batches`] and
+//! [`provider_group_member_batches`] turn items into [`RecordBatches`], an
+//! iterator of record batches holding exactly `batch_size` rows (the last batch
+//! may be smaller). It implements [`RecordBatchReader`], so it can be handed to
+//! DataFusion, Polars or Arrow Flight without going through a file format.
+//! 
+//! ```no_run
+//! use mrf_rs::export::arrow::rate_batches;
+//! use mrf_rs::parser::MrfParser;
+//! 
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let file = MrfParser::parse_in_network_file("in-network.json")?;
+//! 
+//! for batch in rate_batches("in-network.json", &file.in_network, 8192) {
+//!     let batch = batch?;
+//!     println!("{} rates", batch.num_rows());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use ::arrow::array::{
+    ArrayRef, BooleanBuilder, Float64Builder, Int64Builder, ListBuilder, StringBuilder,
+};
+use ::arrow::compute::concat_batches;
+use ::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use ::arrow::error::ArrowError;
+use ::arrow::record_batch::{RecordBatch, RecordBatchReader};
+
+use super::enum_str;
+use crate::types::{
+    AllowedAmount, AllowedAmountFile, InNetworkFile, InNetworkRate, NegotiatedPrice,
+    NegotiatedRateDetail, OutOfNetworkRate, Payment, Provider, ProviderGroup, ProviderReference,
+};
+
+/// Offset added to the ids assigned to provider groups listed inline in a
+/// negotiated rate, keeping them clear of `provider_references` ids.
+pub const INLINE_PROVIDER_GROUP_ID_OFFSET: i64 = 1 << 32;
+
+fn utf8(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::Utf8, nullable)
+}
+
+fn utf8_list(name: &str) -> Field {
+    Field::new(name, DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))), true)
+}
+
+fn int64_list(name: &str) -> Field {
+    Field::new(name, DataType::List(Arc::new(Field::new_list_field(DataType::Int64, true))), true)
+}
+
+/// Schema of the `plans` table: one row per exported file header
+pub fn plans_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        utf8("file_id", false),
+        utf8("file_type", false),
+        utf8("reporting_entity_name", false),
+        utf8("reporting_entity_type", false),
+        utf8("plan_name", true),
+        utf8("plan_id_type", true),
+        utf8("plan_id", true),
+        utf8("plan_market_type", true),
+        utf8("issuer_name", true),
+        utf8("plan_sponsor_name", true),
+        utf8("last_updated_on", false),
+        utf8("version", false),
+    ]))
+}
+
+/// Schema of the `rates` table: one row per negotiated price
+pub fn rates_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        utf8("file_id", false),
+        utf8("negotiation_arrangement", false),
+        utf8("name", false),
+        utf8("billing_code_type", false),
+        utf8("billing_code_type_version", false),
+        utf8("billing_code", false),
+        utf8("description", false),
+        utf8("severity_of_illness", true),
+        utf8("negotiated_type", false),
+        Field::new("negotiated_rate", DataType::Float64, false),
+        utf8("expiration_date", false),
+        utf8("billing_class", false),
+        utf8_list("service_code"),
+        utf8_list("billing_code_modifier"),
+        utf8("additional_information", true),
+        int64_list("provider_group_ids"),
+    ]))
+}
+
+/// Schema of the `provider_groups` table: one row per provider group
+pub fn provider_groups_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        utf8("file_id", false),
+        Field::new("provider_group_id", DataType::Int64, false),
+        Field::new("inline", DataType::Boolean, false),
+        utf8_list("network_name"),
+        utf8("location", true),
+    ]))
+}
+
+/// Schema of the `provider_group_members` table: one row per TIN and NPI in a provider group
+pub fn provider_group_members_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        utf8("file_id", false),
+        Field::new("provider_group_id", DataType::Int64, false),
+        utf8("tin_type", false),
+        utf8("tin_value", false),
+        Field::new("npi", DataType::Int64, true),
+    ]))
+}
+
+/// Schema of the `allowed_amounts` table: one row per billing provider of a payment
+pub fn allowed_amounts_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        utf8("file_id", false),
+        utf8("name", false),
+        utf8("billing_code_type", false),
+        utf8("billing_code_type_version", false),
+        utf8("billing_code", false),
+        utf8("description", false),
+        utf8("tin_type", false),
+        utf8("tin_value", false),
+        utf8_list("service_code"),
+        utf8("billing_class", false),
+        Field::new("allowed_amount", DataType::Float64, false),
+        utf8_list("billing_code_modifier"),
+        Field::new("billed_charge", DataType::Float64, false),
+        int64_list("npi"),
+    ]))
+}
+
+/// Common interface of the record batch builders
+pub trait BatchBuilder {
+    /// The schema of the batches produced by this builder
+    fn schema(&self) -> SchemaRef;
+    
+    /// Number of rows buffered since the last call to `finish`
+    fn len(&self) -> usize;
+    
+    /// Whether no rows are buffered
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Produce a record batch from the buffered rows and reset the builder
+    fn finish(&mut self) -> Result<RecordBatch, ArrowError>;
+}
+
+fn append_string_list(builder: &mut ListBuilder<StringBuilder>, values: Option<&Vec<String>>) {
+    match values {
+        Some(values) => {
+            for value in values {
+                builder.values().append_value(value);
+            }
+            builder.append(true);
+        }
+        None => builder.append(false),
+    }
+}
+
+fn append_int64_list(builder: &mut ListBuilder<Int64Builder>, values: &[i64]) {
+    for value in values {
+        builder.values().append_value(*value);
+    }
+    builder.append(true);
+}
+
+/// Builder for the `plans` table
+pub struct PlanBatchBuilder {
+    file_id: StringBuilder,
+    file_type: StringBuilder,
+    reporting_entity_name: StringBuilder,
+    reporting_entity_type: StringBuilder,
+    plan_name: StringBuilder,
+    plan_id_type: StringBuilder,
+    plan_id: StringBuilder,
+    plan_market_type: StringBuilder,
+    issuer_name: StringBuilder,
+    plan_sponsor_name: StringBuilder,
+    last_updated_on: StringBuilder,
+    version: StringBuilder,
+    rows: usize,
+}
+
+impl PlanBatchBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self {
+            file_id: StringBuilder::new(),
+            file_type: StringBuilder::new(),
+            reporting_entity_name: StringBuilder::new(),
+            reporting_entity_type: StringBuilder::new(),
+            plan_name: StringBuilder::new(),
+            plan_id_type: StringBuilder::new(),
+            plan_id: StringBuilder::new(),
+            plan_market_type: StringBuilder::new(),
+            issuer_name: StringBuilder::new(),
+            plan_sponsor_name: StringBuilder::new(),
+            last_updated_on: StringBuilder::new(),
+            version: StringBuilder::new(),
+            rows: 0,
+        }
+    }
+    
+    /// Append the header of an in-network file
+    pub fn append_in_network(&mut self, file_id: &str, file: &InNetworkFile) {
+        self.file_id.append_value(file_id);
+        self.file_type.append_value("in_network");
+        self.reporting_entity_name.append_value(&file.reporting_entity_name);
+        self.reporting_entity_type.append_value(enum_str(&file.reporting_entity_type));
+        self.plan_name.append_option(file.plan_name.as_deref());
+        self.plan_id_type.append_option(file.plan_id_type.as_ref().map(enum_str));
+        self.plan_id.append_option(file.plan_id.as_deref());
+        self.plan_market_type.append_option(file.plan_market_type.as_ref().map(enum_str));
+        self.issuer_name.append_option(file.issuer_name.as_deref());
+        self.plan_sponsor_name.append_option(file.plan_sponsor_name.as_deref());
+        self.last_updated_on.append_value(&file.last_updated_on);
+        self.version.append_value(&file.version);
+        self.rows += 1;
+    }
+    
+    /// Append the header of an allowed amount file
+    pub fn append_allowed_amount(&mut self, file_id: &str, file: &AllowedAmountFile) {
+        self.file_id.append_value(file_id);
+        self.file_type.append_value("allowed_amount");
+        self.reporting_entity_name.append_value(&file.reporting_entity_name);
+        self.reporting_entity_type.append_value(enum_str(&file.reporting_entity_type));
+        self.plan_name.append_option(file.plan_name.as_deref());
+        self.plan_id_type.append_option(file.plan_id_type.as_ref().map(enum_str));
+        self.plan_id.append_option(file.plan_id.as_deref());
+        self.plan_market_type.append_option(file.plan_market_type.as_ref().map(enum_str));
+        self.issuer_name.append_option(file.issuer_name.as_deref());
+        self.plan_sponsor_name.append_option(file.plan_sponsor_name.as_deref());
+        self.last_updated_on.append_value(&file.last_updated_on);
+        self.version.append_value(&file.version);
+        self.rows += 1;
+    }
+}
+
+impl Default for PlanBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchBuilder for PlanBatchBuilder {
+    fn schema(&self) -> SchemaRef {
+        plans_schema()
+    }
+    
+    fn len(&self) -> usize {
+        self.rows
+    }
+    
+    fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.file_id.finish()),
+            Arc::new(self.file_type.finish()),
+            Arc::new(self.reporting_entity_name.finish()),
+            Arc::new(self.reporting_entity_type.finish()),
+            Arc::new(self.plan_name.finish()),
+            Arc::new(self.plan_id_type.finish()),
+            Arc::new(self.plan_id.finish()),
+            Arc::new(self.plan_market_type.finish()),
+            Arc::new(self.issuer_name.finish()),
+            Arc::new(self.plan_sponsor_name.finish()),
+            Arc::new(self.last_updated_on.finish()),
+            Arc::new(self.version.finish()),
+        ];
+        self.rows = 0;
+        RecordBatch::try_new(self.schema(), columns)
+    }
+}
+
+/// Builder for the `rates` table
+pub struct RateBatchBuilder {
+    file_id: StringBuilder,
+    negotiation_arrangement: StringBuilder,
+    name: StringBuilder,
+    billing_code_type: StringBuilder,
+    billing_code_type_version: StringBuilder,
+    billing_code: StringBuilder,
+    description: StringBuilder,
+    severity_of_illness: StringBuilder,
+    negotiated_type: StringBuilder,
+    negotiated_rate: Float64Builder,
+    expiration_date: StringBuilder,
+    billing_class: StringBuilder,
+    service_code: ListBuilder<StringBuilder>,
+    billing_code_modifier: ListBuilder<StringBuilder>,
+    additional_information: StringBuilder,
+    provider_group_ids: ListBuilder<Int64Builder>,
+    rows: usize,
+}
+
+impl RateBatchBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self {
+            file_id: StringBuilder::new(),
+            negotiation_arrangement: StringBuilder::new(),
+            name: StringBuilder::new(),
+            billing_code_type: StringBuilder::new(),
+            billing_code_type_version: StringBuilder::new(),
+            billing_code: StringBuilder::new(),
+            description: StringBuilder::new(),
+            severity_of_illness: StringBuilder::new(),
+            negotiated_type: StringBuilder::new(),
+            negotiated_rate: Float64Builder::new(),
+            expiration_date: StringBuilder::new(),
+            billing_class: StringBuilder::new(),
+            service_code: ListBuilder::new(StringBuilder::new()),
+            billing_code_modifier: ListBuilder::new(StringBuilder::new()),
+            additional_information: StringBuilder::new(),
+            provider_group_ids: ListBuilder::new(Int64Builder::new()),
+            rows: 0,
+        }
+    }
+    
+    /// Append one negotiated price of an in-network rate
+    /// 
+    /// `provider_group_ids` lists the provider groups the price applies to:
+    /// the `provider_references` ids and the ids assigned to inline groups.
+    pub fn append(
+        &mut self,
+        file_id: &str,
+        rate: &InNetworkRate,
+        price: &NegotiatedPrice,
+        provider_group_ids: &[i64],
+    ) {
+        self.file_id.append_value(file_id);
+        self.negotiation_arrangement.append_value(enum_str(&rate.negotiation_arrangement));
+        self.name.append_value(&rate.name);
+        self.billing_code_type.append_value(enum_str(&rate.billing_code_type));
+        self.billing_code_type_version.append_value(&rate.billing_code_type_version);
+        self.billing_code.append_value(&rate.billing_code);
+        self.description.append_value(&rate.description);
+        self.severity_of_illness.append_option(rate.severity_of_illness.as_deref());
+        self.negotiated_type.append_value(enum_str(&price.negotiated_type));
+        self.negotiated_rate.append_value(price.negotiated_rate);
+        self.expiration_date.append_value(&price.expiration_date);
+        self.billing_class.append_value(enum_str(&price.billing_class));
+        append_string_list(&mut self.service_code, price.service_code.as_ref());
+        append_string_list(&mut self.billing_code_modifier, price.billing_code_modifier.as_ref());
+        self.additional_information.append_option(price.additional_information.as_deref());
+        append_int64_list(&mut self.provider_group_ids, provider_group_ids);
+        self.rows += 1;
+    }
+}
+
+impl Default for RateBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchBuilder for RateBatchBuilder {
+    fn schema(&self) -> SchemaRef {
+        rates_schema()
+    }
+    
+    fn len(&self) -> usize {
+        self.rows
+    }
+    
+    fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.file_id.finish()),
+            Arc::new(self.negotiation_arrangement.finish()),
+            Arc::new(self.name.finish()),
+            Arc::new(self.billing_code_type.finish()),
+            Arc::new(self.billing_code_type_version.finish()),
+            Arc::new(self.billing_code.finish()),
+            Arc::new(self.description.finish()),
+            Arc::new(self.severity_of_illness.finish()),
+            Arc::new(self.negotiated_type.finish()),
+            Arc::new(self.negotiated_rate.finish()),
+            Arc::new(self.expiration_date.finish()),
+            Arc::new(self.billing_class.finish()),
+            Arc::new(self.service_code.finish()),
+            Arc::new(self.billing_code_modifier.finish()),
+            Arc::new(self.additional_information.finish()),
+            Arc::new(self.provider_group_ids.finish()),
+        ];
+        self.rows = 0;
+        RecordBatch::try_new(self.schema(), columns)
+    }
+}
+
+/// Builder for the `provider_groups` table
+pub struct ProviderGroupBatchBuilder {
+    file_id: StringBuilder,
+    provider_group_id: Int64Builder,
+    inline: BooleanBuilder,
+    network_name: ListBuilder<StringBuilder>,
+    location: StringBuilder,
+    rows: usize,
+}
+
+impl ProviderGroupBatchBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self {
+            file_id: StringBuilder::new(),
+            provider_group_id: Int64Builder::new(),
+            inline: BooleanBuilder::new(),
+            network_name: ListBuilder::new(StringBuilder::new()),
+            location: StringBuilder::new(),
+            rows: 0,
+        }
+    }
+    
+    /// Append a provider reference from the file's `provider_references`
+    pub fn append_reference(&mut self, file_id: &str, reference: &ProviderReference) {
+        self.file_id.append_value(file_id);
+        self.provider_group_id.append_value(reference.provider_group_id as i64);
+        self.inline.append_value(false);
+        append_string_list(&mut self.network_name, reference.network_name.as_ref());
+        self.location.append_option(reference.location.as_deref());
+        self.rows += 1;
+    }
+    
+    /// Append a provider group listed inline in a negotiated rate
+    pub fn append_inline(&mut self, file_id: &str, provider_group_id: i64) {
+        self.file_id.append_value(file_id);
+        self.provider_group_id.append_value(provider_group_id);
+        self.inline.append_value(true);
+        self.network_name.append(false);
+        self.location.append_null();
+        self.rows += 1;
+    }
+}
+
+impl Default for ProviderGroupBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchBuilder for ProviderGroupBatchBuilder {
+    fn schema(&self) -> SchemaRef {
+        provider_groups_schema()
+    }
+    
+    fn len(&self) -> usize {
+        self.rows
+    }
+    
+    fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.file_id.finish()),
+            Arc::new(self.provider_group_id.finish()),
+            Arc::new(self.inline.finish()),
+            Arc::new(self.network_name.finish()),
+            Arc::new(self.location.finish()),
+        ];
+        self.rows = 0;
+        RecordBatch::try_new(self.schema(), columns)
+    }
+}
+
+/// Builder for the `provider_group_members` table
+pub struct ProviderGroupMemberBatchBuilder {
+    file_id: StringBuilder,
+    provider_group_id: Int64Builder,
+    tin_type: StringBuilder,
+    tin_value: StringBuilder,
+    npi: Int64Builder,
+    rows: usize,
+}
+
+impl ProviderGroupMemberBatchBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self {
+            file_id: StringBuilder::new(),
+            provider_group_id: Int64Builder::new(),
+            tin_type: StringBuilder::new(),
+            tin_value: StringBuilder::new(),
+            npi: Int64Builder::new(),
+            rows: 0,
+        }
+    }
+    
+    /// Append the TIN and NPIs of a provider group, one row per NPI
+    /// 
+    /// A group without NPIs produces a single row with a null `npi`.
+    pub fn append_group(&mut self, file_id: &str, provider_group_id: i64, group: &ProviderGroup) {
+        let tin_type = enum_str(&group.tin.id_type);
+        let npis: Vec<Option<i64>> = if group.npi.is_empty() {
+            vec![None]
+        } else {
+            group.npi.iter().copied().map(Some).collect()
+        };
+        
+        for npi in npis {
+            self.file_id.append_value(file_id);
+            self.provider_group_id.append_value(provider_group_id);
+            self.tin_type.append_value(&tin_type);
+            self.tin_value.append_value(&group.tin.value);
+            self.npi.append_option(npi);
+            self.rows += 1;
+        }
+    }
+}
+
+impl Default for ProviderGroupMemberBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchBuilder for ProviderGroupMemberBatchBuilder {
+    fn schema(&self) -> SchemaRef {
+        provider_group_members_schema()
+    }
+    
+    fn len(&self) -> usize {
+        self.rows
+    }
+    
+    fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.file_id.finish()),
+            Arc::new(self.provider_group_id.finish()),
+            Arc::new(self.tin_type.finish()),
+            Arc::new(self.tin_value.finish()),
+            Arc::new(self.npi.finish()),
+        ];
+        self.rows = 0;
+        RecordBatch::try_new(self.schema(), columns)
+    }
+}
+
+/// Builder for the `allowed_amounts` table
+pub struct AllowedAmountBatchBuilder {
+    file_id: StringBuilder,
+    name: StringBuilder,
+    billing_code_type: StringBuilder,
+    billing_code_type_version: StringBuilder,
+    billing_code: StringBuilder,
+    description: StringBuilder,
+    tin_type: StringBuilder,
+    tin_value: StringBuilder,
+    service_code: ListBuilder<StringBuilder>,
+    billing_class: StringBuilder,
+    allowed_amount: Float64Builder,
+    billing_code_modifier: ListBuilder<StringBuilder>,
+    billed_charge: Float64Builder,
+    npi: ListBuilder<Int64Builder>,
+    rows: usize,
+}
+
+impl AllowedAmountBatchBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self {
+            file_id: StringBuilder::new(),
+            name: StringBuilder::new(),
+            billing_code_type: StringBuilder::new(),
+            billing_code_type_version: StringBuilder::new(),
+            billing_code: StringBuilder::new(),
+            description: StringBuilder::new(),
+            tin_type: StringBuilder::new(),
+            tin_value: StringBuilder::new(),
+            service_code: ListBuilder::new(StringBuilder::new()),
+            billing_class: StringBuilder::new(),
+            allowed_amount: Float64Builder::new(),
+            billing_code_modifier: ListBuilder::new(StringBuilder::new()),
+            billed_charge: Float64Builder::new(),
+            npi: ListBuilder::new(Int64Builder::new()),
+            rows: 0,
+        }
+    }
+    
+    /// Append one billing provider of an out-of-network payment
+    pub fn append(
+        &mut self,
+        file_id: &str,
+        item: &OutOfNetworkRate,
+        allowed: &AllowedAmount,
+        payment: &Payment,
+        provider: &Provider,
+    ) {
+        self.file_id.append_value(file_id);
+        self.name.append_value(&item.name);
+        self.billing_code_type.append_value(enum_str(&item.billing_code_type));
+        self.billing_code_type_version.append_value(&item.billing_code_type_version);
+        self.billing_code.append_value(&item.billing_code);
+        self.description.append_value(&item.description);
+        self.tin_type.append_value(enum_str(&allowed.tin.id_type));
+        self.tin_value.append_value(&allowed.tin.value);
+        append_string_list(&mut self.service_code, allowed.service_code.as_ref());
+        self.billing_class.append_value(enum_str(&allowed.billing_class));
+        self.allowed_amount.append_value(payment.allowed_amount);
+        append_string_list(&mut self.billing_code_modifier, payment.billing_code_modifier.as_ref());
+        self.billed_charge.append_value(provider.billed_charge);
+        append_int64_list(&mut self.npi, &provider.npi);
+        self.rows += 1;
+    }
+}
+
+impl Default for AllowedAmountBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchBuilder for AllowedAmountBatchBuilder {
+    fn schema(&self) -> SchemaRef {
+        allowed_amounts_schema()
+    }
+    
+    fn len(&self) -> usize {
+        self.rows
+    }
+    
+    fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.file_id.finish()),
+            Arc::new(self.name.finish()),
+            Arc::new(self.billing_code_type.finish()),
+            Arc::new(self.billing_code_type_version.finish()),
+            Arc::new(self.billing_code.finish()),
+            Arc::new(self.description.finish()),
+            Arc::new(self.tin_type.finish()),
+            Arc::new(self.tin_value.finish()),
+            Arc::new(self.service_code.finish()),
+            Arc::new(self.billing_class.finish()),
+            Arc::new(self.allowed_amount.finish()),
+            Arc::new(self.billing_code_modifier.finish()),
+            Arc::new(self.billed_charge.finish()),
+            Arc::new(self.npi.finish()),
+        ];
+        self.rows = 0;
+        RecordBatch::try_new(self.schema(), columns)
+    }
+}
+
+/// Assigns provider group ids to the negotiated rates of one file
+/// 
+/// Inline provider groups are numbered from [`INLINE_PROVIDER_GROUP_ID_OFFSET`]
+/// in the order they appear in the file, so independent passes over the same
+/// file agree on the ids.
+pub(crate) struct ProviderGroupIds {
+    next_inline: i64,
+}
+
+impl ProviderGroupIds {
+    pub(crate) fn new() -> Self {
+        Self {
+            next_inline: INLINE_PROVIDER_GROUP_ID_OFFSET,
+        }
+    }
+    
+    /// Assign the next id to an inline provider group
+    pub(crate) fn next_inline(&mut self) -> i64 {
+        let id = self.next_inline;
+        self.next_inline += 1;
+        id
+    }
+    
+    /// Ids of every provider group a negotiated rate applies to: its
+    /// `provider_references` followed by its inline groups
+    pub(crate) fn resolve(&mut self, detail: &NegotiatedRateDetail) -> Vec<i64> {
+        let mut ids: Vec<i64> = detail
+            .provider_references
+            .iter()
+            .flatten()
+            .map(|id| *id as i64)
+            .collect();
+        for _ in detail.provider_groups.iter().flatten() {
+            ids.push(self.next_inline());
+        }
+        ids
+    }
+}
+
+/// Inline provider groups of a file paired with their assigned ids
+fn inline_provider_groups(file: &InNetworkFile) -> impl Iterator<Item = (i64, &ProviderGroup)> {
+    let mut ids = ProviderGroupIds::new();
+    file.in_network
+        .iter()
+        .flat_map(|rate| rate.negotiated_rates.iter())
+        .flat_map(|detail| detail.provider_groups.iter().flatten())
+        .map(move |group| (ids.next_inline(), group))
+}
+
+/// Iterator converting items into record batches of a fixed number of rows
+/// 
+/// Created by [`rate_batches`], [`allowed_amount_batches`],
+/// [`provider_group_batches`] and [`provider_group_member_batches`].
+pub struct RecordBatches<I, B, F> {
+    items: I,
+    builder: B,
+    append: F,
+    batch_size: usize,
+    ready: VecDeque<RecordBatch>,
+    remainder: Option<RecordBatch>,
+    done: bool,
+}
+
+impl<I, B, F> RecordBatches<I, B, F>
+where
+    I: Iterator,
+    B: BatchBuilder,
+    F: FnMut(&mut B, I::Item),
+{
+    /// Create an iterator that appends each item with `append` and yields
+    /// batches of `batch_size` rows. A `batch_size` of zero is treated as one.
+    pub fn new(items: I, builder: B, batch_size: usize, append: F) -> Self {
+        Self {
+            items,
+            builder,
+            append,
+            batch_size: batch_size.max(1),
+            ready: VecDeque::new(),
+            remainder: None,
+            done: false,
+        }
+    }
+    
+    /// Move buffered rows into full batches, keeping any partial batch as the
+    /// remainder unless this is the final flush
+    fn split(&mut self, last: bool) -> Result<(), ArrowError> {
+        let mut batch = self.builder.finish()?;
+        if let Some(remainder) = self.remainder.take() {
+            batch = concat_batches(&batch.schema(), &[remainder, batch])?;
+        }
+        
+        let mut offset = 0;
+        while batch.num_rows() - offset >= self.batch_size {
+            self.ready.push_back(batch.slice(offset, self.batch_size));
+            offset += self.batch_size;
+        }
+        
+        if offset < batch.num_rows() {
+            let rest = batch.slice(offset, batch.num_rows() - offset);
+            if last {
+                self.ready.push_back(rest);
+            } else {
+                self.remainder = Some(rest);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<I, B, F> Iterator for RecordBatches<I, B, F>
+where
+    I: Iterator,
+    B: BatchBuilder,
+    F: FnMut(&mut B, I::Item),
+{
+    type Item = Result<RecordBatch, ArrowError>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(batch) = self.ready.pop_front() {
+                return Some(Ok(batch));
+            }
+            if self.done {
+                return None;
+            }
+            
+            match self.items.next() {
+                Some(item) => {
+                    (self.append)(&mut self.builder, item);
+                    let buffered = self.builder.len()
+                        + self.remainder.as_ref().map_or(0, RecordBatch::num_rows);
+                    if buffered >= self.batch_size {
+                        if let Err(e) = self.split(false) {
+                            return Some(Err(e));
+                        }
+                    }
+                }
+                None => {
+                    self.done = true;
+                    if !self.builder.is_empty() || self.remainder.is_some() {
+                        if let Err(e) = self.split(true) {
+                            return Some(Err(e));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<I, B, F> RecordBatchReader for RecordBatches<I, B, F>
+where
+    I: Iterator,
+    B: BatchBuilder,
+    F: FnMut(&mut B, I::Item),
+{
+    fn schema(&self) -> SchemaRef {
+        self.builder.schema()
+    }
+}
+
+/// Stream in-network rates into `rates` record batches
+/// 
+/// Inline provider groups are given the same ids as
+/// [`provider_group_batches`] assigns when `rates` is the file's complete
+/// `in_network` array.
+pub fn rate_batches<I>(
+    file_id: impl Into<String>,
+    rates: I,
+    batch_size: usize,
+) -> RecordBatches<I::IntoIter, RateBatchBuilder, impl FnMut(&mut RateBatchBuilder, I::Item)>
+where
+    I: IntoIterator,
+    I::Item: Borrow<InNetworkRate>,
+{
+    let file_id = file_id.into();
+    let mut ids = ProviderGroupIds::new();
+    RecordBatches::new(
+        rates.into_iter(),
+        RateBatchBuilder::new(),
+        batch_size,
+        move |builder, rate: I::Item| {
+            let rate = rate.borrow();
+            for detail in &rate.negotiated_rates {
+                let group_ids = ids.resolve(detail);
+                for price in &detail.negotiated_prices {
+                    builder.append(&file_id, rate, price, &group_ids);
+                }
+            }
+        },
+    )
+}
+
+/// Stream out-of-network items into `allowed_amounts` record batches
+pub fn allowed_amount_batches<I>(
+    file_id: impl Into<String>,
+    items: I,
+    batch_size: usize,
+) -> RecordBatches<I::IntoIter, AllowedAmountBatchBuilder, impl FnMut(&mut AllowedAmountBatchBuilder, I::Item)>
+where
+    I: IntoIterator,
+    I::Item: Borrow<OutOfNetworkRate>,
+{
+    let file_id = file_id.into();
+    RecordBatches::new(
+        items.into_iter(),
+        AllowedAmountBatchBuilder::new(),
+        batch_size,
+        move |builder, item: I::Item| {
+            let item = item.borrow();
+            for allowed in &item.allowed_amounts {
+                for payment in &allowed.payments {
+                    for provider in &payment.providers {
+                        builder.append(&file_id, item, allowed, payment, provider);
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// A provider group of an in-network file
+pub enum ProviderGroupEntry<'a> {
+    /// An entry of the file's `provider_references`
+    Reference(&'a ProviderReference),
+    /// A group listed inline in a negotiated rate, with its assigned id
+    Inline(i64, &'a ProviderGroup),
+}
+
+/// Every provider group of an in-network file, references first
+fn provider_group_entries(file: &InNetworkFile) -> impl Iterator<Item = ProviderGroupEntry<'_>> {
+    file.provider_references
+        .iter()
+        .flatten()
+        .map(ProviderGroupEntry::Reference)
+        .chain(inline_provider_groups(file).map(|(id, group)| ProviderGroupEntry::Inline(id, group)))
+}
+
+/// Stream the provider groups of an in-network file into `provider_groups` record batches
+pub fn provider_group_batches<'a>(
+    file_id: impl Into<String>,
+    file: &'a InNetworkFile,
+    batch_size: usize,
+) -> RecordBatches<
+    impl Iterator<Item = ProviderGroupEntry<'a>>,
+    ProviderGroupBatchBuilder,
+    impl FnMut(&mut ProviderGroupBatchBuilder, ProviderGroupEntry<'a>),
+> {
+    let file_id = file_id.into();
+    RecordBatches::new(
+        provider_group_entries(file),
+        ProviderGroupBatchBuilder::new(),
+        batch_size,
+        move |builder, entry| match entry {
+            ProviderGroupEntry::Reference(reference) => builder.append_reference(&file_id, reference),
+            ProviderGroupEntry::Inline(id, _) => builder.append_inline(&file_id, id),
+        },
+    )
+}
+
+/// Stream the TIN and NPI membership of an in-network file's provider groups
+/// into `provider_group_members` record batches
+pub fn provider_group_member_batches<'a>(
+    file_id: impl Into<String>,
+    file: &'a InNetworkFile,
+    batch_size: usize,
+) -> RecordBatches<
+    impl Iterator<Item = ProviderGroupEntry<'a>>,
+    ProviderGroupMemberBatchBuilder,
+    impl FnMut(&mut ProviderGroupMemberBatchBuilder, ProviderGroupEntry<'a>),
+> {
+    let file_id = file_id.into();
+    RecordBatches::new(
+        provider_group_entries(file),
+        ProviderGroupMemberBatchBuilder::new(),
+        batch_size,
+        move |builder, entry| match entry {
+            ProviderGroupEntry::Reference(reference) => {
+                for group in reference.provider_groups.iter().flatten() {
+                    builder.append_group(&file_id, reference.provider_group_id as i64, group);
+                }
+            }
+            ProviderGroupEntry::Inline(id, group) => builder.append_group(&file_id, id, group),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::arrow::array::{Array, Int64Array, ListArray};
+    
+    fn sample_file() -> InNetworkFile {
+        serde_json::from_str(
+            r#"{
+                "reporting_entity_name": "Acme Health",
+                "reporting_entity_type": "health insurance issuer",
+                "last_updated_on": "2024-03-01",
+                "version": "1.0.0",
+                "provider_references": [{
+                    "provider_group_id": 7,
+                    "provider_groups": [{"npi": [1111111111], "tin": {"type": "ein", "value": "11-1111111"}}]
+                }],
+                "in_network": [
+                    {
+                        "negotiation_arrangement": "ffs",
+                        "name": "Office visit",
+                        "billing_code_type": "CPT",
+                        "billing_code_type_version": "2024",
+                        "billing_code": "99213",
+                        "description": "Office visit",
+                        "negotiated_rates": [{
+                            "provider_references": [7],
+                            "negotiated_prices": [
+                                {"negotiated_type": "negotiated", "negotiated_rate": 95.5, "expiration_date": "9999-12-31", "billing_class": "professional"},
+                                {"negotiated_type": "negotiated", "negotiated_rate": 120.0, "expiration_date": "9999-12-31", "billing_class": "institutional"}
+                            ]
+                        }]
+                    },
+                    {
+                        "negotiation_arrangement": "ffs",
+                        "name": "Office visit",
+                        "billing_code_type": "CPT",
+                        "billing_code_type_version": "2024",
+                        "billing_code": "99214",
+                        "description": "Office visit",
+                        "negotiated_rates": [{
+                            "provider_groups": [{"npi": [2222222222, 3333333333], "tin": {"type": "ein", "value": "22-2222222"}}],
+                            "negotiated_prices": [
+                                {"negotiated_type": "negotiated", "negotiated_rate": 140.0, "expiration_date": "9999-12-31", "billing_class": "professional"},
+                                {"negotiated_type": "negotiated", "negotiated_rate": 150.0, "expiration_date": "9999-12-31", "billing_class": "institutional"},
+                                {"negotiated_type": "negotiated", "negotiated_rate": 160.0, "expiration_date": "9999-12-31", "billing_class": "professional"}
+                            ]
+                        }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_rate_batches_split_to_batch_size() {
+        let file = sample_file();
+        let batches: Vec<_> = rate_batches("f", &file.in_network, 2)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        
+        let sizes: Vec<usize> = batches.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert!(batches.iter().all(|batch| batch.schema() == rates_schema()));
+    }
+    
+    #[test]
+    fn test_inline_group_ids_agree() {
+        let file = sample_file();
+        let rates = rate_batches("f", &file.in_network, 100).next().unwrap().unwrap();
+        let ids = rates
+            .column_by_name("provider_group_ids")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap()
+            .value(4);
+        let ids = ids.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(ids.values().to_vec(), vec![INLINE_PROVIDER_GROUP_ID_OFFSET]);
+        
+        let groups = provider_group_batches("f", &file, 100).next().unwrap().unwrap();
+        let group_ids = groups
+            .column_by_name("provider_group_id")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .values()
+            .to_vec();
+        assert_eq!(group_ids, vec![7, INLINE_PROVIDER_GROUP_ID_OFFSET]);
+        
+        let members = provider_group_member_batches("f", &file, 100).next().unwrap().unwrap();
+        assert_eq!(members.num_rows(), 3);
+    }
+    
+    #[test]
+    fn test_empty_input_yields_no_batches() {
+        let reader = rate_batches("f", Vec::<InNetworkRate>::new(), 10);
+        assert_eq!(reader.schema(), rates_schema());
+        assert_eq!(reader.count(), 0);
+    }
+}
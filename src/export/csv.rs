@@ -0,0 +1,217 @@
+//! CSV export of flattened rate rows
+//! 
+//! Writes one row per (billing code, negotiated price, provider group, TIN),
+//! with a header of the selected [`RateColumn`](super::flat::RateColumn) names.
+//! Missing values are written as empty fields.
+//! 
+//! # Example
+//! 
+//! ```no_run
+//! use mrf_rs::export::csv::CsvRateWriter;
+//! use mrf_rs::export::flat::{FlatExportOptions, NpiMode};
+//! use mrf_rs::parser::MrfParser;
+//! 
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let file = MrfParser::parse_in_network_file("in-network.json")?;
+//! 
+//! let options = FlatExportOptions {
+//!     npi_mode: NpiMode::Join,
+//!     gzip: true,
+//!     ..Default::default()
+//! };
+//! let mut writer = CsvRateWriter::create("rates.csv.gz", options)?;
+//! writer.write_in_network_file("in-network.json", &file)?;
+//! writer.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::flat::{Cell, FlatExportOptions, Output, RateFlattener};
+use super::ExportResult;
+use crate::types::{InNetworkFile, InNetworkRate, ProviderReference};
+
+/// Writes flattened rate rows as CSV
+pub struct CsvRateWriter<W: Write> {
+    writer: ::csv::Writer<Output<W>>,
+    options: FlatExportOptions,
+    rows: usize,
+}
+
+impl CsvRateWriter<BufWriter<File>> {
+    /// Create a writer for a new file at `path`
+    pub fn create(path: impl AsRef<Path>, options: FlatExportOptions) -> ExportResult<Self> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::new(file), options)
+    }
+}
+
+impl<W: Write> CsvRateWriter<W> {
+    /// Create a writer over any output stream and write the header row
+    pub fn new(writer: W, options: FlatExportOptions) -> ExportResult<Self> {
+        options.validate()?;
+        
+        let mut writer = ::csv::Writer::from_writer(Output::new(writer, options.gzip));
+        writer.write_record(options.columns.iter().map(|column| column.name()))?;
+        
+        Ok(Self {
+            writer,
+            options,
+            rows: 0,
+        })
+    }
+    
+    /// Write every rate of an in-network file, returning the number of rows written
+    pub fn write_in_network_file(&mut self, file_id: &str, file: &InNetworkFile) -> ExportResult<usize> {
+        let references = file.provider_references.as_deref().unwrap_or_default();
+        self.write_rates(file_id, &file.in_network, references)
+    }
+    
+    /// Write a sequence of rates, resolving provider references against
+    /// `provider_references`. Returns the number of rows written.
+    /// 
+    /// Inline provider group ids are numbered from the start of `rates`.
+    pub fn write_rates<'a, I>(
+        &mut self,
+        file_id: &str,
+        rates: I,
+        provider_references: &'a [ProviderReference],
+    ) -> ExportResult<usize>
+    where
+        I: IntoIterator<Item = &'a InNetworkRate>,
+    {
+        let mut flattener = RateFlattener::new(provider_references, self.options.npi_mode);
+        let mut record = ::csv::StringRecord::new();
+        let mut written = 0;
+        
+        for rate in rates {
+            written += flattener.flatten(file_id, rate, |row| {
+                record.clear();
+                for column in &self.options.columns {
+                    match column.cell(row, &self.options) {
+                        Cell::Text(text) => record.push_field(&text),
+                        Cell::Float(value) => record.push_field(&value.to_string()),
+                        Cell::Int(value) => record.push_field(&value.to_string()),
+                        Cell::Null => record.push_field(""),
+                    }
+                }
+                self.writer.write_record(&record)
+            })?;
+        }
+        
+        self.rows += written;
+        Ok(written)
+    }
+    
+    /// Total number of rows written, excluding the header
+    pub fn rows_written(&self) -> usize {
+        self.rows
+    }
+    
+    /// Flush all rows, finish compression and return the underlying writer
+    pub fn finish(self) -> ExportResult<W> {
+        let output = self.writer.into_inner().map_err(|e| e.into_error())?;
+        Ok(output.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::flat::{NpiMode, RateColumn};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    
+    fn sample_file() -> InNetworkFile {
+        serde_json::from_str(
+            r#"{
+                "reporting_entity_name": "Acme Health",
+                "reporting_entity_type": "health insurance issuer",
+                "last_updated_on": "2024-03-01",
+                "version": "1.0.0",
+                "provider_references": [{
+                    "provider_group_id": 1,
+                    "provider_groups": [{"npi": [1111111111, 2222222222], "tin": {"type": "ein", "value": "11-1111111"}}]
+                }],
+                "in_network": [{
+                    "negotiation_arrangement": "ffs",
+                    "name": "Office visit",
+                    "billing_code_type": "CPT",
+                    "billing_code_type_version": "2024",
+                    "billing_code": "99213",
+                    "description": "Office visit, established",
+                    "negotiated_rates": [{
+                        "provider_references": [1],
+                        "negotiated_prices": [{
+                            "negotiated_type": "negotiated",
+                            "negotiated_rate": 95.5,
+                            "expiration_date": "9999-12-31",
+                            "billing_class": "professional",
+                            "service_code": ["11", "22"]
+                        }]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap()
+    }
+    
+    fn options(npi_mode: NpiMode) -> FlatExportOptions {
+        FlatExportOptions {
+            columns: vec![
+                RateColumn::BillingCode,
+                RateColumn::NegotiatedRate,
+                RateColumn::ServiceCode,
+                RateColumn::TinValue,
+                RateColumn::Npi,
+            ],
+            npi_mode,
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_csv_explode_npis() {
+        let mut writer = CsvRateWriter::new(Vec::new(), options(NpiMode::Explode)).unwrap();
+        assert_eq!(writer.write_in_network_file("f", &sample_file()).unwrap(), 2);
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        
+        assert_eq!(
+            output,
+            "billing_code,negotiated_rate,service_code,tin_value,npi\n\
+             99213,95.5,11;22,11-1111111,1111111111\n\
+             99213,95.5,11;22,11-1111111,2222222222\n"
+        );
+    }
+    
+    #[test]
+    fn test_csv_join_npis_gzip() {
+        let mut options = options(NpiMode::Join);
+        options.gzip = true;
+        options.list_delimiter = "|".to_string();
+        
+        let mut writer = CsvRateWriter::new(Vec::new(), options).unwrap();
+        writer.write_in_network_file("f", &sample_file()).unwrap();
+        let compressed = writer.finish().unwrap();
+        
+        let mut output = String::new();
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut output).unwrap();
+        assert_eq!(
+            output,
+            "billing_code,negotiated_rate,service_code,tin_value,npi\n\
+             99213,95.5,11|22,11-1111111,1111111111|2222222222\n"
+        );
+    }
+    
+    #[test]
+    fn test_empty_columns_rejected() {
+        let options = FlatExportOptions {
+            columns: Vec::new(),
+            ..Default::default()
+        };
+        assert!(CsvRateWriter::new(Vec::new(), options).is_err());
+    }
+}
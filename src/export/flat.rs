@@ -0,0 +1,378 @@
+//! Flattened rate rows shared by the line-oriented export formats
+//! 
+//! A flat rate row is one (billing code, negotiated price, provider group, TIN)
+//! combination of an in-network file. Provider references are resolved against
+//! the file's `provider_references`, so each row carries the TIN and NPIs of
+//! its provider group. Which fields become columns, and how NPI lists are
+//! represented, is controlled by [`FlatExportOptions`].
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use super::arrow::ProviderGroupIds;
+use super::{enum_str, ExportError};
+use crate::types::{InNetworkRate, NegotiatedPrice, ProviderGroup, ProviderReference, TaxIdentifier};
+
+/// A column of the flattened rate rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateColumn {
+    /// Identifier of the source file given to the writer
+    FileId,
+    /// Negotiation arrangement of the rate (ffs, bundle, capitation)
+    NegotiationArrangement,
+    /// Name of the item or service
+    Name,
+    /// Billing code type (CPT, HCPCS, ...)
+    BillingCodeType,
+    /// Version of the billing code type
+    BillingCodeTypeVersion,
+    /// Billing code
+    BillingCode,
+    /// Description of the billing code
+    Description,
+    /// Severity of illness for DRG-based rates
+    SeverityOfIllness,
+    /// Type of negotiated price
+    NegotiatedType,
+    /// Negotiated rate amount
+    NegotiatedRate,
+    /// Expiration date of the negotiated price
+    ExpirationDate,
+    /// Billing class (professional, institutional, both)
+    BillingClass,
+    /// Place of service codes, joined with the list delimiter
+    ServiceCode,
+    /// Billing code modifiers, joined with the list delimiter
+    BillingCodeModifier,
+    /// Additional information about the negotiated price
+    AdditionalInformation,
+    /// Provider group id, using the Arrow export's ids for inline groups
+    ProviderGroupId,
+    /// Type of the provider group's TIN
+    TinType,
+    /// Value of the provider group's TIN
+    TinValue,
+    /// NPI, or the joined NPI list depending on [`NpiMode`]
+    Npi,
+}
+
+impl RateColumn {
+    /// Every column, in the default output order
+    pub const ALL: &'static [RateColumn] = &[
+        RateColumn::FileId,
+        RateColumn::NegotiationArrangement,
+        RateColumn::Name,
+        RateColumn::BillingCodeType,
+        RateColumn::BillingCodeTypeVersion,
+        RateColumn::BillingCode,
+        RateColumn::Description,
+        RateColumn::SeverityOfIllness,
+        RateColumn::NegotiatedType,
+        RateColumn::NegotiatedRate,
+        RateColumn::ExpirationDate,
+        RateColumn::BillingClass,
+        RateColumn::ServiceCode,
+        RateColumn::BillingCodeModifier,
+        RateColumn::AdditionalInformation,
+        RateColumn::ProviderGroupId,
+        RateColumn::TinType,
+        RateColumn::TinValue,
+        RateColumn::Npi,
+    ];
+    
+    /// The column header, matching the field names of the Arrow schemas
+    pub fn name(&self) -> &'static str {
+        match self {
+            RateColumn::FileId => "file_id",
+            RateColumn::NegotiationArrangement => "negotiation_arrangement",
+            RateColumn::Name => "name",
+            RateColumn::BillingCodeType => "billing_code_type",
+            RateColumn::BillingCodeTypeVersion => "billing_code_type_version",
+            RateColumn::BillingCode => "billing_code",
+            RateColumn::Description => "description",
+            RateColumn::SeverityOfIllness => "severity_of_illness",
+            RateColumn::NegotiatedType => "negotiated_type",
+            RateColumn::NegotiatedRate => "negotiated_rate",
+            RateColumn::ExpirationDate => "expiration_date",
+            RateColumn::BillingClass => "billing_class",
+            RateColumn::ServiceCode => "service_code",
+            RateColumn::BillingCodeModifier => "billing_code_modifier",
+            RateColumn::AdditionalInformation => "additional_information",
+            RateColumn::ProviderGroupId => "provider_group_id",
+            RateColumn::TinType => "tin_type",
+            RateColumn::TinValue => "tin_value",
+            RateColumn::Npi => "npi",
+        }
+    }
+    
+    /// The value of this column for a row
+    /// 
+    /// The type of a column's value does not change from row to row: in
+    /// [`NpiMode::Join`] the `npi` column is text even for a single NPI.
+    pub(crate) fn cell<'a>(&self, row: &FlatRateRow<'a>, options: &FlatExportOptions) -> Cell<'a> {
+        let delimiter = options.list_delimiter.as_str();
+        let join = |values: Option<&Vec<String>>| match values {
+            Some(values) => Cell::Text(Cow::Owned(values.join(delimiter))),
+            None => Cell::Null,
+        };
+        
+        match self {
+            RateColumn::FileId => Cell::Text(Cow::Borrowed(row.file_id)),
+            RateColumn::NegotiationArrangement => {
+                Cell::Text(Cow::Owned(enum_str(&row.rate.negotiation_arrangement)))
+            }
+            RateColumn::Name => Cell::Text(Cow::Borrowed(&row.rate.name)),
+            RateColumn::BillingCodeType => Cell::Text(Cow::Owned(enum_str(&row.rate.billing_code_type))),
+            RateColumn::BillingCodeTypeVersion => Cell::Text(Cow::Borrowed(&row.rate.billing_code_type_version)),
+            RateColumn::BillingCode => Cell::Text(Cow::Borrowed(&row.rate.billing_code)),
+            RateColumn::Description => Cell::Text(Cow::Borrowed(&row.rate.description)),
+            RateColumn::SeverityOfIllness => match &row.rate.severity_of_illness {
+                Some(severity) => Cell::Text(Cow::Borrowed(severity)),
+                None => Cell::Null,
+            },
+            RateColumn::NegotiatedType => Cell::Text(Cow::Owned(enum_str(&row.price.negotiated_type))),
+            RateColumn::NegotiatedRate => Cell::Float(row.price.negotiated_rate),
+            RateColumn::ExpirationDate => Cell::Text(Cow::Borrowed(&row.price.expiration_date)),
+            RateColumn::BillingClass => Cell::Text(Cow::Owned(enum_str(&row.price.billing_class))),
+            RateColumn::ServiceCode => join(row.price.service_code.as_ref()),
+            RateColumn::BillingCodeModifier => join(row.price.billing_code_modifier.as_ref()),
+            RateColumn::AdditionalInformation => match &row.price.additional_information {
+                Some(info) => Cell::Text(Cow::Borrowed(info)),
+                None => Cell::Null,
+            },
+            RateColumn::ProviderGroupId => row.provider_group_id.map_or(Cell::Null, Cell::Int),
+            RateColumn::TinType => row
+                .tin
+                .map_or(Cell::Null, |tin| Cell::Text(Cow::Owned(enum_str(&tin.id_type)))),
+            RateColumn::TinValue => row
+                .tin
+                .map_or(Cell::Null, |tin| Cell::Text(Cow::Borrowed(&tin.value))),
+            RateColumn::Npi => match (row.npi, options.npi_mode) {
+                (Some([npi]), NpiMode::Explode) => Cell::Int(*npi),
+                (Some(npis), NpiMode::Join) if !npis.is_empty() => Cell::Text(Cow::Owned(
+                    npis.iter().map(i64::to_string).collect::<Vec<_>>().join(delimiter),
+                )),
+                _ => Cell::Null,
+            },
+        }
+    }
+}
+
+impl fmt::Display for RateColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for RateColumn {
+    type Err = ExportError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RateColumn::ALL
+            .iter()
+            .copied()
+            .find(|column| column.name() == s)
+            .ok_or_else(|| ExportError::Config(format!("Unknown rate column: {}", s)))
+    }
+}
+
+/// How the NPI list of a provider group is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NpiMode {
+    /// One row per NPI
+    #[default]
+    Explode,
+    /// One row per TIN with the NPIs joined by the list delimiter
+    Join,
+}
+
+/// Options shared by the CSV and NDJSON rate writers
+#[derive(Debug, Clone)]
+pub struct FlatExportOptions {
+    /// Columns to write, in order
+    pub columns: Vec<RateColumn>,
+    
+    /// Representation of NPI lists
+    pub npi_mode: NpiMode,
+    
+    /// Delimiter used to join list values into a single column
+    pub list_delimiter: String,
+    
+    /// Whether to gzip-compress the output
+    pub gzip: bool,
+}
+
+impl Default for FlatExportOptions {
+    fn default() -> Self {
+        Self {
+            columns: RateColumn::ALL.to_vec(),
+            npi_mode: NpiMode::default(),
+            list_delimiter: ";".to_string(),
+            gzip: false,
+        }
+    }
+}
+
+impl FlatExportOptions {
+    pub(crate) fn validate(&self) -> Result<(), ExportError> {
+        if self.columns.is_empty() {
+            return Err(ExportError::Config("At least one column must be selected".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// One flattened rate row
+pub(crate) struct FlatRateRow<'a> {
+    pub file_id: &'a str,
+    pub rate: &'a InNetworkRate,
+    pub price: &'a NegotiatedPrice,
+    pub provider_group_id: Option<i64>,
+    pub tin: Option<&'a TaxIdentifier>,
+    pub npi: Option<&'a [i64]>,
+}
+
+/// A typed column value
+pub(crate) enum Cell<'a> {
+    Text(Cow<'a, str>),
+    Float(f64),
+    Int(i64),
+    Null,
+}
+
+/// Expands in-network rates into flat rows
+pub(crate) struct RateFlattener<'a> {
+    references: HashMap<i64, &'a ProviderReference>,
+    ids: ProviderGroupIds,
+    npi_mode: NpiMode,
+}
+
+impl<'a> RateFlattener<'a> {
+    pub(crate) fn new(provider_references: &'a [ProviderReference], npi_mode: NpiMode) -> Self {
+        Self {
+            references: provider_references
+                .iter()
+                .map(|reference| (reference.provider_group_id as i64, reference))
+                .collect(),
+            ids: ProviderGroupIds::new(),
+            npi_mode,
+        }
+    }
+    
+    /// Call `emit` for every row of a rate, returning the number of rows
+    pub(crate) fn flatten<'b, E>(
+        &mut self,
+        file_id: &'b str,
+        rate: &'b InNetworkRate,
+        mut emit: impl FnMut(&FlatRateRow<'b>) -> Result<(), E>,
+    ) -> Result<usize, E>
+    where
+        'a: 'b,
+    {
+        let mut rows = 0;
+        
+        for detail in &rate.negotiated_rates {
+            let ids = self.ids.resolve(detail);
+            let reference_count = ids.len() - detail.provider_groups.as_ref().map_or(0, Vec::len);
+            
+            let mut groups: Vec<(Option<i64>, Option<&'b ProviderGroup>)> = Vec::new();
+            for id in &ids[..reference_count] {
+                match self.references.get(id).and_then(|r| r.provider_groups.as_ref()) {
+                    Some(reference_groups) if !reference_groups.is_empty() => {
+                        groups.extend(reference_groups.iter().map(|group| (Some(*id), Some(group))));
+                    }
+                    _ => groups.push((Some(*id), None)),
+                }
+            }
+            for (id, group) in ids[reference_count..].iter().zip(detail.provider_groups.iter().flatten()) {
+                groups.push((Some(*id), Some(group)));
+            }
+            if groups.is_empty() {
+                groups.push((None, None));
+            }
+            
+            for price in &detail.negotiated_prices {
+                for (provider_group_id, group) in &groups {
+                    let mut row = FlatRateRow {
+                        file_id,
+                        rate,
+                        price,
+                        provider_group_id: *provider_group_id,
+                        tin: group.map(|group| &group.tin),
+                        npi: None,
+                    };
+                    
+                    match (group, self.npi_mode) {
+                        (Some(group), NpiMode::Explode) if !group.npi.is_empty() => {
+                            for npi in &group.npi {
+                                row.npi = Some(std::slice::from_ref(npi));
+                                emit(&row)?;
+                                rows += 1;
+                            }
+                        }
+                        (group, _) => {
+                            row.npi = group.map(|group| group.npi.as_slice());
+                            emit(&row)?;
+                            rows += 1;
+                        }
+                    }
+                }
+            }
+        }
+        
+        Ok(rows)
+    }
+}
+
+/// An output stream that is optionally gzip-compressed
+pub(crate) enum Output<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+}
+
+impl<W: Write> Output<W> {
+    pub(crate) fn new(writer: W, gzip: bool) -> Self {
+        if gzip {
+            Output::Gzip(GzEncoder::new(writer, Compression::default()))
+        } else {
+            Output::Plain(writer)
+        }
+    }
+    
+    /// Finish compression and return the underlying writer
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Output::Plain(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+            Output::Gzip(encoder) => {
+                let mut writer = encoder.finish()?;
+                writer.flush()?;
+                Ok(writer)
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(writer) => writer.write(buf),
+            Output::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(writer) => writer.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Export of parsed MRF data into analytics formats
+//! 
+//! This module flattens the nested Transparency in Coverage structures into
+//! tabular rows that can be loaded by query engines such as DuckDB and Spark.
+//! 
+//! # Architecture
+//! 
+//! - [`arrow`]: column schemas, record batch builders shared by all formats,
+//!   and streaming conversion of items into Arrow record batches
+//! - [`parquet`]: Parquet files with optional hive-style partitioning
+//! - [`flat`]: column selection and NPI handling for one-row-per-TIN rate rows
+//! - [`csv`] and [`ndjson`]: line-oriented writers of flat rate rows, optionally gzipped
+
+use serde::Serialize;
+use thiserror::Error;
+
+pub mod arrow;
+pub mod csv;
+pub mod flat;
+pub mod ndjson;
+pub mod parquet;
+
+/// Error type for export operations
+#[derive(Debug, Error)]
+pub enum ExportError {
+    /// IO error occurred while writing output
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    /// Error building Arrow arrays or record batches
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ::arrow::error::ArrowError),
+    
+    /// Error encoding Parquet output
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ::parquet::errors::ParquetError),
+    
+    /// Error writing CSV output
+    #[error("CSV error: {0}")]
+    Csv(#[from] ::csv::Error),
+    
+    /// Error encoding JSON output
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    
+    /// Invalid export configuration
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+}
+
+/// Result type for export operations
+pub type ExportResult<T> = Result<T, ExportError>;
+
+/// Render a serde-backed enum as the string it has in MRF files
+pub(crate) fn enum_str<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(other) => other.to_string(),
+        Err(_) => String::new(),
+    }
+}
@@ -0,0 +1,203 @@
+//! Newline-delimited JSON export of flattened rate rows
+//! 
+//! Writes one JSON object per (billing code, negotiated price, provider group,
+//! TIN) row, with keys in the order of the selected
+//! [`RateColumn`](super::flat::RateColumn)s. Rates and ids are written as JSON
+//! numbers, missing values as `null`, and list values joined into strings the
+//! same way as the CSV writer.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::flat::{Cell, FlatExportOptions, Output, RateFlattener};
+use super::{ExportError, ExportResult};
+use crate::types::{InNetworkFile, InNetworkRate, ProviderReference};
+
+/// Writes flattened rate rows as newline-delimited JSON
+pub struct NdjsonRateWriter<W: Write> {
+    output: Output<W>,
+    options: FlatExportOptions,
+    rows: usize,
+}
+
+impl NdjsonRateWriter<BufWriter<File>> {
+    /// Create a writer for a new file at `path`
+    pub fn create(path: impl AsRef<Path>, options: FlatExportOptions) -> ExportResult<Self> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::new(file), options)
+    }
+}
+
+impl<W: Write> NdjsonRateWriter<W> {
+    /// Create a writer over any output stream
+    pub fn new(writer: W, options: FlatExportOptions) -> ExportResult<Self> {
+        options.validate()?;
+        
+        Ok(Self {
+            output: Output::new(writer, options.gzip),
+            options,
+            rows: 0,
+        })
+    }
+    
+    /// Write every rate of an in-network file, returning the number of rows written
+    pub fn write_in_network_file(&mut self, file_id: &str, file: &InNetworkFile) -> ExportResult<usize> {
+        let references = file.provider_references.as_deref().unwrap_or_default();
+        self.write_rates(file_id, &file.in_network, references)
+    }
+    
+    /// Write a sequence of rates, resolving provider references against
+    /// `provider_references`. Returns the number of rows written.
+    /// 
+    /// Inline provider group ids are numbered from the start of `rates`.
+    pub fn write_rates<'a, I>(
+        &mut self,
+        file_id: &str,
+        rates: I,
+        provider_references: &'a [ProviderReference],
+    ) -> ExportResult<usize>
+    where
+        I: IntoIterator<Item = &'a InNetworkRate>,
+    {
+        let mut flattener = RateFlattener::new(provider_references, self.options.npi_mode);
+        let mut line = String::new();
+        let mut written = 0;
+        
+        for rate in rates {
+            written += flattener.flatten(file_id, rate, |row| {
+                line.clear();
+                line.push('{');
+                for (i, column) in self.options.columns.iter().enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    line.push_str(&serde_json::to_string(column.name())?);
+                    line.push(':');
+                    match column.cell(row, &self.options) {
+                        Cell::Text(text) => line.push_str(&serde_json::to_string(text.as_ref())?),
+                        Cell::Float(value) => line.push_str(&serde_json::to_string(&value)?),
+                        Cell::Int(value) => line.push_str(&value.to_string()),
+                        Cell::Null => line.push_str("null"),
+                    }
+                }
+                line.push_str("}\n");
+                self.output.write_all(line.as_bytes())?;
+                Ok::<_, ExportError>(())
+            })?;
+        }
+        
+        self.rows += written;
+        Ok(written)
+    }
+    
+    /// Total number of rows written
+    pub fn rows_written(&self) -> usize {
+        self.rows
+    }
+    
+    /// Flush all rows, finish compression and return the underlying writer
+    pub fn finish(self) -> ExportResult<W> {
+        Ok(self.output.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::flat::{NpiMode, RateColumn};
+    
+    #[test]
+    fn test_ndjson_rows() {
+        let file: InNetworkFile = serde_json::from_str(
+            r#"{
+                "reporting_entity_name": "Acme Health",
+                "reporting_entity_type": "health insurance issuer",
+                "last_updated_on": "2024-03-01",
+                "version": "1.0.0",
+                "in_network": [{
+                    "negotiation_arrangement": "ffs",
+                    "name": "Office visit",
+                    "billing_code_type": "CPT",
+                    "billing_code_type_version": "2024",
+                    "billing_code": "99213",
+                    "description": "Office \"visit\"",
+                    "negotiated_rates": [{
+                        "provider_groups": [{"npi": [1111111111, 2222222222], "tin": {"type": "ein", "value": "11-1111111"}}],
+                        "negotiated_prices": [{
+                            "negotiated_type": "negotiated",
+                            "negotiated_rate": 100.0,
+                            "expiration_date": "9999-12-31",
+                            "billing_class": "professional"
+                        }]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        
+        let options = FlatExportOptions {
+            columns: vec![
+                RateColumn::Description,
+                RateColumn::NegotiatedRate,
+                RateColumn::ServiceCode,
+                RateColumn::TinType,
+                RateColumn::Npi,
+            ],
+            npi_mode: NpiMode::Join,
+            ..Default::default()
+        };
+        let mut writer = NdjsonRateWriter::new(Vec::new(), options).unwrap();
+        assert_eq!(writer.write_in_network_file("f", &file).unwrap(), 1);
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        
+        assert_eq!(
+            output,
+            "{\"description\":\"Office \\\"visit\\\"\",\"negotiated_rate\":100.0,\"service_code\":null,\"tin_type\":\"ein\",\"npi\":\"1111111111;2222222222\"}\n"
+        );
+    }
+    
+    #[test]
+    fn test_joined_npi_is_always_text() {
+        let file: InNetworkFile = serde_json::from_str(
+            r#"{
+                "reporting_entity_name": "Acme Health",
+                "reporting_entity_type": "health insurance issuer",
+                "last_updated_on": "2024-03-01",
+                "version": "1.0.0",
+                "in_network": [{
+                    "negotiation_arrangement": "ffs",
+                    "name": "Office visit",
+                    "billing_code_type": "CPT",
+                    "billing_code_type_version": "2024",
+                    "billing_code": "99213",
+                    "description": "Office visit",
+                    "negotiated_rates": [{
+                        "provider_groups": [
+                            {"npi": [1111111111], "tin": {"type": "ein", "value": "11-1111111"}},
+                            {"npi": [2222222222, 3333333333], "tin": {"type": "ein", "value": "22-2222222"}}
+                        ],
+                        "negotiated_prices": [{
+                            "negotiated_type": "negotiated",
+                            "negotiated_rate": 100.0,
+                            "expiration_date": "9999-12-31",
+                            "billing_class": "professional"
+                        }]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        
+        let options = FlatExportOptions {
+            columns: vec![RateColumn::Npi],
+            npi_mode: NpiMode::Join,
+            ..Default::default()
+        };
+        let mut writer = NdjsonRateWriter::new(Vec::new(), options).unwrap();
+        assert_eq!(writer.write_in_network_file("f", &file).unwrap(), 2);
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        
+        assert_eq!(output, "{\"npi\":\"1111111111\"}\n{\"npi\":\"2222222222;3333333333\"}\n");
+    }
+}
@@ -0,0 +1,733 @@
+//! Parquet export with optional hive-style partitioning
+//! 
+//! [`ParquetExporter`] writes one dataset directory per table below its output
+//! directory. Each table uses the schema of the same name in [`super::arrow`]:
+//! 
+//! | Table | Rows | Schema |
+//! |-------|------|--------|
+//! | `plans` | One per exported file | [`plans_schema`](super::arrow::plans_schema) |
+//! | `rates` | One per negotiated price | [`rates_schema`](super::arrow::rates_schema) |
+//! | `provider_groups` | One per provider group | [`provider_groups_schema`](super::arrow::provider_groups_schema) |
+//! | `provider_group_members` | One per TIN and NPI | [`provider_group_members_schema`](super::arrow::provider_group_members_schema) |
+//! | `allowed_amounts` | One per billing provider | [`allowed_amounts_schema`](super::arrow::allowed_amounts_schema) |
+//! 
+//! Provider groups listed inline in a negotiated rate have no id in the source
+//! file, so they are assigned ids starting at
+//! [`INLINE_PROVIDER_GROUP_ID_OFFSET`](super::arrow::INLINE_PROVIDER_GROUP_ID_OFFSET) within each file. Rates reference their
+//! groups through the `provider_group_ids` list column.
+//! 
+//! With partitioning enabled, files are laid out as
+//! `<table>/payer=<name>/month=<YYYY-MM>/billing_code_type=<type>/part-00000.parquet`,
+//! which DuckDB (`hive_partitioning = true`) and Spark discover automatically.
+//! The `billing_code_type` level only applies to `rates` and `allowed_amounts`.
+//! Each export adds new part files next to those already in a partition, so
+//! later months or other payer files can be exported into the same root.
+//! 
+//! Each table keeps at most [`ParquetExportOptions::max_open_files`] part
+//! files open. When another partition needs one, the partition written least
+//! recently is closed, and rows arriving for it later go to a new part file.
+//! Part files still open when an exporter is dropped without
+//! [`finish`](ParquetExporter::finish) have no footer, so they are removed.
+//! 
+//! # Example
+//! 
+//! ```no_run
+//! use mrf_rs::export::parquet::{ParquetExporter, ParquetExportOptions, PartitionKey};
+//! use mrf_rs::parser::MrfParser;
+//! use mrf_rs::types::MrfFile;
+//! 
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let options = ParquetExportOptions {
+//!     partition_by: vec![PartitionKey::Payer, PartitionKey::Month],
+//!     ..Default::default()
+//! };
+//! let mut exporter = ParquetExporter::new("out", options)?;
+//! 
+//! if let MrfFile::InNetwork(file) = MrfParser::parse_file("in-network.json")? {
+//!     exporter.write_in_network_file("in-network.json", &file)?;
+//! }
+//! 
+//! let summary = exporter.finish()?;
+//! println!("Wrote {} rates", summary.rates);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::{Compression, ZstdLevel};
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::schema::types::ColumnPath;
+
+use super::arrow::{
+    AllowedAmountBatchBuilder, BatchBuilder, PlanBatchBuilder, ProviderGroupBatchBuilder,
+    ProviderGroupIds, ProviderGroupMemberBatchBuilder, RateBatchBuilder,
+};
+use super::{enum_str, ExportError, ExportResult};
+use crate::types::{AllowedAmountFile, BillingCodeType, InNetworkFile};
+
+/// Columns holding codes, names and descriptions, which repeat heavily across rows
+const DICTIONARY_COLUMNS: &[&str] = &[
+    "file_id",
+    "file_type",
+    "reporting_entity_name",
+    "reporting_entity_type",
+    "plan_name",
+    "plan_id_type",
+    "plan_id",
+    "plan_market_type",
+    "issuer_name",
+    "plan_sponsor_name",
+    "negotiation_arrangement",
+    "name",
+    "billing_code_type",
+    "billing_code_type_version",
+    "billing_code",
+    "description",
+    "severity_of_illness",
+    "negotiated_type",
+    "expiration_date",
+    "billing_class",
+    "additional_information",
+    "tin_type",
+    "tin_value",
+    "location",
+];
+
+/// Compression codec for Parquet column chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParquetCompression {
+    /// No compression
+    Uncompressed,
+    /// Snappy compression, fast with moderate ratio
+    #[default]
+    Snappy,
+    /// Zstandard compression at the given level (1-22)
+    Zstd(i32),
+}
+
+/// A hive partition level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionKey {
+    /// The reporting entity name (`payer=`)
+    Payer,
+    /// The year and month of `last_updated_on` (`month=`)
+    Month,
+    /// The billing code type of rates and allowed amounts (`billing_code_type=`)
+    BillingCodeType,
+}
+
+/// Options for Parquet export
+#[derive(Debug, Clone)]
+pub struct ParquetExportOptions {
+    /// Maximum number of rows per row group
+    pub row_group_size: usize,
+    
+    /// Compression codec for column chunks
+    pub compression: ParquetCompression,
+    
+    /// Hive partition levels, outermost first. Empty writes unpartitioned tables.
+    pub partition_by: Vec<PartitionKey>,
+    
+    /// Maximum number of part files each table keeps open at once
+    pub max_open_files: usize,
+}
+
+impl Default for ParquetExportOptions {
+    fn default() -> Self {
+        Self {
+            row_group_size: 128 * 1024,
+            compression: ParquetCompression::default(),
+            partition_by: Vec::new(),
+            max_open_files: 64,
+        }
+    }
+}
+
+/// Counts of rows and files written by an export
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// Rows written to the `plans` table
+    pub plans: usize,
+    
+    /// Rows written to the `rates` table
+    pub rates: usize,
+    
+    /// Rows written to the `provider_groups` table
+    pub provider_groups: usize,
+    
+    /// Rows written to the `provider_group_members` table
+    pub provider_group_members: usize,
+    
+    /// Rows written to the `allowed_amounts` table
+    pub allowed_amounts: usize,
+    
+    /// Paths of the Parquet files written
+    pub files: Vec<PathBuf>,
+}
+
+/// Partition values of the file currently being exported
+struct FilePartition {
+    payer: String,
+    month: String,
+}
+
+/// An open Parquet file and the rows buffered for it
+struct PartitionWriter<B> {
+    builder: B,
+    writer: ArrowWriter<File>,
+    path: PathBuf,
+    
+    /// Value of the table's use counter when this partition was last written
+    last_used: u64,
+}
+
+impl<B: BatchBuilder> PartitionWriter<B> {
+    /// Write remaining rows and close the file, returning the rows written
+    /// 
+    /// The file is removed if it cannot be completed.
+    fn close(self) -> ExportResult<(PathBuf, usize)> {
+        let PartitionWriter { mut builder, mut writer, path, .. } = self;
+        let rows = builder.len();
+        let result = (|| -> ExportResult<()> {
+            if rows > 0 {
+                let batch = builder.finish()?;
+                writer.write(&batch)?;
+            }
+            writer.close()?;
+            Ok(())
+        })();
+        
+        match result {
+            Ok(()) => Ok((path, rows)),
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// All partitions of one table
+struct TableWriter<B> {
+    name: &'static str,
+    partitions: HashMap<PathBuf, PartitionWriter<B>>,
+    max_open: usize,
+    uses: u64,
+    rows: usize,
+    
+    /// Part files already closed to make room for other partitions
+    closed: Vec<PathBuf>,
+}
+
+impl<B: BatchBuilder + Default> TableWriter<B> {
+    fn new(name: &'static str, max_open: usize) -> Self {
+        Self {
+            name,
+            partitions: HashMap::new(),
+            max_open,
+            uses: 0,
+            rows: 0,
+            closed: Vec::new(),
+        }
+    }
+    
+    /// Get the builder for a partition, opening its file on first use
+    /// 
+    /// If the table already has `max_open` files open, the least recently
+    /// used partition is closed first.
+    fn builder(
+        &mut self,
+        root: &Path,
+        partition: PathBuf,
+        properties: &WriterProperties,
+    ) -> ExportResult<&mut B> {
+        if !self.partitions.contains_key(&partition) {
+            if self.partitions.len() >= self.max_open {
+                self.close_least_recent()?;
+            }
+            
+            let dir = root.join(self.name).join(&partition);
+            fs::create_dir_all(&dir)?;
+            let builder = B::default();
+            let (path, file) = create_part_file(&dir)?;
+            let writer = match ArrowWriter::try_new(file, builder.schema(), Some(properties.clone())) {
+                Ok(writer) => writer,
+                Err(e) => {
+                    let _ = fs::remove_file(&path);
+                    return Err(e.into());
+                }
+            };
+            self.partitions
+                .insert(partition.clone(), PartitionWriter { builder, writer, path, last_used: 0 });
+        }
+        
+        self.uses += 1;
+        let open = self.partitions.get_mut(&partition).expect("partition was just inserted");
+        open.last_used = self.uses;
+        Ok(&mut open.builder)
+    }
+    
+    /// Close the partition written least recently
+    fn close_least_recent(&mut self) -> ExportResult<()> {
+        let oldest = self
+            .partitions
+            .iter()
+            .min_by_key(|(_, open)| open.last_used)
+            .map(|(partition, _)| partition.clone());
+        
+        if let Some(open) = oldest.and_then(|partition| self.partitions.remove(&partition)) {
+            let (path, rows) = open.close()?;
+            self.rows += rows;
+            self.closed.push(path);
+        }
+        Ok(())
+    }
+    
+    /// Write out any partition whose buffer has reached the row group size
+    fn flush_full(&mut self, row_group_size: usize) -> ExportResult<()> {
+        for partition in self.partitions.values_mut() {
+            if partition.builder.len() >= row_group_size {
+                self.rows += partition.builder.len();
+                let batch = partition.builder.finish()?;
+                partition.writer.write(&batch)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Write remaining rows and close every file
+    fn close(mut self, summary: &mut ExportSummary) -> ExportResult<usize> {
+        summary.files.append(&mut self.closed);
+        
+        let mut partitions: Vec<_> = self.partitions.keys().cloned().collect();
+        partitions.sort();
+        
+        for partition in partitions {
+            let open = self.partitions.remove(&partition).expect("partition is open");
+            let (path, rows) = open.close()?;
+            self.rows += rows;
+            summary.files.push(path);
+        }
+        
+        Ok(self.rows)
+    }
+}
+
+impl<B> Drop for TableWriter<B> {
+    /// Remove part files that were never closed, as they have no footer
+    fn drop(&mut self) {
+        for open in self.partitions.values() {
+            let _ = fs::remove_file(&open.path);
+        }
+    }
+}
+
+/// Create the first unused `part-NNNNN.parquet` file in `dir`
+/// 
+/// Parts written by earlier exports into the same directory are never
+/// overwritten; creation fails instead of reusing a name, so concurrent
+/// exporters also end up with distinct parts.
+fn create_part_file(dir: &Path) -> ExportResult<(PathBuf, File)> {
+    for part in 0u32.. {
+        let path = dir.join(format!("part-{:05}.parquet", part));
+        match File::create_new(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(ExportError::Config(format!("No free part file name in {}", dir.display())))
+}
+
+/// Writes parsed MRF files into a set of Parquet tables
+pub struct ParquetExporter {
+    root: PathBuf,
+    options: ParquetExportOptions,
+    properties: WriterProperties,
+    plans: TableWriter<PlanBatchBuilder>,
+    rates: TableWriter<RateBatchBuilder>,
+    provider_groups: TableWriter<ProviderGroupBatchBuilder>,
+    provider_group_members: TableWriter<ProviderGroupMemberBatchBuilder>,
+    allowed_amounts: TableWriter<AllowedAmountBatchBuilder>,
+}
+
+impl ParquetExporter {
+    /// Create an exporter writing below `output_dir`, creating it if needed
+    pub fn new(output_dir: impl AsRef<Path>, options: ParquetExportOptions) -> ExportResult<Self> {
+        if options.row_group_size == 0 {
+            return Err(ExportError::Config("row_group_size must be greater than zero".to_string()));
+        }
+        if options.max_open_files == 0 {
+            return Err(ExportError::Config("max_open_files must be greater than zero".to_string()));
+        }
+        
+        let compression = match options.compression {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Zstd(level) => Compression::ZSTD(ZstdLevel::try_new(level)?),
+        };
+        
+        let mut properties = WriterProperties::builder()
+            .set_compression(compression)
+            .set_max_row_group_size(options.row_group_size)
+            .set_dictionary_enabled(false);
+        for column in DICTIONARY_COLUMNS {
+            properties = properties.set_column_dictionary_enabled(ColumnPath::from(*column), true);
+        }
+        
+        let root = output_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+        
+        let max_open = options.max_open_files;
+        Ok(Self {
+            root,
+            options,
+            properties: properties.build(),
+            plans: TableWriter::new("plans", max_open),
+            rates: TableWriter::new("rates", max_open),
+            provider_groups: TableWriter::new("provider_groups", max_open),
+            provider_group_members: TableWriter::new("provider_group_members", max_open),
+            allowed_amounts: TableWriter::new("allowed_amounts", max_open),
+        })
+    }
+    
+    /// Relative partition directory for a row
+    fn partition_dir(&self, file: &FilePartition, billing_code_type: Option<&BillingCodeType>) -> PathBuf {
+        let mut dir = PathBuf::new();
+        for key in &self.options.partition_by {
+            match key {
+                PartitionKey::Payer => dir.push(format!("payer={}", escape_partition_value(&file.payer))),
+                PartitionKey::Month => dir.push(format!("month={}", escape_partition_value(&file.month))),
+                PartitionKey::BillingCodeType => {
+                    if let Some(code_type) = billing_code_type {
+                        dir.push(format!("billing_code_type={}", escape_partition_value(&enum_str(code_type))));
+                    }
+                }
+            }
+        }
+        dir
+    }
+    
+    /// Export an in-network file into the `plans`, `rates`, `provider_groups`
+    /// and `provider_group_members` tables
+    pub fn write_in_network_file(&mut self, file_id: &str, file: &InNetworkFile) -> ExportResult<()> {
+        let partition = FilePartition {
+            payer: file.reporting_entity_name.clone(),
+            month: month_of(&file.last_updated_on),
+        };
+        let row_group_size = self.options.row_group_size;
+        
+        let dir = self.partition_dir(&partition, None);
+        self.plans
+            .builder(&self.root, dir.clone(), &self.properties)?
+            .append_in_network(file_id, file);
+        
+        for reference in file.provider_references.iter().flatten() {
+            self.provider_groups
+                .builder(&self.root, dir.clone(), &self.properties)?
+                .append_reference(file_id, reference);
+            for group in reference.provider_groups.iter().flatten() {
+                self.provider_group_members
+                    .builder(&self.root, dir.clone(), &self.properties)?
+                    .append_group(file_id, reference.provider_group_id as i64, group);
+            }
+            self.provider_group_members.flush_full(row_group_size)?;
+        }
+        self.provider_groups.flush_full(row_group_size)?;
+        
+        let mut ids = ProviderGroupIds::new();
+        for rate in &file.in_network {
+            let rate_dir = self.partition_dir(&partition, Some(&rate.billing_code_type));
+            
+            for detail in &rate.negotiated_rates {
+                let group_ids = ids.resolve(detail);
+                let inline_count = detail.provider_groups.as_ref().map_or(0, Vec::len);
+                let inline_ids = &group_ids[group_ids.len() - inline_count..];
+                
+                for (id, group) in inline_ids.iter().zip(detail.provider_groups.iter().flatten()) {
+                    self.provider_groups
+                        .builder(&self.root, dir.clone(), &self.properties)?
+                        .append_inline(file_id, *id);
+                    self.provider_group_members
+                        .builder(&self.root, dir.clone(), &self.properties)?
+                        .append_group(file_id, *id, group);
+                }
+                
+                let builder = self.rates.builder(&self.root, rate_dir.clone(), &self.properties)?;
+                for price in &detail.negotiated_prices {
+                    builder.append(file_id, rate, price, &group_ids);
+                }
+            }
+            
+            self.rates.flush_full(row_group_size)?;
+            self.provider_groups.flush_full(row_group_size)?;
+            self.provider_group_members.flush_full(row_group_size)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Export an allowed amount file into the `plans` and `allowed_amounts` tables
+    pub fn write_allowed_amount_file(&mut self, file_id: &str, file: &AllowedAmountFile) -> ExportResult<()> {
+        let partition = FilePartition {
+            payer: file.reporting_entity_name.clone(),
+            month: month_of(&file.last_updated_on),
+        };
+        let row_group_size = self.options.row_group_size;
+        
+        let dir = self.partition_dir(&partition, None);
+        self.plans
+            .builder(&self.root, dir, &self.properties)?
+            .append_allowed_amount(file_id, file);
+        
+        for item in &file.out_of_network {
+            let item_dir = self.partition_dir(&partition, Some(&item.billing_code_type));
+            let builder = self.allowed_amounts.builder(&self.root, item_dir, &self.properties)?;
+            
+            for allowed in &item.allowed_amounts {
+                for payment in &allowed.payments {
+                    for provider in &payment.providers {
+                        builder.append(file_id, item, allowed, payment, provider);
+                    }
+                }
+            }
+            
+            self.allowed_amounts.flush_full(row_group_size)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Flush buffered rows, close every Parquet file and report what was written
+    pub fn finish(self) -> ExportResult<ExportSummary> {
+        let mut summary = ExportSummary::default();
+        summary.plans = self.plans.close(&mut summary)?;
+        summary.rates = self.rates.close(&mut summary)?;
+        summary.provider_groups = self.provider_groups.close(&mut summary)?;
+        summary.provider_group_members = self.provider_group_members.close(&mut summary)?;
+        summary.allowed_amounts = self.allowed_amounts.close(&mut summary)?;
+        Ok(summary)
+    }
+}
+
+/// The `YYYY-MM` prefix of a date, or "unknown" if it has no such prefix
+fn month_of(date: &str) -> String {
+    let month = date.get(..7).unwrap_or_default();
+    let valid = month.len() == 7
+        && month.as_bytes()[4] == b'-'
+        && month.bytes().enumerate().all(|(i, b)| i == 4 || b.is_ascii_digit());
+    
+    if valid {
+        month.to_string()
+    } else {
+        "unknown".to_string()
+    }
+}
+
+/// Percent-encode characters that are unsafe in hive partition directory names
+fn escape_partition_value(value: &str) -> String {
+    if value.is_empty() {
+        return "__HIVE_DEFAULT_PARTITION__".to_string();
+    }
+    
+    let mut escaped = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b' ') {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::arrow::array::{Array, Float64Array, StringArray};
+    use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    
+    fn sample_in_network() -> InNetworkFile {
+        serde_json::from_str(
+            r#"{
+                "reporting_entity_name": "Acme Health/West",
+                "reporting_entity_type": "health insurance issuer",
+                "last_updated_on": "2024-03-01",
+                "version": "1.0.0",
+                "provider_references": [{
+                    "provider_group_id": 1,
+                    "provider_groups": [{"npi": [1111111111, 2222222222], "tin": {"type": "ein", "value": "11-1111111"}}]
+                }],
+                "in_network": [{
+                    "negotiation_arrangement": "ffs",
+                    "name": "Office visit",
+                    "billing_code_type": "CPT",
+                    "billing_code_type_version": "2024",
+                    "billing_code": "99213",
+                    "description": "Office visit",
+                    "negotiated_rates": [{
+                        "provider_references": [1],
+                        "provider_groups": [{"npi": [3333333333], "tin": {"type": "npi", "value": "3333333333"}}],
+                        "negotiated_prices": [
+                            {"negotiated_type": "negotiated", "negotiated_rate": 95.5, "expiration_date": "9999-12-31", "billing_class": "professional"},
+                            {"negotiated_type": "negotiated", "negotiated_rate": 120.0, "expiration_date": "9999-12-31", "billing_class": "institutional"}
+                        ]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap()
+    }
+    
+    fn read_batches(path: &Path) -> Vec<::arrow::record_batch::RecordBatch> {
+        let file = File::open(path).unwrap();
+        ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+    
+    #[test]
+    fn test_export_in_network_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut exporter = ParquetExporter::new(dir.path(), ParquetExportOptions::default()).unwrap();
+        exporter.write_in_network_file("file-1", &sample_in_network()).unwrap();
+        let summary = exporter.finish().unwrap();
+        
+        assert_eq!(summary.plans, 1);
+        assert_eq!(summary.rates, 2);
+        assert_eq!(summary.provider_groups, 2);
+        assert_eq!(summary.provider_group_members, 3);
+        assert_eq!(summary.allowed_amounts, 0);
+        assert_eq!(summary.files.len(), 4);
+        
+        let batches = read_batches(&dir.path().join("rates/part-00000.parquet"));
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let codes = batch.column_by_name("billing_code").unwrap();
+        let codes = codes.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(codes.value(0), "99213");
+        let rates = batch.column_by_name("negotiated_rate").unwrap();
+        let rates = rates.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(rates.value(1), 120.0);
+        let types = batch.column_by_name("billing_code_type").unwrap();
+        assert!(!types.is_null(0));
+    }
+    
+    #[test]
+    fn test_export_hive_partitioning() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ParquetExportOptions {
+            row_group_size: 1,
+            partition_by: vec![PartitionKey::Payer, PartitionKey::Month, PartitionKey::BillingCodeType],
+            ..Default::default()
+        };
+        let mut exporter = ParquetExporter::new(dir.path(), options).unwrap();
+        exporter.write_in_network_file("file-1", &sample_in_network()).unwrap();
+        exporter.finish().unwrap();
+        
+        let rates = dir
+            .path()
+            .join("rates/payer=Acme Health%2FWest/month=2024-03/billing_code_type=CPT/part-00000.parquet");
+        let batches = read_batches(&rates);
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+        
+        let plans = dir.path().join("plans/payer=Acme Health%2FWest/month=2024-03/part-00000.parquet");
+        assert!(plans.exists());
+    }
+    
+    #[test]
+    fn test_exports_into_same_root_keep_earlier_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut files = Vec::new();
+        for _ in 0..2 {
+            let mut exporter = ParquetExporter::new(dir.path(), ParquetExportOptions::default()).unwrap();
+            exporter.write_in_network_file("file-1", &sample_in_network()).unwrap();
+            files.extend(exporter.finish().unwrap().files);
+        }
+        
+        assert_eq!(files.len(), 8);
+        assert!(files.contains(&dir.path().join("rates/part-00000.parquet")));
+        assert!(files.contains(&dir.path().join("rates/part-00001.parquet")));
+        for path in &files {
+            assert_eq!(files.iter().filter(|other| *other == path).count(), 1);
+        }
+        
+        let rows: usize = ["part-00000.parquet", "part-00001.parquet"]
+            .iter()
+            .flat_map(|part| read_batches(&dir.path().join("rates").join(part)))
+            .map(|batch| batch.num_rows())
+            .sum();
+        assert_eq!(rows, 4);
+    }
+    
+    #[test]
+    fn test_open_files_capped_per_table() {
+        let mut file = sample_in_network();
+        let mut hcpcs = file.in_network[0].clone();
+        hcpcs.billing_code_type = BillingCodeType::HCPCS;
+        file.in_network = vec![file.in_network[0].clone(), hcpcs.clone(), file.in_network[0].clone(), hcpcs];
+        
+        let dir = tempfile::tempdir().unwrap();
+        let options = ParquetExportOptions {
+            partition_by: vec![PartitionKey::BillingCodeType],
+            max_open_files: 1,
+            ..Default::default()
+        };
+        let mut exporter = ParquetExporter::new(dir.path(), options).unwrap();
+        exporter.write_in_network_file("file-1", &file).unwrap();
+        assert_eq!(exporter.rates.partitions.len(), 1);
+        let summary = exporter.finish().unwrap();
+        
+        assert_eq!(summary.rates, 8);
+        for code_type in ["CPT", "HCPCS"] {
+            let partition = dir.path().join(format!("rates/billing_code_type={}", code_type));
+            let rows: usize = fs::read_dir(&partition)
+                .unwrap()
+                .flat_map(|entry| read_batches(&entry.unwrap().path()))
+                .map(|batch| batch.num_rows())
+                .sum();
+            assert_eq!(rows, 4);
+            assert_eq!(fs::read_dir(&partition).unwrap().count(), 2);
+        }
+    }
+    
+    #[test]
+    fn test_drop_without_finish_removes_open_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut exporter = ParquetExporter::new(dir.path(), ParquetExportOptions::default()).unwrap();
+        exporter.write_in_network_file("file-1", &sample_in_network()).unwrap();
+        assert!(dir.path().join("rates/part-00000.parquet").exists());
+        drop(exporter);
+        
+        for table in ["plans", "rates", "provider_groups", "provider_group_members"] {
+            assert_eq!(fs::read_dir(dir.path().join(table)).unwrap().count(), 0);
+        }
+    }
+    
+    #[test]
+    fn test_month_of() {
+        assert_eq!(month_of("2024-03-01"), "2024-03");
+        assert_eq!(month_of("March 2024"), "unknown");
+        assert_eq!(month_of(""), "unknown");
+    }
+    
+    #[test]
+    fn test_zero_row_group_size_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ParquetExportOptions {
+            row_group_size: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ParquetExporter::new(dir.path(), options),
+            Err(ExportError::Config(_))
+        ));
+    }
+}
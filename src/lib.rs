@@ -1,3 +1,5 @@
 pub mod types;
 pub mod parser;
+pub mod serializer;
 pub mod sources;
+pub mod export;
@@ -9,6 +9,9 @@
 //! - Allowed Amount files
 //! - Provider Reference files
 //! 
+//! Hospital standard charge files published under the CMS hospital price
+//! transparency rules are also supported, in both the JSON and CSV templates.
+//! 
 //! # Features
 //! 
 //! - Type-safe parsing with automatic file type detection
@@ -51,6 +54,11 @@ use serde::de::DeserializeOwned;
 use serde_json;
 
 use crate::types::{MrfFile, TableOfContentsFile, InNetworkFile, AllowedAmountFile, ProviderReferenceFile};
+use crate::types::hospital::HospitalStandardChargeFile;
+use crate::types::{SchemaVersion, UpgradeReport};
+
+mod hospital;
+mod versioning;
 
 /// Error type for parsing operations
 /// 
@@ -83,6 +91,21 @@ pub enum ParseError {
     /// that was attempted.
     #[error("File not found: {0}")]
     FileNotFound(String),
+    
+    /// CSV parsing error occurred
+    /// 
+    /// This error indicates that a CSV file (such as a hospital standard charge
+    /// file) could not be read as CSV.
+    #[error("CSV parsing error: {0}")]
+    Csv(#[from] csv::Error),
+    
+    /// The file content does not follow the expected template
+    /// 
+    /// This error is returned when the data is well-formed but required
+    /// rows, columns or values are missing or invalid. The error message
+    /// describes the problem and, where possible, the row it occurred on.
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
 }
 
 /// Result type alias for parsing operations
@@ -488,6 +511,193 @@ impl MrfParser {
         Ok(provider_ref_file)
     }
     
+    /// Parse any MRF file type from a file path, upgrading it to the latest schema
+    /// 
+    /// Unlike `parse_file`, this method inspects the file's declared `version`
+    /// and returns an `UpgradeReport` describing how the file maps onto the
+    /// latest in-crate model: which newer fields were defaulted and which
+    /// fields of the file are not represented. Files of every version are
+    /// deserialized into the same latest model.
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// use mrf_rs::parser::MrfParser;
+    /// 
+    /// let (file_2022, report_2022) = MrfParser::parse_versioned_file("2022-07-01_in-network.json")?;
+    /// let (file_2025, report_2025) = MrfParser::parse_versioned_file("2025-06-01_in-network.json")?;
+    /// 
+    /// println!("2022 file defaulted: {:?}", report_2022.defaulted_fields);
+    /// println!("2025 file lost: {:?}", report_2025.lost_fields);
+    /// # Ok::<(), mrf_rs::parser::ParseError>(())
+    /// ```
+    /// 
+    /// # Memory Usage
+    /// 
+    /// The whole document is held as a `serde_json::Value` while it is inspected,
+    /// which needs considerably more memory than `parse_file`.
+    pub fn parse_versioned_file<P: AsRef<Path>>(path: P) -> ParseResult<(MrfFile, UpgradeReport)> {
+        let path = path.as_ref();
+        
+        if !path.exists() {
+            return Err(ParseError::FileNotFound(
+                path.to_string_lossy().to_string()
+            ));
+        }
+        
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        
+        Self::parse_versioned_reader(reader)
+    }
+    
+    /// Parse any MRF file type from a reader, upgrading it to the latest schema
+    /// 
+    /// Reader-based version of `parse_versioned_file`.
+    pub fn parse_versioned_reader<R: Read>(reader: R) -> ParseResult<(MrfFile, UpgradeReport)> {
+        let value = serde_json::from_reader(reader)?;
+        Self::upgrade(value)
+    }
+    
+    /// Convert an already-parsed JSON document into the latest in-crate model
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use mrf_rs::parser::MrfParser;
+    /// use mrf_rs::types::SchemaVersion;
+    /// 
+    /// let value = serde_json::json!({
+    ///     "reporting_entity_name": "Example Corp",
+    ///     "reporting_entity_type": "health insurance issuer",
+    ///     "in_network": [],
+    ///     "last_updated_on": "2022-07-01",
+    ///     "version": "1.0.0"
+    /// });
+    /// 
+    /// let (_file, report) = MrfParser::upgrade(value)?;
+    /// assert_eq!(report.source_version, Some(SchemaVersion::V1_0_0));
+    /// assert!(report.defaulted_fields.contains(&"issuer_name".to_string()));
+    /// # Ok::<(), mrf_rs::parser::ParseError>(())
+    /// ```
+    pub fn upgrade(value: serde_json::Value) -> ParseResult<(MrfFile, UpgradeReport)> {
+        versioning::upgrade(value)
+    }
+    
+    /// Detect the schema version declared by a JSON document
+    /// 
+    /// Returns `None` if the document has no `version` field or the value
+    /// is not a dotted numeric version.
+    pub fn detect_version(value: &serde_json::Value) -> Option<SchemaVersion> {
+        versioning::detect_version(value)
+    }
+    
+    /// Parse a hospital standard charge file in any supported template
+    /// 
+    /// Files with a `.csv` extension are parsed with the CSV "tall" or "wide"
+    /// template; all other files are parsed with the JSON template.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Path to the hospital standard charge file
+    /// 
+    /// # Examples
+    /// 
+    /// ```no_run
+    /// use mrf_rs::parser::MrfParser;
+    /// 
+    /// let hospital = MrfParser::parse_hospital_file("123456789_west-mercy_standardcharges.csv")?;
+    /// 
+    /// for charge in hospital.negotiated_charges() {
+    ///     println!("{} {}: {}", charge.payer_name, charge.billing_code, charge.negotiated_rate);
+    /// }
+    /// # Ok::<(), mrf_rs::parser::ParseError>(())
+    /// ```
+    pub fn parse_hospital_file<P: AsRef<Path>>(path: P) -> ParseResult<HospitalStandardChargeFile> {
+        let path = path.as_ref();
+        let is_csv = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("csv"))
+            .unwrap_or(false);
+        
+        if is_csv {
+            Self::parse_hospital_csv_file(path)
+        } else {
+            Self::parse_hospital_json_file(path)
+        }
+    }
+    
+    /// Parse a hospital standard charge file using the JSON template
+    /// 
+    /// Hospital files are published under the CMS hospital price transparency
+    /// rules rather than Transparency in Coverage, so they are not detected by
+    /// `parse_file`.
+    pub fn parse_hospital_json_file<P: AsRef<Path>>(path: P) -> ParseResult<HospitalStandardChargeFile> {
+        let path = path.as_ref();
+        
+        if !path.exists() {
+            return Err(ParseError::FileNotFound(
+                path.to_string_lossy().to_string()
+            ));
+        }
+        
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        
+        Self::parse_hospital_json_reader(reader)
+    }
+    
+    /// Parse a hospital standard charge JSON file from a reader
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use std::io::Cursor;
+    /// use mrf_rs::parser::MrfParser;
+    /// 
+    /// let json = r#"{
+    ///     "hospital_name": "West Mercy Hospital",
+    ///     "last_updated_on": "2024-07-01",
+    ///     "version": "2.0.0",
+    ///     "standard_charge_information": []
+    /// }"#;
+    /// 
+    /// let hospital = MrfParser::parse_hospital_json_reader(Cursor::new(json))?;
+    /// assert_eq!(hospital.hospital_name, "West Mercy Hospital");
+    /// # Ok::<(), mrf_rs::parser::ParseError>(())
+    /// ```
+    pub fn parse_hospital_json_reader<R: Read>(reader: R) -> ParseResult<HospitalStandardChargeFile> {
+        let hospital_file = serde_json::from_reader(reader)?;
+        Ok(hospital_file)
+    }
+    
+    /// Parse a hospital standard charge file using the CSV templates
+    /// 
+    /// Both the "tall" template (one row per payer and plan) and the "wide"
+    /// template (one column group per payer and plan) are supported. Rows that
+    /// describe the same item are grouped into the nested JSON shape.
+    pub fn parse_hospital_csv_file<P: AsRef<Path>>(path: P) -> ParseResult<HospitalStandardChargeFile> {
+        let path = path.as_ref();
+        
+        if !path.exists() {
+            return Err(ParseError::FileNotFound(
+                path.to_string_lossy().to_string()
+            ));
+        }
+        
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        
+        Self::parse_hospital_csv_reader(reader)
+    }
+    
+    /// Parse a hospital standard charge CSV file from a reader
+    /// 
+    /// The template variant is detected from the column headers.
+    pub fn parse_hospital_csv_reader<R: Read>(reader: R) -> ParseResult<HospitalStandardChargeFile> {
+        hospital::parse_csv(reader)
+    }
+    
     /// Generic parser for any type that implements DeserializeOwned
     /// 
     /// This method provides flexibility to parse specific parts of MRF files
@@ -639,6 +849,216 @@ mod tests {
         assert!(result.is_err());
     }
     
+    const HOSPITAL_CSV_GENERAL: &str = "hospital_name,last_updated_on,version,hospital_location,hospital_address,license_number|CA,\"To the best of its knowledge and belief, the hospital has included all applicable standard charge information in accordance with the requirements of 45 CFR 180.50, and the information encoded is true, accurate, and complete as of the date indicated.\"
+West Mercy Hospital,2024-07-01,2.0.0,West Mercy Hospital|West Mercy Surgical Center,\"12 Main Street, Fullerton, CA 92832|23 Ocean Ave, San Jose, CA 94088\",50056,true
+";
+    
+    #[test]
+    fn test_parse_hospital_json() {
+        let json = r#"{
+            "hospital_name": "West Mercy Hospital",
+            "last_updated_on": "2024-07-01",
+            "version": "2.0.0",
+            "hospital_location": ["West Mercy Hospital"],
+            "hospital_address": ["12 Main Street, Fullerton, CA 92832"],
+            "license_information": {"license_number": "50056", "state": "CA"},
+            "affirmation": {"affirmation": "To the best of its knowledge...", "confirm_affirmation": true},
+            "standard_charge_information": [{
+                "description": "Major hip and knee joint replacement",
+                "code_information": [
+                    {"code": "470", "type": "MS-DRG"},
+                    {"code": "175869", "type": "LOCAL"}
+                ],
+                "standard_charges": [{
+                    "setting": "inpatient",
+                    "gross_charge": 55000,
+                    "discounted_cash": 45000,
+                    "minimum": 20000,
+                    "maximum": 25000,
+                    "payers_information": [
+                        {"payer_name": "Platform Health Insurance", "plan_name": "PPO", "standard_charge_dollar": 20000, "methodology": "case rate"},
+                        {"payer_name": "Region Health Insurance", "plan_name": "HMO", "standard_charge_percentage": 50, "estimated_amount": 25000, "methodology": "percent of total billed charges"},
+                        {"payer_name": "Region Health Insurance", "plan_name": "EPO", "standard_charge_algorithm": "See contract", "methodology": "other"}
+                    ]
+                }]
+            }]
+        }"#;
+        
+        let file = MrfParser::parse_hospital_json_reader(std::io::Cursor::new(json)).unwrap();
+        assert_eq!(file.hospital_name, "West Mercy Hospital");
+        assert_eq!(file.license_information.as_ref().unwrap().state, "CA");
+        
+        let charges = file.negotiated_charges();
+        // Two codes times two payers with a rate; the algorithm-only payer is skipped
+        assert_eq!(charges.len(), 4);
+        assert_eq!(charges[0].billing_code_type, BillingCodeType::MSDRG);
+        assert_eq!(charges[0].billing_code, "470");
+        assert_eq!(charges[0].negotiated_type, NegotiatedType::Negotiated);
+        assert_eq!(charges[0].negotiated_rate, 20000.0);
+        assert_eq!(charges[1].billing_code_type, BillingCodeType::LOCAL);
+        assert_eq!(charges[2].negotiated_type, NegotiatedType::Percentage);
+        assert_eq!(charges[2].negotiated_rate, 50.0);
+        assert_eq!(charges[2].estimated_amount, Some(25000.0));
+    }
+    
+    #[test]
+    fn test_parse_hospital_csv_tall() {
+        let csv = format!("{}{}", HOSPITAL_CSV_GENERAL, "description,code|1,code|1|type,code|2,code|2|type,modifiers,setting,drug_unit_of_measurement,drug_type_of_measurement,standard_charge|gross,standard_charge|discounted_cash,payer_name,plan_name,standard_charge|negotiated_dollar,standard_charge|negotiated_percentage,standard_charge|negotiated_algorithm,estimated_amount,standard_charge|methodology,standard_charge|min,standard_charge|max,additional_generic_notes
+Major hip and knee joint replacement,470,MS-DRG,175869,LOCAL,,inpatient,,,55000,45000,Platform Health Insurance,PPO,20000,,,,case rate,20000,25000,
+Major hip and knee joint replacement,470,MS-DRG,175869,LOCAL,,inpatient,,,55000,45000,Region Health Insurance,HMO,,50,,25000,percent of total billed charges,20000,25000,
+Behavioral health; residential,1001,RC,,,,inpatient,,,1500,1300,Platform Health Insurance,PPO,1000,,,,per diem,1000,1000,
+");
+        
+        let file = MrfParser::parse_hospital_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(file.hospital_name, "West Mercy Hospital");
+        assert_eq!(file.hospital_location.len(), 2);
+        assert_eq!(file.hospital_address[0], "12 Main Street, Fullerton, CA 92832");
+        assert_eq!(file.license_information.as_ref().unwrap().license_number.as_deref(), Some("50056"));
+        assert!(file.affirmation.as_ref().unwrap().confirm_affirmation);
+        
+        // Rows for the same item are grouped into one standard charge with two payers
+        assert_eq!(file.standard_charge_information.len(), 2);
+        let hip = &file.standard_charge_information[0];
+        assert_eq!(hip.code_information.len(), 2);
+        assert_eq!(hip.standard_charges.len(), 1);
+        assert_eq!(hip.standard_charges[0].payers_information.as_ref().unwrap().len(), 2);
+        
+        let charges = file.negotiated_charges();
+        assert_eq!(charges.len(), 5);
+        let per_diem = charges.iter().find(|c| c.billing_code == "1001").unwrap();
+        assert_eq!(per_diem.billing_code_type, BillingCodeType::RC);
+        assert_eq!(per_diem.negotiated_type, NegotiatedType::PerDiem);
+    }
+    
+    #[test]
+    fn test_parse_hospital_csv_wide() {
+        let csv = format!("{}{}", HOSPITAL_CSV_GENERAL, "description,code|1,code|1|type,setting,standard_charge|gross,standard_charge|discounted_cash,standard_charge|Platform Health Insurance|PPO|negotiated_dollar,standard_charge|Platform Health Insurance|PPO|negotiated_percentage,standard_charge|Platform Health Insurance|PPO|negotiated_algorithm,estimated_amount|Platform Health Insurance|PPO,standard_charge|Platform Health Insurance|PPO|methodology,standard_charge|Region Health Insurance|HMO|negotiated_dollar,standard_charge|Region Health Insurance|HMO|negotiated_percentage,standard_charge|Region Health Insurance|HMO|negotiated_algorithm,estimated_amount|Region Health Insurance|HMO,standard_charge|Region Health Insurance|HMO|methodology,standard_charge|min,standard_charge|max,additional_generic_notes
+Office visit,99213,CPT,outpatient,$150.00,120,95,,,,fee schedule,,,,,,95,95,
+Office visit,99214,CPT,outpatient,200,160,130,,,,fee schedule,,60,,140,percent of total billed charges,130,140,
+");
+        
+        let file = MrfParser::parse_hospital_csv_reader(csv.as_bytes()).unwrap();
+        assert_eq!(file.standard_charge_information.len(), 2);
+        assert_eq!(file.standard_charge_information[0].standard_charges[0].gross_charge, Some(150.0));
+        
+        let charges = file.negotiated_charges();
+        assert_eq!(charges.len(), 3);
+        assert_eq!(charges[0].payer_name, "Platform Health Insurance");
+        assert_eq!(charges[0].plan_name, "PPO");
+        assert_eq!(charges[0].negotiated_type, NegotiatedType::FeeSchedule);
+        assert_eq!(charges[2].payer_name, "Region Health Insurance");
+        assert_eq!(charges[2].negotiated_type, NegotiatedType::Percentage);
+        assert_eq!(charges[2].estimated_amount, Some(140.0));
+    }
+    
+    #[test]
+    fn test_parse_hospital_csv_invalid_setting() {
+        let csv = format!("{}{}", HOSPITAL_CSV_GENERAL, "description,code|1,code|1|type,setting,standard_charge|gross
+Office visit,99213,CPT,clinic,150
+");
+        
+        let result = MrfParser::parse_hospital_csv_reader(csv.as_bytes());
+        assert!(matches!(result, Err(ParseError::InvalidFormat(_))));
+    }
+    
+    #[test]
+    fn test_schema_version_parse() {
+        assert_eq!(SchemaVersion::parse("1.0.0"), Some(SchemaVersion::V1_0_0));
+        assert_eq!(SchemaVersion::parse("v2.0"), Some(SchemaVersion::V2_0_0));
+        assert_eq!(SchemaVersion::parse("1.4"), Some(SchemaVersion::new(1, 4, 0)));
+        assert_eq!(SchemaVersion::parse("latest"), None);
+        assert_eq!(SchemaVersion::parse("1.0.0.0"), None);
+        assert!(SchemaVersion::new(1, 4, 0) < SchemaVersion::V2_0_0);
+    }
+    
+    #[test]
+    fn test_upgrade_v1_in_network() {
+        let value = serde_json::json!({
+            "reporting_entity_name": "Test Entity",
+            "reporting_entity_type": "health insurance issuer",
+            "in_network": [{
+                "negotiation_arrangement": "ffs",
+                "name": "Knee replacement",
+                "billing_code_type": "CPT",
+                "billing_code_type_version": "2022",
+                "billing_code": "27447",
+                "description": "Knee replacement",
+                "negotiated_rates": [],
+                "custom_rank": 1
+            }],
+            "last_updated_on": "2022-07-01",
+            "version": "1.0.0"
+        });
+        
+        let (file, report) = MrfParser::upgrade(value).unwrap();
+        assert!(matches!(file, MrfFile::InNetwork(_)));
+        assert_eq!(report.source_version, Some(SchemaVersion::V1_0_0));
+        assert_eq!(report.target_version, SchemaVersion::LATEST);
+        assert_eq!(report.lost_fields, vec!["in_network[].custom_rank".to_string()]);
+        assert!(report.defaulted_fields.contains(&"issuer_name".to_string()));
+        assert!(report.defaulted_fields.contains(&"in_network[].severity_of_illness".to_string()));
+        // No provider references in the file, so their new fields are not reported
+        assert!(!report.defaulted_fields.iter().any(|f| f.starts_with("provider_references")));
+        // Table of contents fields never apply to an in-network file
+        assert!(!report.defaulted_fields.iter().any(|f| f.starts_with("reporting_structure")));
+    }
+    
+    #[test]
+    fn test_upgrade_v2_in_network() {
+        let json = r#"{
+            "reporting_entity_name": "Test Entity",
+            "reporting_entity_type": "health insurance issuer",
+            "issuer_name": "Test Issuer",
+            "plan_sponsor_name": "Test Sponsor",
+            "in_network": [],
+            "provider_references": [{
+                "provider_group_id": 1,
+                "network_name": ["PPO"],
+                "provider_groups": [{"npi": [1234567890], "tin": {"type": "ein", "value": "11-1111111"}}]
+            }],
+            "last_updated_on": "2025-06-01",
+            "version": "2.0.0"
+        }"#;
+        
+        let (file, report) = MrfParser::parse_versioned_reader(json.as_bytes()).unwrap();
+        assert!(report.defaulted_fields.is_empty());
+        assert!(report.is_lossless());
+        
+        match file {
+            MrfFile::InNetwork(file) => {
+                assert_eq!(file.issuer_name.as_deref(), Some("Test Issuer"));
+                let references = file.provider_references.unwrap();
+                assert_eq!(references[0].network_name, Some(vec!["PPO".to_string()]));
+            }
+            _ => panic!("Expected InNetwork file type"),
+        }
+    }
+    
+    #[test]
+    fn test_upgrade_without_version() {
+        let value = serde_json::json!({
+            "reporting_entity_name": "Test Entity",
+            "reporting_entity_type": "Third-Party Administrator",
+            "reporting_structure": [{
+                "reporting_plans": [{
+                    "plan_name": "Plan",
+                    "plan_id_type": "EIN",
+                    "plan_id": "123456789",
+                    "plan_market_type": "group"
+                }],
+                "in_network_files": []
+            }]
+        });
+        
+        assert_eq!(MrfParser::detect_version(&value), None);
+        
+        let (_, report) = MrfParser::upgrade(value).unwrap();
+        assert_eq!(report.source_version, None);
+        assert!(report.defaulted_fields.contains(&"last_updated_on".to_string()));
+        assert!(report.defaulted_fields.contains(&"reporting_structure[].reporting_plans[].issuer_name".to_string()));
+        assert!(!report.defaulted_fields.contains(&"issuer_name".to_string()));
+    }
+    
     #[test]
     fn test_parse_from_reader() {
         let json = r#"{
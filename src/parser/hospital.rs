@@ -0,0 +1,379 @@
+//! Conversion of CMS hospital standard charge CSV templates
+//! 
+//! Both CSV templates start with two rows of general hospital information followed
+//! by a row of column headers. The "tall" template has one row per payer and plan,
+//! with `payer_name` and `plan_name` columns. The "wide" template has one row per
+//! item and encodes the payer and plan in the column headers
+//! (e.g. `standard_charge|Platform Health|PPO|negotiated_dollar`).
+//! 
+//! Rows describing the same item are grouped back into the nested shape of the
+//! JSON template so that both formats produce a [`HospitalStandardChargeFile`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use serde::de::DeserializeOwned;
+
+use super::{ParseError, ParseResult};
+use crate::types::hospital::{
+    Affirmation, CodeInformation, DrugInformation, HospitalStandardChargeFile,
+    LicenseInformation, PayerInformation, StandardCharge, StandardChargeInformation,
+};
+
+/// Column positions for one payer and plan in the wide template
+#[derive(Debug, Default)]
+struct WidePayerColumns {
+    payer_name: String,
+    plan_name: String,
+    dollar: Option<usize>,
+    percentage: Option<usize>,
+    algorithm: Option<usize>,
+    estimated_amount: Option<usize>,
+    methodology: Option<usize>,
+    notes: Option<usize>,
+}
+
+/// Column layout resolved from the header row
+#[derive(Debug, Default)]
+struct ColumnLayout {
+    named: HashMap<String, usize>,
+    codes: Vec<(usize, usize)>,
+    wide_payers: Vec<WidePayerColumns>,
+}
+
+impl ColumnLayout {
+    fn from_headers(headers: &csv::StringRecord) -> Self {
+        let mut layout = ColumnLayout::default();
+        let mut code_columns: BTreeMap<(usize, String), (Option<usize>, Option<usize>)> = BTreeMap::new();
+        let mut payer_index: HashMap<(String, String), usize> = HashMap::new();
+        
+        for (idx, header) in headers.iter().enumerate() {
+            let parts: Vec<&str> = header.split('|').map(str::trim).collect();
+            let key = parts[0].to_lowercase();
+            
+            match (key.as_str(), parts.len()) {
+                ("code", 2) => code_columns.entry(natural_key(parts[1])).or_default().0 = Some(idx),
+                ("code", 3) => code_columns.entry(natural_key(parts[1])).or_default().1 = Some(idx),
+                ("standard_charge", 4) | ("estimated_amount", 3) | ("additional_payer_notes", 3) => {
+                    let payer = (parts[1].to_string(), parts[2].to_string());
+                    let position = *payer_index.entry(payer.clone()).or_insert_with(|| {
+                        layout.wide_payers.push(WidePayerColumns {
+                            payer_name: payer.0.clone(),
+                            plan_name: payer.1.clone(),
+                            ..Default::default()
+                        });
+                        layout.wide_payers.len() - 1
+                    });
+                    let columns = &mut layout.wide_payers[position];
+                    
+                    match (key.as_str(), parts.get(3).map(|s| s.to_lowercase())) {
+                        ("estimated_amount", _) => columns.estimated_amount = Some(idx),
+                        ("additional_payer_notes", _) => columns.notes = Some(idx),
+                        (_, Some(field)) if field == "negotiated_dollar" => columns.dollar = Some(idx),
+                        (_, Some(field)) if field == "negotiated_percentage" => columns.percentage = Some(idx),
+                        (_, Some(field)) if field == "negotiated_algorithm" => columns.algorithm = Some(idx),
+                        (_, Some(field)) if field == "methodology" => columns.methodology = Some(idx),
+                        _ => {}
+                    }
+                }
+                _ => {
+                    layout.named.insert(header.trim().to_lowercase(), idx);
+                }
+            }
+        }
+        
+        layout.codes = code_columns
+            .into_values()
+            .filter_map(|(code, code_type)| Some((code?, code_type?)))
+            .collect();
+        
+        layout
+    }
+    
+    fn is_tall(&self) -> bool {
+        self.named.contains_key("payer_name")
+    }
+    
+    fn get<'a>(&self, record: &'a csv::StringRecord, name: &str) -> Option<&'a str> {
+        self.named
+            .get(name)
+            .and_then(|idx| record.get(*idx))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Sort numbered columns such as `code|2` before `code|10`
+fn natural_key(value: &str) -> (usize, String) {
+    (value.parse().unwrap_or(usize::MAX), value.to_string())
+}
+
+/// Deserialize a CSV cell into one of the serde-backed enums
+fn parse_enum<T: DeserializeOwned>(value: &str, field: &str, row: usize) -> ParseResult<T> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).map_err(|_| {
+        ParseError::InvalidFormat(format!("Invalid {} '{}' on row {}", field, value, row))
+    })
+}
+
+/// Parse a monetary or percentage cell, tolerating currency symbols and separators
+fn parse_amount(value: Option<&str>, field: &str, row: usize) -> ParseResult<Option<f64>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    
+    let cleaned: String = value.chars().filter(|c| !matches!(c, '$' | ',' | '%')).collect();
+    cleaned.trim().parse::<f64>().map(Some).map_err(|_| {
+        ParseError::InvalidFormat(format!("Invalid {} '{}' on row {}", field, value, row))
+    })
+}
+
+fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .map(|v| v.split('|').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Build the file header from the two general information rows
+fn parse_general_information(
+    labels: &csv::StringRecord,
+    values: &csv::StringRecord,
+) -> ParseResult<HospitalStandardChargeFile> {
+    let mut file = HospitalStandardChargeFile {
+        hospital_name: String::new(),
+        last_updated_on: String::new(),
+        version: String::new(),
+        hospital_location: Vec::new(),
+        hospital_address: Vec::new(),
+        license_information: None,
+        affirmation: None,
+        modifier_information: None,
+        standard_charge_information: Vec::new(),
+    };
+    
+    for (label, value) in labels.iter().zip(values.iter()) {
+        let value = value.trim();
+        let key = label.trim().to_lowercase();
+        
+        if key == "hospital_name" {
+            file.hospital_name = value.to_string();
+        } else if key == "last_updated_on" {
+            file.last_updated_on = value.to_string();
+        } else if key == "version" {
+            file.version = value.to_string();
+        } else if key == "hospital_location" {
+            file.hospital_location = split_list(Some(value));
+        } else if key == "hospital_address" {
+            file.hospital_address = split_list(Some(value));
+        } else if let Some(state) = key.strip_prefix("license_number|") {
+            file.license_information = Some(LicenseInformation {
+                license_number: Some(value.to_string()).filter(|v| !v.is_empty()),
+                state: state.trim().to_uppercase(),
+            });
+        } else if key.starts_with("to the best of its knowledge") {
+            file.affirmation = Some(Affirmation {
+                affirmation: label.trim().to_string(),
+                confirm_affirmation: matches!(value.to_lowercase().as_str(), "true" | "yes" | "y"),
+            });
+        }
+    }
+    
+    if file.hospital_name.is_empty() {
+        return Err(ParseError::InvalidFormat(
+            "Missing hospital_name in general information rows".to_string(),
+        ));
+    }
+    
+    Ok(file)
+}
+
+/// Collect the payer-specific charges present on a data row
+fn parse_payers(
+    layout: &ColumnLayout,
+    record: &csv::StringRecord,
+    row: usize,
+) -> ParseResult<Vec<PayerInformation>> {
+    let cell = |idx: Option<usize>| {
+        idx.and_then(|i| record.get(i)).map(str::trim).filter(|v| !v.is_empty())
+    };
+    
+    let build = |payer_name: &str,
+                 plan_name: &str,
+                 dollar: Option<&str>,
+                 percentage: Option<&str>,
+                 algorithm: Option<&str>,
+                 estimated: Option<&str>,
+                 methodology: Option<&str>,
+                 notes: Option<&str>|
+     -> ParseResult<Option<PayerInformation>> {
+        if dollar.is_none() && percentage.is_none() && algorithm.is_none() {
+            return Ok(None);
+        }
+        
+        Ok(Some(PayerInformation {
+            payer_name: payer_name.to_string(),
+            plan_name: plan_name.to_string(),
+            standard_charge_dollar: parse_amount(dollar, "negotiated_dollar", row)?,
+            standard_charge_percentage: parse_amount(percentage, "negotiated_percentage", row)?,
+            standard_charge_algorithm: algorithm.map(str::to_string),
+            estimated_amount: parse_amount(estimated, "estimated_amount", row)?,
+            methodology: parse_enum(&methodology.unwrap_or("other").to_lowercase(), "methodology", row)?,
+            additional_payer_notes: notes.map(str::to_string),
+        }))
+    };
+    
+    let mut payers = Vec::new();
+    
+    if layout.is_tall() {
+        if let Some(payer_name) = layout.get(record, "payer_name") {
+            let payer = build(
+                payer_name,
+                layout.get(record, "plan_name").unwrap_or_default(),
+                layout.get(record, "standard_charge|negotiated_dollar"),
+                layout.get(record, "standard_charge|negotiated_percentage"),
+                layout.get(record, "standard_charge|negotiated_algorithm"),
+                layout.get(record, "estimated_amount"),
+                layout.get(record, "standard_charge|methodology"),
+                layout.get(record, "additional_payer_notes"),
+            )?;
+            payers.extend(payer);
+        }
+    } else {
+        for columns in &layout.wide_payers {
+            let payer = build(
+                &columns.payer_name,
+                &columns.plan_name,
+                cell(columns.dollar),
+                cell(columns.percentage),
+                cell(columns.algorithm),
+                cell(columns.estimated_amount),
+                cell(columns.methodology),
+                cell(columns.notes),
+            )?;
+            payers.extend(payer);
+        }
+    }
+    
+    Ok(payers)
+}
+
+/// Convert a hospital standard charge CSV (tall or wide template) into the JSON model
+pub(super) fn parse_csv<R: Read>(reader: R) -> ParseResult<HospitalStandardChargeFile> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut records = csv_reader.records();
+    
+    let mut next_header_row = |name: &str| -> ParseResult<csv::StringRecord> {
+        records
+            .next()
+            .transpose()?
+            .ok_or_else(|| ParseError::InvalidFormat(format!("Missing {} row", name)))
+    };
+    
+    let labels = next_header_row("general information header")?;
+    let values = next_header_row("general information")?;
+    let headers = next_header_row("column header")?;
+    
+    let mut file = parse_general_information(&labels, &values)?;
+    let layout = ColumnLayout::from_headers(&headers);
+    
+    if !layout.named.contains_key("description") {
+        return Err(ParseError::InvalidFormat("Missing description column".to_string()));
+    }
+    
+    let mut item_index: HashMap<String, usize> = HashMap::new();
+    let mut charge_index: HashMap<(usize, String), usize> = HashMap::new();
+    
+    for (offset, record) in records.enumerate() {
+        let record = record?;
+        // Rows are numbered from 1 and the first three rows are headers
+        let row = offset + 4;
+        
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        
+        let description = layout.get(&record, "description").unwrap_or_default();
+        
+        let mut code_information = Vec::new();
+        for (code_idx, type_idx) in &layout.codes {
+            let code = record.get(*code_idx).map(str::trim).unwrap_or_default();
+            let code_type = record.get(*type_idx).map(str::trim).unwrap_or_default();
+            if !code.is_empty() && !code_type.is_empty() {
+                code_information.push(CodeInformation {
+                    code: code.to_string(),
+                    code_type: parse_enum(&code_type.to_uppercase(), "code type", row)?,
+                });
+            }
+        }
+        
+        let drug_information = match (
+            layout.get(&record, "drug_unit_of_measurement"),
+            layout.get(&record, "drug_type_of_measurement"),
+        ) {
+            (Some(unit), Some(unit_type)) => Some(DrugInformation {
+                unit: unit.to_string(),
+                unit_type: unit_type.to_string(),
+            }),
+            _ => None,
+        };
+        
+        let setting = layout.get(&record, "setting").ok_or_else(|| {
+            ParseError::InvalidFormat(format!("Missing setting on row {}", row))
+        })?;
+        let modifiers = split_list(layout.get(&record, "modifiers"));
+        
+        let standard_charge = StandardCharge {
+            setting: parse_enum(&setting.to_lowercase(), "setting", row)?,
+            gross_charge: parse_amount(layout.get(&record, "standard_charge|gross"), "gross charge", row)?,
+            discounted_cash: parse_amount(layout.get(&record, "standard_charge|discounted_cash"), "discounted cash", row)?,
+            minimum: parse_amount(layout.get(&record, "standard_charge|min"), "minimum", row)?,
+            maximum: parse_amount(layout.get(&record, "standard_charge|max"), "maximum", row)?,
+            modifiers: Some(modifiers).filter(|m| !m.is_empty()),
+            payers_information: None,
+            additional_generic_notes: layout.get(&record, "additional_generic_notes").map(str::to_string),
+        };
+        let payers = parse_payers(&layout, &record, row)?;
+        
+        // Group rows describing the same item, then the same charge within that item
+        let codes_key: Vec<String> = code_information
+            .iter()
+            .map(|c| format!("{:?}:{}", c.code_type, c.code))
+            .collect();
+        let item_key = format!("{}|{}|{:?}", description, codes_key.join(","), drug_information);
+        let item_position = *item_index.entry(item_key).or_insert_with(|| {
+            file.standard_charge_information.push(StandardChargeInformation {
+                description: description.to_string(),
+                drug_information,
+                code_information,
+                standard_charges: Vec::new(),
+            });
+            file.standard_charge_information.len() - 1
+        });
+        let item = &mut file.standard_charge_information[item_position];
+        
+        let charge_key = format!(
+            "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+            standard_charge.setting,
+            standard_charge.gross_charge,
+            standard_charge.discounted_cash,
+            standard_charge.minimum,
+            standard_charge.maximum,
+            standard_charge.modifiers,
+            standard_charge.additional_generic_notes,
+        );
+        let charge_position = *charge_index.entry((item_position, charge_key)).or_insert_with(|| {
+            item.standard_charges.push(standard_charge);
+            item.standard_charges.len() - 1
+        });
+        
+        if !payers.is_empty() {
+            item.standard_charges[charge_position]
+                .payers_information
+                .get_or_insert_with(Vec::new)
+                .extend(payers);
+        }
+    }
+    
+    Ok(file)
+}
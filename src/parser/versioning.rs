@@ -0,0 +1,413 @@
+//! Schema-version-aware parsing
+//! 
+//! Files are read into a JSON value first so that the declared `version` can be
+//! inspected before deserializing. The document is then compared with a field
+//! table describing the latest in-crate model: fields the model does not know
+//! are reported as lost, and fields introduced by releases newer than the file's
+//! version are reported as defaulted. Fields renamed by a newer release are
+//! moved to their new name before deserializing and reported as upgraded. Lost
+//! fields are still carried in the `extra` map of the enclosing type.
+//! 
+//! Every version is deserialized into the same latest model once its fields
+//! have been migrated; there are no per-version struct shapes. The release
+//! table records the fields added by 2.0.0 but no renames, as no field renamed
+//! between published schema releases has been confirmed yet. A release that
+//! renames fields only needs its `renamed_fields` filled in.
+
+use std::collections::BTreeSet;
+use serde_json::Value;
+
+use super::ParseResult;
+use crate::types::{MrfFile, SchemaVersion, UpgradeReport};
+
+/// Shape of a JSON field in the latest model
+enum Shape {
+    /// A scalar or an array of scalars
+    Leaf,
+    /// An object, or an array of objects, with the given fields
+    Object(&'static [Field]),
+}
+
+/// A named field and its shape
+struct Field(&'static str, Shape);
+
+const TAX_IDENTIFIER: &[Field] = &[Field("type", Shape::Leaf), Field("value", Shape::Leaf)];
+
+const PROVIDER_GROUP: &[Field] = &[
+    Field("npi", Shape::Leaf),
+    Field("tin", Shape::Object(TAX_IDENTIFIER)),
+];
+
+const FILE_LOCATION: &[Field] = &[Field("description", Shape::Leaf), Field("location", Shape::Leaf)];
+
+const REPORTING_PLAN: &[Field] = &[
+    Field("plan_name", Shape::Leaf),
+    Field("plan_id_type", Shape::Leaf),
+    Field("plan_id", Shape::Leaf),
+    Field("plan_market_type", Shape::Leaf),
+    Field("issuer_name", Shape::Leaf),
+    Field("plan_sponsor_name", Shape::Leaf),
+];
+
+const REPORTING_STRUCTURE: &[Field] = &[
+    Field("reporting_plans", Shape::Object(REPORTING_PLAN)),
+    Field("in_network_files", Shape::Object(FILE_LOCATION)),
+    Field("allowed_amount_file", Shape::Object(FILE_LOCATION)),
+];
+
+const TABLE_OF_CONTENTS: &[Field] = &[
+    Field("reporting_entity_name", Shape::Leaf),
+    Field("reporting_entity_type", Shape::Leaf),
+    Field("reporting_structure", Shape::Object(REPORTING_STRUCTURE)),
+    Field("version", Shape::Leaf),
+    Field("last_updated_on", Shape::Leaf),
+];
+
+const CODE: &[Field] = &[
+    Field("billing_code_type", Shape::Leaf),
+    Field("billing_code_type_version", Shape::Leaf),
+    Field("billing_code", Shape::Leaf),
+    Field("description", Shape::Leaf),
+];
+
+const NEGOTIATED_PRICE: &[Field] = &[
+    Field("negotiated_type", Shape::Leaf),
+    Field("negotiated_rate", Shape::Leaf),
+    Field("expiration_date", Shape::Leaf),
+    Field("billing_class", Shape::Leaf),
+    Field("service_code", Shape::Leaf),
+    Field("billing_code_modifier", Shape::Leaf),
+    Field("additional_information", Shape::Leaf),
+];
+
+const NEGOTIATED_RATE: &[Field] = &[
+    Field("negotiated_prices", Shape::Object(NEGOTIATED_PRICE)),
+    Field("provider_groups", Shape::Object(PROVIDER_GROUP)),
+    Field("provider_references", Shape::Leaf),
+];
+
+const IN_NETWORK_RATE: &[Field] = &[
+    Field("negotiation_arrangement", Shape::Leaf),
+    Field("name", Shape::Leaf),
+    Field("billing_code_type", Shape::Leaf),
+    Field("billing_code_type_version", Shape::Leaf),
+    Field("billing_code", Shape::Leaf),
+    Field("description", Shape::Leaf),
+    Field("severity_of_illness", Shape::Leaf),
+    Field("negotiated_rates", Shape::Object(NEGOTIATED_RATE)),
+    Field("bundled_codes", Shape::Object(CODE)),
+    Field("covered_services", Shape::Object(CODE)),
+];
+
+const PROVIDER_REFERENCE: &[Field] = &[
+    Field("provider_group_id", Shape::Leaf),
+    Field("network_name", Shape::Leaf),
+    Field("provider_groups", Shape::Object(PROVIDER_GROUP)),
+    Field("location", Shape::Leaf),
+];
+
+const IN_NETWORK: &[Field] = &[
+    Field("reporting_entity_name", Shape::Leaf),
+    Field("reporting_entity_type", Shape::Leaf),
+    Field("plan_name", Shape::Leaf),
+    Field("plan_id_type", Shape::Leaf),
+    Field("plan_id", Shape::Leaf),
+    Field("plan_market_type", Shape::Leaf),
+    Field("issuer_name", Shape::Leaf),
+    Field("plan_sponsor_name", Shape::Leaf),
+    Field("in_network", Shape::Object(IN_NETWORK_RATE)),
+    Field("provider_references", Shape::Object(PROVIDER_REFERENCE)),
+    Field("last_updated_on", Shape::Leaf),
+    Field("version", Shape::Leaf),
+];
+
+const PROVIDER: &[Field] = &[Field("billed_charge", Shape::Leaf), Field("npi", Shape::Leaf)];
+
+const PAYMENT: &[Field] = &[
+    Field("allowed_amount", Shape::Leaf),
+    Field("billing_code_modifier", Shape::Leaf),
+    Field("providers", Shape::Object(PROVIDER)),
+];
+
+const ALLOWED_AMOUNT: &[Field] = &[
+    Field("tin", Shape::Object(TAX_IDENTIFIER)),
+    Field("service_code", Shape::Leaf),
+    Field("billing_class", Shape::Leaf),
+    Field("payments", Shape::Object(PAYMENT)),
+];
+
+const OUT_OF_NETWORK_RATE: &[Field] = &[
+    Field("name", Shape::Leaf),
+    Field("billing_code_type", Shape::Leaf),
+    Field("billing_code_type_version", Shape::Leaf),
+    Field("billing_code", Shape::Leaf),
+    Field("description", Shape::Leaf),
+    Field("allowed_amounts", Shape::Object(ALLOWED_AMOUNT)),
+];
+
+const ALLOWED_AMOUNT_FILE: &[Field] = &[
+    Field("reporting_entity_name", Shape::Leaf),
+    Field("reporting_entity_type", Shape::Leaf),
+    Field("plan_name", Shape::Leaf),
+    Field("plan_id_type", Shape::Leaf),
+    Field("plan_id", Shape::Leaf),
+    Field("plan_market_type", Shape::Leaf),
+    Field("issuer_name", Shape::Leaf),
+    Field("plan_sponsor_name", Shape::Leaf),
+    Field("out_of_network", Shape::Object(OUT_OF_NETWORK_RATE)),
+    Field("last_updated_on", Shape::Leaf),
+    Field("version", Shape::Leaf),
+    Field("sourceSystem_plan", Shape::Leaf),
+];
+
+const PROVIDER_REFERENCE_FILE: &[Field] = &[
+    Field("provider_groups", Shape::Object(PROVIDER_GROUP)),
+    Field("version", Shape::Leaf),
+];
+
+/// A schema release and the field paths it introduced or renamed
+struct Release {
+    version: SchemaVersion,
+    added_fields: &'static [&'static str],
+    /// Pairs of the field path before the release and the path it was renamed
+    /// to; both paths share the same parent object
+    renamed_fields: &'static [(&'static str, &'static str)],
+}
+
+/// Schema releases that changed the fields modelled by this crate, oldest first
+/// 
+/// Only changes confirmed against the published schemas belong here.
+const RELEASES: &[Release] = &[
+    Release {
+        version: SchemaVersion::V1_0_0,
+        added_fields: &[],
+        renamed_fields: &[],
+    },
+    Release {
+        version: SchemaVersion::V2_0_0,
+        added_fields: &[
+            "last_updated_on",
+            "issuer_name",
+            "plan_sponsor_name",
+            "reporting_structure[].reporting_plans[].issuer_name",
+            "reporting_structure[].reporting_plans[].plan_sponsor_name",
+            "in_network[].severity_of_illness",
+            "provider_references[].network_name",
+        ],
+        renamed_fields: &[],
+    },
+];
+
+/// Pick the field table for a document from its required top-level keys
+fn root_fields(value: &Value) -> &'static [Field] {
+    if value.get("reporting_structure").is_some() {
+        TABLE_OF_CONTENTS
+    } else if value.get("in_network").is_some() {
+        IN_NETWORK
+    } else if value.get("out_of_network").is_some() {
+        ALLOWED_AMOUNT_FILE
+    } else {
+        PROVIDER_REFERENCE_FILE
+    }
+}
+
+/// Walk a value against a field table, recording present and unknown field paths
+fn walk(
+    value: &Value,
+    fields: &'static [Field],
+    path: &str,
+    present: &mut BTreeSet<String>,
+    unknown: &mut BTreeSet<String>,
+) {
+    match value {
+        Value::Array(items) => {
+            let path = format!("{}[]", path);
+            if !items.is_empty() {
+                present.insert(path.clone());
+            }
+            for item in items {
+                walk(item, fields, &path, present, unknown);
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                
+                match fields.iter().find(|field| field.0 == key) {
+                    Some(Field(_, Shape::Object(child_fields))) => {
+                        walk(child, child_fields, &child_path, present, unknown);
+                        present.insert(child_path);
+                    }
+                    Some(Field(_, Shape::Leaf)) => {
+                        present.insert(child_path);
+                    }
+                    None => {
+                        unknown.insert(child_path);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Move the fields at `path` (relative to `value`) to the key `new_key`,
+/// returning whether any field was moved
+fn rename(value: &mut Value, path: &str, new_key: &str) -> bool {
+    match value {
+        Value::Array(items) => {
+            let mut moved = false;
+            for item in items {
+                moved |= rename(item, path, new_key);
+            }
+            moved
+        }
+        Value::Object(map) => match path.split_once('.') {
+            Some((key, rest)) => match map.get_mut(key.trim_end_matches("[]")) {
+                Some(child) => rename(child, rest, new_key),
+                None => false,
+            },
+            // A file that already uses the new name keeps its value
+            None if map.contains_key(new_key) => false,
+            None => match map.remove(path) {
+                Some(child) => {
+                    map.insert(new_key.to_string(), child);
+                    true
+                }
+                None => false,
+            },
+        },
+        _ => false,
+    }
+}
+
+/// Apply the renames of every release newer than `baseline` to a document,
+/// returning pairs of the old and new field paths that were moved
+fn apply_renames(value: &mut Value, releases: &[Release], baseline: SchemaVersion) -> Vec<(String, String)> {
+    let mut upgraded = Vec::new();
+    for release in releases.iter().filter(|release| release.version > baseline) {
+        for (from, to) in release.renamed_fields {
+            let new_key = to.rsplit('.').next().unwrap_or(to);
+            if rename(value, from, new_key) {
+                upgraded.push((from.to_string(), to.to_string()));
+            }
+        }
+    }
+    upgraded
+}
+
+/// Detect the schema version declared by a parsed document
+pub(super) fn detect_version(value: &Value) -> Option<SchemaVersion> {
+    value
+        .get("version")
+        .and_then(Value::as_str)
+        .and_then(SchemaVersion::parse)
+}
+
+/// Convert a document of any schema version into the latest in-crate model
+pub(super) fn upgrade(value: Value) -> ParseResult<(MrfFile, UpgradeReport)> {
+    upgrade_with(value, RELEASES)
+}
+
+/// Convert a document using the given release history
+fn upgrade_with(mut value: Value, releases: &[Release]) -> ParseResult<(MrfFile, UpgradeReport)> {
+    let declared_version = value.get("version").and_then(Value::as_str).map(str::to_string);
+    let source_version = declared_version.as_deref().and_then(SchemaVersion::parse);
+    
+    // Files without a usable version are treated as the oldest release
+    let baseline = source_version.unwrap_or(SchemaVersion::V1_0_0);
+    let upgraded_fields = apply_renames(&mut value, releases, baseline);
+    
+    let root = root_fields(&value);
+    let mut present = BTreeSet::new();
+    let mut unknown = BTreeSet::new();
+    walk(&value, root, "", &mut present, &mut unknown);
+    
+    let defaulted_fields = releases
+        .iter()
+        .filter(|release| release.version > baseline)
+        .flat_map(|release| release.added_fields.iter())
+        .filter(|path| {
+            // Only report fields that belong to this file type, whose parent
+            // object occurs in the document, and that were not supplied anyway
+            let top_level = path.split(['.', '[']).next().unwrap_or_default();
+            let parent_present = match path.rsplit_once('.') {
+                Some((parent, _)) => present.contains(parent),
+                None => true,
+            };
+            root.iter().any(|field| field.0 == top_level)
+                && parent_present
+                && !present.contains(**path)
+        })
+        .map(|path| path.to_string())
+        .collect();
+    
+    let file = serde_json::from_value(value)?;
+    
+    let report = UpgradeReport {
+        declared_version,
+        source_version,
+        target_version: SchemaVersion::LATEST,
+        defaulted_fields,
+        upgraded_fields,
+        lost_fields: unknown.into_iter().collect(),
+    };
+    
+    Ok((file, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const RENAMING_RELEASES: &[Release] = &[Release {
+        version: SchemaVersion::V2_0_0,
+        added_fields: &[],
+        renamed_fields: &[
+            ("in_network[].rank", "in_network[].severity_of_illness"),
+            ("sponsor_name", "plan_sponsor_name"),
+        ],
+    }];
+    
+    #[test]
+    fn test_renamed_fields_are_upgraded() {
+        let value = serde_json::json!({
+            "reporting_entity_name": "Test Entity",
+            "reporting_entity_type": "health insurance issuer",
+            "sponsor_name": "Test Sponsor",
+            "plan_sponsor_name": "Kept Sponsor",
+            "in_network": [{
+                "negotiation_arrangement": "ffs",
+                "name": "Knee replacement",
+                "billing_code_type": "CPT",
+                "billing_code_type_version": "2022",
+                "billing_code": "27447",
+                "description": "Knee replacement",
+                "rank": "major",
+                "negotiated_rates": []
+            }],
+            "last_updated_on": "2022-07-01",
+            "version": "1.0.0"
+        });
+        
+        let (file, report) = upgrade_with(value, RENAMING_RELEASES).unwrap();
+        assert_eq!(
+            report.upgraded_fields,
+            vec![("in_network[].rank".to_string(), "in_network[].severity_of_illness".to_string())]
+        );
+        // The file already used the new name, so the old one stays unknown
+        assert_eq!(report.lost_fields, vec!["sponsor_name".to_string()]);
+        
+        match file {
+            MrfFile::InNetwork(file) => {
+                assert_eq!(file.in_network[0].severity_of_illness.as_deref(), Some("major"));
+                assert_eq!(file.plan_sponsor_name.as_deref(), Some("Kept Sponsor"));
+            }
+            _ => panic!("Expected InNetwork file type"),
+        }
+    }
+}
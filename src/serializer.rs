@@ -0,0 +1,221 @@
+//! Canonical JSON serialization of MRF files
+//! 
+//! This module re-emits parsed MRF files as canonical JSON: a single,
+//! deterministic byte representation of a file's content. Two files that
+//! parse to the same model always serialize to the same bytes, so re-published
+//! files can be compared byte for byte.
+//! 
+//! # Canonical Form
+//! 
+//! - Compact output with no insignificant whitespace and no trailing newline
+//! - Fields in the order of the Transparency in Coverage schema (the
+//!   declaration order of the types in [`crate::types`])
+//! - Fields not defined by the schema follow the schema fields, sorted by key
+//! - Numbers in the shortest decimal form that reads back to the same value,
+//!   without exponents or a trailing `.0` (`100`, not `100.0` or `1e2`)
+//! - Enumerated values in the spelling of the schema (`"HIOS"` for `"hios"`);
+//!   entity types and billing code types outside the schema are written as
+//!   `"Other"`
+//! - Optional fields that are absent or `null` are omitted
+//! 
+//! # Round-Trip Guarantee
+//! 
+//! For every file accepted by [`MrfParser`](crate::parser::MrfParser):
+//! 
+//! - Serializing, parsing the output and serializing again yields identical bytes
+//! - Every field of the source file, including fields unknown to the schema,
+//!   is present in the output with the same value, up to the normalizations
+//!   listed above
+//! 
+//! # Examples
+//! 
+//! ```no_run
+//! use mrf_rs::parser::MrfParser;
+//! use mrf_rs::serializer::MrfSerializer;
+//! 
+//! let file = MrfParser::parse_file("in_network.json")?;
+//! MrfSerializer::write_canonical_file("in_network.canonical.json", &file)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use serde::Serialize;
+use serde_json::ser::Formatter;
+
+/// Error type for serialization operations
+#[derive(Debug, thiserror::Error)]
+pub enum SerializeError {
+    /// IO error occurred while writing the output
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    /// The value could not be serialized as JSON
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Result type alias for serialization operations
+pub type SerializeResult<T> = Result<T, SerializeError>;
+
+/// JSON formatter producing the canonical number representation
+/// 
+/// Output is compact, like [`serde_json::ser::CompactFormatter`], but floating
+/// point numbers are written in their shortest round-trip decimal form with no
+/// exponent and no fractional part when they are integral.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CanonicalFormatter;
+
+impl CanonicalFormatter {
+    fn format_f64(value: f64) -> String {
+        // Rust's `Display` for floats is the shortest representation that
+        // parses back to the same value and never uses an exponent
+        if value == 0.0 {
+            "0".to_string()
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+impl Formatter for CanonicalFormatter {
+    fn write_f32<W>(&mut self, writer: &mut W, value: f32) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        let formatted = if value == 0.0 { "0".to_string() } else { value.to_string() };
+        writer.write_all(formatted.as_bytes())
+    }
+    
+    fn write_f64<W>(&mut self, writer: &mut W, value: f64) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        writer.write_all(Self::format_f64(value).as_bytes())
+    }
+}
+
+/// Serializer for MRF files in canonical JSON form
+/// 
+/// `MrfSerializer` mirrors [`MrfParser`](crate::parser::MrfParser): a set of
+/// static methods writing any of the MRF types, or an
+/// [`MrfFile`](crate::types::MrfFile), to various destinations.
+pub struct MrfSerializer;
+
+impl MrfSerializer {
+    /// Write a value as canonical JSON to any writer
+    /// 
+    /// The writer is not buffered by this method; wrap files and sockets in a
+    /// [`BufWriter`] for large outputs.
+    pub fn to_canonical_writer<W: Write, T: Serialize + ?Sized>(writer: W, value: &T) -> SerializeResult<()> {
+        let mut serializer = serde_json::Serializer::with_formatter(writer, CanonicalFormatter);
+        value.serialize(&mut serializer)?;
+        Ok(())
+    }
+    
+    /// Serialize a value as canonical JSON bytes
+    pub fn to_canonical_vec<T: Serialize + ?Sized>(value: &T) -> SerializeResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+        Self::to_canonical_writer(&mut buffer, value)?;
+        Ok(buffer)
+    }
+    
+    /// Serialize a value as a canonical JSON string
+    pub fn to_canonical_string<T: Serialize + ?Sized>(value: &T) -> SerializeResult<String> {
+        let buffer = Self::to_canonical_vec(value)?;
+        // serde_json only ever writes valid UTF-8
+        Ok(String::from_utf8(buffer).expect("JSON output is valid UTF-8"))
+    }
+    
+    /// Write a value as canonical JSON to a file, replacing any existing content
+    pub fn write_canonical_file<P: AsRef<Path>, T: Serialize + ?Sized>(path: P, value: &T) -> SerializeResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        Self::to_canonical_writer(&mut writer, value)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::MrfParser;
+    use crate::types::{BillingCodeType, EntityType, MrfFile, PlanIdType};
+    
+    #[test]
+    fn test_number_formatting() {
+        assert_eq!(CanonicalFormatter::format_f64(100.0), "100");
+        assert_eq!(CanonicalFormatter::format_f64(95.5), "95.5");
+        assert_eq!(CanonicalFormatter::format_f64(0.1), "0.1");
+        assert_eq!(CanonicalFormatter::format_f64(-0.0), "0");
+        assert_eq!(CanonicalFormatter::format_f64(1e21), "1000000000000000000000");
+        assert_eq!(CanonicalFormatter::format_f64(1.5e-7), "0.00000015");
+    }
+    
+    #[test]
+    fn test_canonical_in_network_file() {
+        let json = r#"{
+            "version": "1.0.0",
+            "last_updated_on": "2024-01-01",
+            "reporting_entity_type": "medicare",
+            "reporting_entity_name": "Acme",
+            "plan_id_type": "hios",
+            "plan_name": null,
+            "vendor_note": {"b": 2, "a": 1.50},
+            "in_network": [{
+                "negotiation_arrangement": "ffs",
+                "name": "Visit",
+                "billing_code_type": "CPT",
+                "billing_code_type_version": "2024",
+                "billing_code": "99213",
+                "description": "Visit",
+                "negotiated_rates": [{
+                    "provider_references": [1],
+                    "negotiated_prices": [{
+                        "billing_class": "professional",
+                        "service_code": ["11"],
+                        "negotiated_type": "negotiated",
+                        "negotiated_rate": 100.00,
+                        "expiration_date": "9999-12-31",
+                        "price_note": "kept"
+                    }]
+                }]
+            }]
+        }"#;
+        
+        let file = MrfParser::parse_str(json).unwrap();
+        match &file {
+            MrfFile::InNetwork(in_network) => {
+                assert_eq!(in_network.reporting_entity_type, EntityType::Other);
+                assert_eq!(in_network.plan_id_type, Some(PlanIdType::Hios));
+            }
+            other => panic!("Expected InNetwork, got {:?}", other),
+        }
+        
+        let canonical = MrfSerializer::to_canonical_string(&file).unwrap();
+        assert_eq!(
+            canonical,
+            concat!(
+                r#"{"reporting_entity_name":"Acme","reporting_entity_type":"Other","plan_id_type":"HIOS","#,
+                r#""in_network":[{"negotiation_arrangement":"ffs","name":"Visit","billing_code_type":"CPT","#,
+                r#""billing_code_type_version":"2024","billing_code":"99213","description":"Visit","#,
+                r#""negotiated_rates":[{"negotiated_prices":[{"negotiated_type":"negotiated","negotiated_rate":100,"#,
+                r#""expiration_date":"9999-12-31","service_code":["11"],"billing_class":"professional","#,
+                r#""price_note":"kept"}],"#,
+                r#""provider_references":[1]}]}],"last_updated_on":"2024-01-01","version":"1.0.0","#,
+                r#""vendor_note":{"a":1.5,"b":2}}"#,
+            )
+        );
+        
+        let reparsed = MrfParser::parse_str(&canonical).unwrap();
+        assert_eq!(MrfSerializer::to_canonical_string(&reparsed).unwrap(), canonical);
+    }
+    
+    #[test]
+    fn test_values_outside_the_schema_are_other() {
+        assert_eq!(serde_json::from_str::<EntityType>(r#""Other""#).unwrap(), EntityType::Other);
+        assert_eq!(serde_json::from_str::<BillingCodeType>(r#""CUSTOM""#).unwrap(), BillingCodeType::Other);
+        assert_eq!(serde_json::to_string(&BillingCodeType::Other).unwrap(), r#""Other""#);
+    }
+}
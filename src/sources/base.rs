@@ -4,112 +4,544 @@
 //! different insurer implementations, including HTTP client setup,
 //! rate limiting, retry logic, and download utilities.
 
-use super::{FetchOptions, MrfFileInfo, ProgressCallback, SourceConfig, SourceError, SourceResult};
-use async_trait::async_trait;
-use reqwest::{Client, ClientBuilder, Response};
-use std::path::Path;
-use std::sync::Arc;
+use super::cache::{CacheMetadata, CacheValidators, DownloadCache};
+use super::{
+    ByteStream, CompressionType, FetchOptions, MrfFileInfo, ProgressCallback, SourceConfig, SourceError,
+    SourceResult, DEFAULT_TIMEOUT_SECS,
+};
+use super::rate_limit::{BandwidthThrottle, RateLimitPermit, RateLimiter};
+use super::retry::{parse_retry_after, RetryState};
+use super::size_limit::SizeGuard;
+use super::sniff::{self, Sniffed, SNIFF_LEN};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use async_compression::tokio::bufread::GzipDecoder;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures_util::{future, Stream, StreamExt};
+use md5::Md5;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_TYPE,
+    ETAG, IF_RANGE, LAST_MODIFIED, RANGE, RETRY_AFTER,
+};
+use reqwest::{Certificate, Client, ClientBuilder, Identity, Method, NoProxy, Proxy, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::time::sleep;
+use tokio_util::io::{ReaderStream, StreamReader};
 use tracing::{debug, info, warn};
 
 /// HTTP client wrapper with rate limiting and retry logic
 #[derive(Clone)]
 pub struct HttpClient {
     client: Client,
+    /// Client with the opposite certificate verification setting to
+    /// `client`, built on first use
+    alternate_client: Arc<OnceLock<Client>>,
     config: SourceConfig,
-    rate_limiter: Option<Arc<RateLimiter>>,
+    rate_limiter: Option<RateLimiter>,
+    throttle: BandwidthThrottle,
 }
 
 impl HttpClient {
     /// Create a new HTTP client with the given configuration
     pub fn new(config: SourceConfig) -> SourceResult<Self> {
+        let client = Self::build_client(&config, Self::default_verify_ssl(&config))?;
+        
+        let rate_limiter = config
+            .rate_limiter
+            .clone()
+            .or_else(|| config.rate_limit.map(RateLimiter::per_second));
+        let throttle = config
+            .bandwidth_throttle
+            .clone()
+            .unwrap_or_else(|| BandwidthThrottle::new(config.max_bytes_per_second));
+        
+        Ok(Self {
+            client,
+            alternate_client: Arc::new(OnceLock::new()),
+            config,
+            rate_limiter,
+            throttle,
+        })
+    }
+    
+    /// Build a reqwest client from the configuration
+    fn build_client(config: &SourceConfig, verify_ssl: bool) -> SourceResult<Client> {
         let mut builder = ClientBuilder::new()
             .timeout(Duration::from_secs(
                 config
                     .default_options
                     .as_ref()
                     .and_then(|o| o.timeout_secs)
-                    .unwrap_or(300),
+                    .unwrap_or(DEFAULT_TIMEOUT_SECS),
             ))
             .gzip(true)
             .deflate(true)
-            .brotli(true);
-            
+            .brotli(true)
+            .danger_accept_invalid_certs(!verify_ssl);
 
         if let Some(user_agent) = &config.user_agent {
             builder = builder.user_agent(user_agent);
         }
+        
+        if let Some(proxy_url) = &config.proxy {
+            let proxy = Proxy::all(proxy_url)
+                .map_err(|e| SourceError::Config(format!("Invalid proxy URL {}: {}", proxy_url, e)))?
+                .no_proxy(NoProxy::from_string(&config.no_proxy.join(",")));
+            builder = builder.proxy(proxy);
+        }
+        
+        for path in &config.root_certificates {
+            let certificates = std::fs::read(path)
+                .map_err(SourceError::from)
+                .and_then(|pem| Certificate::from_pem_bundle(&pem).map_err(SourceError::Http))
+                .map_err(|e| SourceError::Config(format!("Failed to load root certificates from {:?}: {}", path, e)))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        
+        if let Some(path) = &config.client_identity {
+            let identity = std::fs::read(path)
+                .map_err(SourceError::from)
+                .and_then(|pem| Identity::from_pem(&pem).map_err(SourceError::Http))
+                .map_err(|e| SourceError::Config(format!("Failed to load client identity from {:?}: {}", path, e)))?;
+            builder = builder.identity(identity);
+        }
+        
+        if !config.default_headers.is_empty() {
+            let mut headers = HeaderMap::new();
+            for (name, value) in &config.default_headers {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| SourceError::Config(format!("Invalid header name {:?}: {}", name, e)))?;
+                let value = HeaderValue::from_str(value)
+                    .map_err(|e| SourceError::Config(format!("Invalid value for header {}: {}", name, e)))?;
+                headers.insert(name, value);
+            }
+            builder = builder.default_headers(headers);
+        }
 
-        let client = builder
+        builder
             .build()
-            .map_err(|e| SourceError::Config(format!("Failed to build HTTP client: {}", e)))?;
+            .map_err(|e| SourceError::Config(format!("Failed to build HTTP client: {}", e)))
+    }
 
-        let rate_limiter = config.rate_limit.map(|limit| Arc::new(RateLimiter::new(limit)));
+    /// The client's default fetch options, from `SourceConfig::default_options`
+    pub fn default_options(&self) -> FetchOptions {
+        self.config.default_options.clone().unwrap_or_default()
+    }
 
-        Ok(Self {
-            client,
-            config,
-            rate_limiter,
-        })
+    /// Resolve per-call options against the client defaults
+    /// 
+    /// Per-call options take precedence; settings they leave unset fall back
+    /// to `SourceConfig::default_options`.
+    pub fn resolve_options(&self, options: Option<&FetchOptions>) -> FetchOptions {
+        match options {
+            Some(options) => options.clone().with_defaults(self.config.default_options.as_ref()),
+            None => self.default_options(),
+        }
+    }
+    
+    /// The rate limiter applied to requests, if any
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+    
+    /// The bandwidth throttle applied to response bodies
+    /// 
+    /// Shared by all clones of this client; use
+    /// [`BandwidthThrottle::set_limit`] to change the limit while downloads
+    /// are running.
+    pub fn bandwidth_throttle(&self) -> &BandwidthThrottle {
+        &self.throttle
+    }
+    
+    /// Whether `client` verifies certificates
+    fn default_verify_ssl(config: &SourceConfig) -> bool {
+        config
+            .default_options
+            .as_ref()
+            .is_none_or(|options| options.verify_ssl)
+    }
+    
+    /// Get the reqwest client matching the TLS settings of `options`
+    /// 
+    /// `options` is merged with the client defaults first, so a per-call
+    /// `verify_ssl` can turn verification off, and a per-call override can
+    /// turn it back on.
+    fn client_for(&self, options: &FetchOptions) -> SourceResult<Client> {
+        let verify_ssl = self.resolve_options(Some(options)).verify_ssl;
+        if verify_ssl == Self::default_verify_ssl(&self.config) {
+            return Ok(self.client.clone());
+        }
+        
+        if let Some(client) = self.alternate_client.get() {
+            return Ok(client.clone());
+        }
+        
+        if !verify_ssl {
+            warn!("TLS certificate verification is disabled for this request");
+        }
+        let client = Self::build_client(&self.config, verify_ssl)?;
+        Ok(self.alternate_client.get_or_init(|| client).clone())
     }
 
     /// Execute an HTTP GET request with retry logic
     pub async fn get(&self, url: &str) -> SourceResult<Response> {
-        let options = self.config.default_options.as_ref().cloned().unwrap_or_default();
-        let max_retries = options.max_retries.unwrap_or(3);
+        self.get_with_options(url, &self.default_options()).await
+    }
+    
+    /// Execute an HTTP GET request with retry logic using the given options
+    /// 
+    /// `options.timeout_secs`, `options.max_retries` and `options.verify_ssl`
+    /// apply to this request only.
+    pub async fn get_with_options(&self, url: &str, options: &FetchOptions) -> SourceResult<Response> {
+        self.get_with_headers(url, options, HeaderMap::new()).await
+    }
+    
+    /// Execute an HTTP GET request with additional request headers
+    /// 
+    /// A `304 Not Modified` answer to a conditional request is returned as a
+    /// successful response.
+    pub async fn get_with_headers(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+        headers: HeaderMap,
+    ) -> SourceResult<Response> {
+        let (response, _permit) = self.get_with_permit(url, options, headers).await?;
+        Ok(response)
+    }
+    
+    /// Execute an HTTP GET request, returning the rate limiter permit with
+    /// the response
+    /// 
+    /// Hold the permit while reading the body so the body transfer counts
+    /// against the limiter's concurrency cap.
+    pub async fn get_with_permit(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+        headers: HeaderMap,
+    ) -> SourceResult<(Response, Option<RateLimitPermit>)> {
+        let mut retry = self.retry_state(options);
+        self.get_retrying(url, options, headers, &mut retry).await
+    }
+    
+    /// Retry bookkeeping for one logical request made with `options`
+    /// 
+    /// Every request and body read belonging to the same download shares one
+    /// state, so `max_retries` and the policy deadline bound the download as
+    /// a whole.
+    pub(crate) fn retry_state(&self, options: &FetchOptions) -> RetryState<'_> {
+        RetryState::new(&self.config.retry_policy, options.effective_max_retries())
+    }
+    
+    /// Like `get_with_permit`, drawing retries from `retry`
+    pub(crate) async fn get_retrying(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+        headers: HeaderMap,
+        retry: &mut RetryState<'_>,
+    ) -> SourceResult<(Response, Option<RateLimitPermit>)> {
+        let permit = match &self.rate_limiter {
+            Some(limiter) => Some(limiter.acquire(url).await),
+            None => None,
+        };
+        
+        let response = self.send_with_retry(Method::GET, url, options, headers, true, retry).await?;
+        Ok((response, permit))
+    }
+    
+    /// Execute an HTTP HEAD request with rate limiting and retry logic
+    pub async fn head(&self, url: &str, options: &FetchOptions) -> SourceResult<Response> {
+        let _permit = match &self.rate_limiter {
+            Some(limiter) => Some(limiter.acquire(url).await),
+            None => None,
+        };
+        
+        let mut retry = self.retry_state(options);
+        self.send_with_retry(Method::HEAD, url, options, HeaderMap::new(), true, &mut retry).await
+    }
+    
+    /// Detect the compression and MRF type of a remote file from its first bytes
+    /// 
+    /// Asks for the first [`SNIFF_LEN`] bytes with a `Range` request and
+    /// reads no more than that even if the server sends the whole file.
+    pub async fn sniff(&self, url: &str, options: &FetchOptions) -> SourceResult<Sniffed> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+        headers.insert(
+            RANGE,
+            HeaderValue::from_str(&format!("bytes=0-{}", SNIFF_LEN - 1)).expect("valid range header"),
+        );
+        
+        let (response, _permit) = self.get_with_permit(url, options, headers).await?;
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        let mut stream = response.bytes_stream();
+        while head.len() < SNIFF_LEN {
+            match stream.next().await {
+                Some(chunk) => head.extend_from_slice(&chunk?),
+                None => break,
+            }
+        }
+        head.truncate(SNIFF_LEN);
+        
+        Ok(sniff::sniff(&head))
+    }
+    
+    /// Send a request, retrying according to the retry policy
+    /// 
+    /// The caller must hold a rate limiter permit; `first_token_taken` tells
+    /// whether acquiring it already consumed the rate token for the first attempt.
+    /// Retries are drawn from `retry`, which may already have been used by
+    /// earlier requests of the same download.
+    async fn send_with_retry(
+        &self,
+        method: Method,
+        url: &str,
+        options: &FetchOptions,
+        headers: HeaderMap,
+        first_token_taken: bool,
+        retry: &mut RetryState<'_>,
+    ) -> SourceResult<Response> {
+        let client = self.client_for(options)?;
+        let policy = &self.config.retry_policy;
+        let mut first = true;
 
-        let mut attempt = 0;
         loop {
             // Apply rate limiting
-            if let Some(limiter) = &self.rate_limiter {
-                limiter.acquire().await;
+            if let (Some(limiter), false) = (&self.rate_limiter, first_token_taken && first) {
+                limiter.wait_for_token(url).await;
             }
+            first = false;
 
-            debug!("HTTP GET attempt {} for {}", attempt + 1, url);
+            debug!("HTTP {} attempt {} for {}", method, retry.attempt() + 1, url);
 
-            match self.client.get(url).send().await {
+            let mut request = client.request(method.clone(), url).headers(headers.clone());
+            if let Some(timeout) = options.timeout_secs {
+                request = request.timeout(Duration::from_secs(timeout));
+            }
+            
+            match request.send().await {
                 Ok(response) => {
-                    if response.status().is_success() {
+                    let status = response.status();
+                    if status.is_success() || status == StatusCode::NOT_MODIFIED {
                         return Ok(response);
-                    } else if response.status().as_u16() == 429 {
-                        // Rate limited
+                    }
+                    
+                    if policy.is_retryable_status(status.as_u16()) {
                         let retry_after = response
                             .headers()
-                            .get("retry-after")
+                            .get(RETRY_AFTER)
                             .and_then(|v| v.to_str().ok())
-                            .and_then(|v| v.parse::<u64>().ok())
-                            .unwrap_or(60);
-
-                        warn!("Rate limited, retrying after {} seconds", retry_after);
-                        return Err(SourceError::RateLimited(retry_after));
-                    } else if attempt < max_retries && response.status().is_server_error() {
-                        warn!(
-                            "Server error ({}), retrying...",
-                            response.status()
-                        );
-                        attempt += 1;
-                        sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                            .and_then(|v| parse_retry_after(v, Utc::now()));
+                        
+                        if let Some(delay) = retry.next_delay(retry_after) {
+                            warn!("HTTP error ({}) for {}, retrying in {:.1?}", status, url, delay);
+                            sleep(delay).await;
+                            continue;
+                        }
+                        
+                        if status == StatusCode::TOO_MANY_REQUESTS {
+                            let retry_after = retry_after.map_or(60, |delay| delay.as_secs());
+                            warn!("Rate limited, retry after {} seconds", retry_after);
+                            return Err(SourceError::RateLimited(retry_after));
+                        }
+                    }
+                    
+                    return Err(SourceError::Other(format!("HTTP error: {}", status)));
+                }
+                Err(e) => {
+                    if let Some(delay) = retry.next_delay(None) {
+                        warn!("Request failed: {}, retrying in {:.1?}", e, delay);
+                        sleep(delay).await;
                         continue;
-                    } else {
-                        return Err(SourceError::Other(
-                            format!("HTTP error: {}", response.status())
-                        ));
                     }
+                    return Err(SourceError::Http(e));
+                }
+            }
+        }
+    }
+    
+    /// Read a response body into memory
+    /// 
+    /// Call this while holding the permit returned with the response, if any.
+    /// If the body stream fails, the request is retried according to the
+    /// retry policy. When the response carries a length and a validator, the
+    /// retry asks only for the missing bytes with a `Range` request, and
+    /// starts over otherwise.
+    /// 
+    /// The size limits in `options` are checked as the body arrives, so an
+    /// oversized body is never buffered in full.
+    pub async fn read_body(&self, url: &str, options: &FetchOptions, response: Response) -> SourceResult<Vec<u8>> {
+        let mut retry = self.retry_state(options);
+        self.read_body_retrying(url, options, response, &mut retry).await
+    }
+    
+    /// Like `read_body`, drawing retries from `retry`
+    pub(crate) async fn read_body_retrying(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+        response: Response,
+        retry: &mut RetryState<'_>,
+    ) -> SourceResult<Vec<u8>> {
+        let validators = CacheValidators::from_response(&response);
+        let resumable = validators.content_length.is_some() && validators.range_validator().is_some();
+        
+        let mut data = Vec::new();
+        let mut guard = SizeGuard::new(options);
+        let mut response = response;
+        loop {
+            let mut stream = response.bytes_stream();
+            let error = loop {
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        guard.update(&chunk)?;
+                        self.throttle.consume(chunk.len()).await;
+                        data.extend_from_slice(&chunk);
+                    }
+                    Some(Err(e)) => break e,
+                    None => return Ok(data),
                 }
-                Err(e) if attempt < max_retries => {
-                    warn!("Request failed: {}, retrying...", e);
-                    attempt += 1;
-                    sleep(Duration::from_secs(2u64.pow(attempt))).await;
-                    continue;
+            };
+            
+            let Some(delay) = retry.next_delay(None) else {
+                return Err(SourceError::Http(error));
+            };
+            warn!("Download of {} interrupted after {} bytes: {}, retrying in {:.1?}", url, data.len(), error, delay);
+            sleep(delay).await;
+            
+            let mut headers = HeaderMap::new();
+            let offset = data.len() as u64;
+            if let (true, Some(validator)) = (resumable && offset > 0, validators.range_validator()) {
+                if let Ok(validator) = HeaderValue::from_str(validator) {
+                    headers.insert(RANGE, HeaderValue::from_str(&format!("bytes={}-", offset)).expect("valid range header"));
+                    headers.insert(IF_RANGE, validator);
                 }
-                Err(e) => return Err(SourceError::Http(e)),
+            }
+            
+            // The caller still holds the permit of the original request
+            response = self.send_with_retry(Method::GET, url, options, headers, false, retry).await?;
+            if response.status() != StatusCode::PARTIAL_CONTENT || utils::content_range_start(&response) != Some(offset) {
+                debug!("Restarting download of {} from the beginning", url);
+                data.clear();
+                guard.reset();
             }
         }
     }
 
+    /// Stream a response body, decompressing it when `compression` is gzip
+    /// 
+    /// The request is retried according to the retry policy but the body is
+    /// not: an error while streaming ends the stream with that error. Size
+    /// limits apply as chunks arrive, `options.max_size` to the bytes
+    /// received and `options.max_decompressed_size` to the decompressed
+    /// output. The rate limiter permit is held until the stream is dropped.
+    /// 
+    /// Only gzip can be decompressed as a stream; other formats are rejected.
+    pub async fn fetch_stream(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+        compression: Option<CompressionType>,
+    ) -> SourceResult<ByteStream> {
+        let decompress = match compression {
+            None | Some(CompressionType::None) => false,
+            Some(CompressionType::Gzip) => true,
+            Some(other) => {
+                return Err(SourceError::Other(format!(
+                    "Streaming decompression of {:?} files is not supported",
+                    other
+                )));
+            }
+        };
+        
+        let (response, permit) = self.get_with_permit(url, options, HeaderMap::new()).await?;
+        Self::check_content_length(&response, options)?;
+        
+        // Decompressed output is measured directly when we decompress it
+        let mut limits = options.clone();
+        if decompress {
+            limits.max_decompressed_size = None;
+        }
+        let mut guard = SizeGuard::new(&limits);
+        let received = Arc::new(AtomicU64::new(0));
+        let received_in_body = Arc::clone(&received);
+        let throttle = self.throttle.clone();
+        
+        let body = response.bytes_stream().then(move |chunk| {
+            // Keep the permit for as long as the body is being read
+            let _permit = &permit;
+            let chunk = chunk
+                .map_err(SourceError::Http)
+                .and_then(|chunk| guard.update(&chunk).map(|_| chunk));
+            received_in_body.store(guard.received(), Ordering::Relaxed);
+            
+            let throttle = throttle.clone();
+            async move {
+                if let Ok(chunk) = &chunk {
+                    throttle.consume(chunk.len()).await;
+                }
+                chunk
+            }
+        });
+        
+        if !decompress {
+            return Ok(fuse_on_error(body).boxed());
+        }
+        
+        let reader = StreamReader::new(fuse_on_error(body).map(|chunk| chunk.map_err(io::Error::other)));
+        let mut decoder = GzipDecoder::new(reader);
+        decoder.multiple_members(true);
+        
+        let limit = options.max_decompressed_size;
+        let mut decompressed = 0u64;
+        let content = ReaderStream::new(decoder).map(move |chunk| {
+            let chunk = chunk.map_err(into_source_error)?;
+            decompressed += chunk.len() as u64;
+            match limit {
+                Some(limit) if decompressed > limit => Err(SourceError::DecompressedSizeLimitExceeded {
+                    limit,
+                    received: received.load(Ordering::Relaxed),
+                }),
+                _ => Ok(chunk),
+            }
+        });
+        
+        Ok(fuse_on_error(content).boxed())
+    }
+    
+    /// Check a response's Content-Length against `options.max_size`
+    pub fn check_content_length(response: &Response, options: &FetchOptions) -> SourceResult<()> {
+        if let Some(max_size) = options.max_size {
+            if let Some(content_length) = response.headers()
+                .get("content-length")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+            {
+                if content_length > max_size {
+                    return Err(SourceError::SizeLimitExceeded {
+                        limit: max_size,
+                        received: 0,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+    
     /// Download a file with progress tracking
     pub async fn download_file(
         &self,
@@ -117,30 +549,197 @@ impl HttpClient {
         path: &Path,
         progress: Option<ProgressCallback>,
     ) -> SourceResult<()> {
-        let response = self.get(url).await?;
+        self.download_file_with_options(url, path, &self.default_options(), progress).await
+    }
+    
+    /// Download a file with progress tracking using the given options
+    /// 
+    /// Data is written to `<path>.part`, with the response validators kept in
+    /// `<path>.part.meta`, and moved to `path` once complete, so `path` never
+    /// holds a truncated file. Before the rename the part file is checked
+    /// against the `Content-Length` and, when the server advertises one, the
+    /// digest of the response, then synced to disk. If a previous
+    /// download of the same URL was interrupted, it is continued with a `Range`
+    /// request guarded by `If-Range`, so the download restarts from the
+    /// beginning when the server no longer serves the same object or does not
+    /// support ranges.
+    pub async fn download_file_with_options(
+        &self,
+        url: &str,
+        path: &Path,
+        options: &FetchOptions,
+        progress: Option<ProgressCallback>,
+    ) -> SourceResult<()> {
+        self.download_file_if_modified(url, path, options, None, progress).await?;
+        Ok(())
+    }
+    
+    /// Download a file unless it is unchanged since `validators` were recorded
+    /// 
+    /// Sends `If-None-Match`/`If-Modified-Since` built from `validators`.
+    /// When the server answers `304 Not Modified`, `path` is left untouched
+    /// and the validators of the `304` response are returned, so the cached
+    /// copy can be refreshed with them.
+    pub async fn download_file_if_modified(
+        &self,
+        url: &str,
+        path: &Path,
+        options: &FetchOptions,
+        validators: Option<&CacheValidators>,
+        progress: Option<ProgressCallback>,
+    ) -> SourceResult<ConditionalDownload> {
+        let mut retry = self.retry_state(options);
         
-        let total_size = response
-            .headers()
-            .get("content-length")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.parse::<u64>().ok());
-
-        if let Some(total) = total_size {
-            info!("Downloading {} bytes to {:?}", total, path);
+        let result = loop {
+            match self.download_attempt(url, path, options, validators, progress.as_ref(), &mut retry).await {
+                Ok(DownloadAttempt::NotModified(validators)) => break Ok(ConditionalDownload::NotModified(validators)),
+                Ok(DownloadAttempt::Complete(validators)) => break Ok(ConditionalDownload::Downloaded(validators)),
+                Ok(DownloadAttempt::Interrupted(error)) => match retry.next_delay(None) {
+                    Some(delay) => {
+                        warn!("Download of {} interrupted: {}, resuming in {:.1?}", url, error, delay);
+                        sleep(delay).await;
+                    }
+                    None => break Err(error),
+                },
+                Err(error) => break Err(error),
+            }
+        };
+        
+        if result.is_err() {
+            self.discard_partial_download(url, path).await;
         }
-
+        result
+    }
+    
+    /// Remove the temporary files of a failed download of `url` to `path`
+    /// 
+    /// A `.part` file that a later attempt can resume is kept; anything else,
+    /// including a part file without a usable validator, is deleted.
+    pub async fn discard_partial_download(&self, url: &str, path: &Path) {
+        let part_path = utils::part_path(path);
+        let meta_path = utils::part_meta_path(path);
+        if PartialDownload::load(url, &part_path, &meta_path).await.is_none() {
+            utils::remove_partial_download(path).await;
+        }
+    }
+    
+    /// Make one attempt at downloading a file, resuming a previous one if possible
+    async fn download_attempt(
+        &self,
+        url: &str,
+        path: &Path,
+        options: &FetchOptions,
+        validators: Option<&CacheValidators>,
+        progress: Option<&ProgressCallback>,
+        retry: &mut RetryState<'_>,
+    ) -> SourceResult<DownloadAttempt> {
         // Create parent directory if it doesn't exist
         if let Some(parent) = path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
-
-        let mut file = File::create(path).await?;
-        let mut downloaded = 0u64;
+        
+        let part_path = utils::part_path(path);
+        let meta_path = utils::part_meta_path(path);
+        let resume = PartialDownload::load(url, &part_path, &meta_path).await;
+        
+        // Ranges refer to the bytes as served, so transparent decoding must be
+        // off for a resumed file to line up with the part already on disk
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+        if let Some(validators) = validators {
+            headers.extend(validators.conditional_headers());
+        }
+        if let Some((partial, offset)) = &resume {
+            if let Some(validator) = partial.validators.range_validator().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(RANGE, HeaderValue::from_str(&format!("bytes={}-", offset)).expect("valid range header"));
+                headers.insert(IF_RANGE, validator);
+            }
+        }
+        
+        let (response, permit) = self.get_retrying(url, options, headers, retry).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            debug!("Not modified: {}", url);
+            return Ok(DownloadAttempt::NotModified(CacheValidators::from_response(&response)));
+        }
+        
+        let resumed = match resume {
+            Some((partial, offset))
+                if response.status() == StatusCode::PARTIAL_CONTENT
+                    && partial.validators.matches(&response)
+                    && utils::content_range_start(&response) == Some(offset) =>
+            {
+                Some((partial, offset))
+            }
+            Some(_) => {
+                info!("Server did not resume {}, restarting download", url);
+                None
+            }
+            None => None,
+        };
+        
+        let mut guard = SizeGuard::new(options);
+        let (mut file, mut downloaded, partial) = match resumed {
+            Some((partial, offset)) => {
+                if let (Some(max_size), Some(total)) = (options.max_size, partial.validators.content_length) {
+                    if total > max_size {
+                        utils::remove_partial_download(path).await;
+                        return Err(SourceError::SizeLimitExceeded {
+                            limit: max_size,
+                            received: 0,
+                        });
+                    }
+                }
+                // Only a decompressed size limit needs the stored bytes inflated
+                let counted = if options.max_decompressed_size.is_some() {
+                    guard.update_from_file(&part_path).await
+                } else {
+                    guard.skip(offset)
+                };
+                if let Err(e) = counted {
+                    utils::remove_partial_download(path).await;
+                    return Err(e);
+                }
+                
+                info!("Resuming download of {} at byte {}", url, offset);
+                let file = OpenOptions::new().append(true).open(&part_path).await?;
+                (file, offset, partial)
+            }
+            None => {
+                Self::check_content_length(&response, options)?;
+                
+                let partial = PartialDownload::from_response(url, &response);
+                partial.save(&meta_path).await?;
+                
+                if let Some(ranges) = Self::parallel_ranges(&response, &partial.validators, options) {
+                    return self
+                        .download_ranges(url, path, options, partial, ranges, (response, permit), progress, retry)
+                        .await;
+                }
+                (File::create(&part_path).await?, 0, partial)
+            }
+        };
+        
+        let total_size = partial.validators.content_length;
+        if let Some(total) = total_size {
+            info!("Downloading {} bytes to {:?}", total, path);
+        }
+        
         let mut stream = response.bytes_stream();
 
-        use futures_util::StreamExt;
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(SourceError::Http)?;
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    file.flush().await?;
+                    return Ok(DownloadAttempt::Interrupted(SourceError::Http(e)));
+                }
+            };
+            if let Err(e) = guard.update(&chunk) {
+                drop(file);
+                utils::remove_partial_download(path).await;
+                return Err(e);
+            }
+            self.throttle.consume(chunk.len()).await;
             file.write_all(&chunk).await?;
             downloaded += chunk.len() as u64;
 
@@ -150,27 +749,462 @@ impl HttpClient {
         }
 
         file.flush().await?;
+        file.sync_all().await?;
+        drop(file);
+        
+        if let Some(total) = total_size {
+            if downloaded < total {
+                return Ok(DownloadAttempt::Interrupted(SourceError::Other(format!(
+                    "Incomplete download of {}: received {} of {} bytes",
+                    url, downloaded, total
+                ))));
+            }
+        }
+        
+        self.finish_download(url, path, partial, downloaded).await
+    }
+    
+    /// Verify a complete part file and move it into place
+    async fn finish_download(
+        &self,
+        url: &str,
+        path: &Path,
+        partial: PartialDownload,
+        downloaded: u64,
+    ) -> SourceResult<DownloadAttempt> {
+        let part_path = utils::part_path(path);
+        let meta_path = utils::part_meta_path(path);
+        
+        if let Some(total) = partial.validators.content_length {
+            if downloaded > total {
+                utils::remove_partial_download(path).await;
+                return Ok(DownloadAttempt::Interrupted(SourceError::Integrity(format!(
+                    "{}: received {} bytes, expected {}",
+                    url, downloaded, total
+                ))));
+            }
+        }
+        
+        if let Some(expected) = &partial.digest {
+            let actual = expected.algorithm.digest_file(&part_path).await?;
+            if actual != expected.value {
+                utils::remove_partial_download(path).await;
+                return Ok(DownloadAttempt::Interrupted(SourceError::Integrity(format!(
+                    "{}: {} digest {} does not match advertised {}",
+                    url, expected.algorithm.name(), actual, expected.value
+                ))));
+            }
+        }
+        
+        tokio::fs::rename(&part_path, path).await?;
+        sync_parent_dir(path).await;
+        let _ = tokio::fs::remove_file(&meta_path).await;
         info!("Download complete: {:?}", path);
 
+        Ok(DownloadAttempt::Complete(CacheValidators {
+            content_length: Some(downloaded),
+            ..partial.validators
+        }))
+    }
+    
+    /// Split a fresh download into ranges, if the response allows it
+    /// 
+    /// Requires `options.parallel_ranges` above one and a response that
+    /// advertises `Accept-Ranges: bytes` with a known length and a validator,
+    /// so every range is guaranteed to come from the same object. Ranges are
+    /// at least [`MIN_RANGE_SIZE`] long.
+    fn parallel_ranges(
+        response: &Response,
+        validators: &CacheValidators,
+        options: &FetchOptions,
+    ) -> Option<Vec<Range<u64>>> {
+        let accepts_ranges = response
+            .headers()
+            .get(ACCEPT_RANGES)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+        if response.status() != StatusCode::OK || !accepts_ranges || validators.range_validator().is_none() {
+            return None;
+        }
+        
+        let total = validators.content_length?;
+        let count = (options.parallel_ranges? as u64).min(total / MIN_RANGE_SIZE);
+        if count < 2 {
+            return None;
+        }
+        
+        let size = total.div_ceil(count);
+        Some((0..count).map(|i| i * size..((i + 1) * size).min(total)).collect())
+    }
+    
+    /// Download a file as concurrent ranges into a preallocated part file
+    /// 
+    /// The first range is read from the body of `first`, the response that
+    /// started the download; the others are fetched with `Range` requests
+    /// guarded by `If-Range`. Each range is retried on its own with the
+    /// retries and deadline left in `retry`.
+    #[allow(clippy::too_many_arguments)]
+    async fn download_ranges(
+        &self,
+        url: &str,
+        path: &Path,
+        options: &FetchOptions,
+        partial: PartialDownload,
+        ranges: Vec<Range<u64>>,
+        first: (Response, Option<RateLimitPermit>),
+        progress: Option<&ProgressCallback>,
+        retry: &RetryState<'_>,
+    ) -> SourceResult<DownloadAttempt> {
+        let part_path = utils::part_path(path);
+        let total = ranges.last().map_or(0, |range| range.end);
+        info!("Downloading {} bytes to {:?} in {} ranges", total, path, ranges.len());
+        
+        let file = File::create(&part_path).await?;
+        file.set_len(total).await?;
+        
+        let job = RangeDownload {
+            url,
+            part_path: &part_path,
+            options,
+            validator: partial.validators.range_validator().unwrap_or_default(),
+            total,
+            downloaded: AtomicU64::new(0),
+            progress,
+        };
+        
+        let mut first = Some(first);
+        let result = future::try_join_all(
+            ranges
+                .into_iter()
+                .map(|range| self.download_range(&job, range, first.take(), retry.split())),
+        )
+        .await;
+        
+        match result {
+            Ok(_) => {}
+            Err(error @ SourceError::Integrity(_)) => {
+                // The object changed under us, start over
+                utils::remove_partial_download(path).await;
+                return Ok(DownloadAttempt::Interrupted(error));
+            }
+            Err(error) => return Err(error),
+        }
+        
+        // Ranges arrive out of order, so the decompressed size is only
+        // checked once the file is assembled
+        if options.max_decompressed_size.is_some() {
+            if let Err(e) = SizeGuard::new(options).update_from_file(&part_path).await {
+                utils::remove_partial_download(path).await;
+                return Err(e);
+            }
+        }
+        
+        file.sync_all().await?;
+        drop(file);
+        self.finish_download(url, path, partial, total).await
+    }
+    
+    /// Download one range of a parallel download, retrying it until complete
+    async fn download_range(
+        &self,
+        job: &RangeDownload<'_>,
+        range: Range<u64>,
+        mut started: Option<(Response, Option<RateLimitPermit>)>,
+        mut retry: RetryState<'_>,
+    ) -> SourceResult<()> {
+        let mut file = OpenOptions::new().write(true).open(job.part_path).await?;
+        let mut position = range.start;
+        
+        loop {
+            let (response, _permit) = match started.take() {
+                Some(started) => started,
+                None => {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+                    headers.insert(
+                        RANGE,
+                        HeaderValue::from_str(&format!("bytes={}-{}", position, range.end - 1)).expect("valid range header"),
+                    );
+                    if let Ok(validator) = HeaderValue::from_str(job.validator) {
+                        headers.insert(IF_RANGE, validator);
+                    }
+                    
+                    let (response, permit) = self.get_retrying(job.url, job.options, headers, &mut retry).await?;
+                    if response.status() != StatusCode::PARTIAL_CONTENT
+                        || utils::content_range_start(&response) != Some(position)
+                    {
+                        return Err(SourceError::Integrity(format!(
+                            "{} changed during a ranged download",
+                            job.url
+                        )));
+                    }
+                    (response, permit)
+                }
+            };
+            
+            file.seek(SeekFrom::Start(position)).await?;
+            let mut stream = response.bytes_stream();
+            let error = loop {
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        // The first range reads from a response for the whole file
+                        let len = (range.end - position).min(chunk.len() as u64);
+                        self.throttle.consume(len as usize).await;
+                        file.write_all(&chunk[..len as usize]).await?;
+                        position += len;
+                        
+                        let done = job.downloaded.fetch_add(len, Ordering::Relaxed) + len;
+                        if let Some(callback) = job.progress {
+                            callback(done, job.total);
+                        }
+                        if position == range.end {
+                            file.flush().await?;
+                            return Ok(());
+                        }
+                    }
+                    Some(Err(e)) => break SourceError::Http(e),
+                    None => {
+                        break SourceError::Other(format!(
+                            "Range of {} ended at byte {} of {}",
+                            job.url, position, range.end
+                        ))
+                    }
+                }
+            };
+            
+            let Some(delay) = retry.next_delay(None) else {
+                return Err(error);
+            };
+            warn!("Range {}-{} of {} interrupted: {}, resuming in {:.1?}", range.start, range.end, job.url, error, delay);
+            sleep(delay).await;
+        }
+    }
+}
+
+/// Smallest range a parallel download is split into
+pub const MIN_RANGE_SIZE: u64 = 1 << 20;
+
+/// Shared state of a parallel ranged download
+struct RangeDownload<'a> {
+    url: &'a str,
+    part_path: &'a Path,
+    options: &'a FetchOptions,
+    validator: &'a str,
+    total: u64,
+    /// Bytes written across all ranges
+    downloaded: AtomicU64,
+    progress: Option<&'a ProgressCallback>,
+}
+
+/// Sidecar metadata for a partially downloaded file
+/// 
+/// Records the validators of the response the `.part` file was started from,
+/// so a later attempt can check the server still serves the same object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PartialDownload {
+    url: String,
+    #[serde(flatten)]
+    validators: CacheValidators,
+    /// Digest of the complete file advertised by the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    digest: Option<ContentDigest>,
+}
+
+impl PartialDownload {
+    fn from_response(url: &str, response: &Response) -> Self {
+        Self {
+            url: url.to_string(),
+            validators: CacheValidators::from_response(response),
+            digest: ContentDigest::from_response(response),
+        }
+    }
+    
+    /// Load the state of an interrupted download of `url`, with the number of
+    /// bytes already on disk
+    async fn load(url: &str, part_path: &Path, meta_path: &Path) -> Option<(Self, u64)> {
+        let meta = tokio::fs::read(meta_path).await.ok()?;
+        let partial: Self = serde_json::from_slice(&meta).ok()?;
+        let offset = tokio::fs::metadata(part_path).await.ok()?.len();
+        
+        let resumable = partial.url == url
+            && partial.validators.range_validator().is_some()
+            && offset > 0
+            && partial.validators.content_length.is_none_or(|total| offset < total);
+        
+        resumable.then_some((partial, offset))
+    }
+    
+    async fn save(&self, meta_path: &Path) -> SourceResult<()> {
+        let json = serde_json::to_vec(self).map_err(|e| SourceError::Parse(e.to_string()))?;
+        tokio::fs::write(meta_path, json).await?;
         Ok(())
     }
 }
 
+/// Hash algorithms accepted in digest headers, strongest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DigestAlgorithm {
+    Sha256,
+    Md5,
+}
+
+impl DigestAlgorithm {
+    fn name(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha-256",
+            DigestAlgorithm::Md5 => "md5",
+        }
+    }
+    
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "sha-256" => Some(DigestAlgorithm::Sha256),
+            "md5" => Some(DigestAlgorithm::Md5),
+            _ => None,
+        }
+    }
+    
+    /// Hex-encoded digest of a file's content
+    async fn digest_file(self, path: &Path) -> SourceResult<String> {
+        match self {
+            DigestAlgorithm::Sha256 => hash_file::<Sha256>(path).await,
+            DigestAlgorithm::Md5 => hash_file::<Md5>(path).await,
+        }
+    }
+}
+
+/// Digest of a response body advertised by the server
+/// 
+/// Read from `Repr-Digest` (RFC 9530), `Digest` (RFC 3230) or `Content-MD5`,
+/// preferring SHA-256 when several are present. Only full responses are
+/// considered, since a digest on a `206` may cover just the range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ContentDigest {
+    algorithm: DigestAlgorithm,
+    /// Hex-encoded digest value
+    value: String,
+}
+
+impl ContentDigest {
+    fn from_response(response: &Response) -> Option<Self> {
+        if response.status() != StatusCode::OK {
+            return None;
+        }
+        
+        let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok());
+        let mut digests = Vec::new();
+        
+        // Repr-Digest: sha-256=:<base64>:, sha-512=:<base64>:
+        if let Some(value) = header("repr-digest") {
+            for member in value.split(',') {
+                if let Some((name, encoded)) = member.split_once('=') {
+                    digests.extend(Self::decode(name, encoded.trim().trim_matches(':')));
+                }
+            }
+        }
+        // Digest: SHA-256=<base64>, MD5=<base64>
+        if let Some(value) = header("digest") {
+            for member in value.split(',') {
+                if let Some((name, encoded)) = member.split_once('=') {
+                    digests.extend(Self::decode(name, encoded.trim()));
+                }
+            }
+        }
+        if let Some(value) = header("content-md5") {
+            digests.extend(Self::decode("md5", value.trim()));
+        }
+        
+        digests.into_iter().min_by_key(|digest| digest.algorithm as u8)
+    }
+    
+    fn decode(name: &str, encoded: &str) -> Option<Self> {
+        let algorithm = DigestAlgorithm::from_name(name)?;
+        let bytes = BASE64.decode(encoded).ok()?;
+        Some(Self {
+            algorithm,
+            value: bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        })
+    }
+}
+
+/// Compute the hex-encoded digest of a file
+async fn hash_file<D: Digest>(path: &Path) -> SourceResult<String> {
+    let mut file = File::open(path).await?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Flush a rename in `path`'s directory to disk
+/// 
+/// Best effort: directories cannot be opened for syncing on every platform.
+async fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(parent).await {
+            let _ = dir.sync_all().await;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Outcome of [`HttpClient::download_file_if_modified`]
+#[derive(Debug, Clone)]
+pub enum ConditionalDownload {
+    /// The server answered `304 Not Modified` with these validators
+    NotModified(CacheValidators),
+    
+    /// The file was downloaded; the validators describe the new content
+    Downloaded(CacheValidators),
+}
+
+/// Outcome of a single download attempt
+enum DownloadAttempt {
+    /// The server answered `304 Not Modified` with these validators
+    NotModified(CacheValidators),
+    
+    /// The file was downloaded completely
+    Complete(CacheValidators),
+    
+    /// The body stream failed or the content did not verify; a part file
+    /// that can be resumed is kept
+    Interrupted(SourceError),
+}
+
 /// Utility functions for MRF file handling
 pub mod utils {
     use super::*;
     
     /// Detect MRF file type from URL or filename
+    /// 
+    /// Matches whole words of the path, so `toc` matches `plan_toc.json` but
+    /// not `protocol`. Use [`sniff`](crate::sources::sniff) to detect the
+    /// type from the content.
     pub fn detect_file_type(url: &str) -> super::super::MrfFileType {
-        let lower = url.to_lowercase();
+        let path = url_path(url);
+        let words: Vec<&str> = path
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.strip_suffix('s').unwrap_or(word))
+            .collect();
+        let has = |phrase: &[&str]| words.windows(phrase.len()).any(|window| window == phrase);
         
-        if lower.contains("table-of-contents") || lower.contains("toc") {
+        if has(&["table", "of", "content"]) || has(&["toc"]) {
             super::super::MrfFileType::TableOfContents
-        } else if lower.contains("in-network") || lower.contains("negotiated") {
+        } else if has(&["in", "network"]) || has(&["negotiated"]) {
             super::super::MrfFileType::InNetwork
-        } else if lower.contains("allowed-amount") || lower.contains("out-of-network") {
+        } else if has(&["allowed", "amount"]) || has(&["out", "of", "network"]) {
             super::super::MrfFileType::AllowedAmount
-        } else if lower.contains("provider-reference") || lower.contains("provider_reference") {
+        } else if has(&["provider", "reference"]) {
             super::super::MrfFileType::ProviderReference
         } else {
             super::super::MrfFileType::Unknown
@@ -178,54 +1212,93 @@ pub mod utils {
     }
     
     /// Detect compression type from URL or headers
+    /// 
+    /// Falls back to no compression when neither gives a hint.
     pub fn detect_compression(url: &str, content_type: Option<&str>) -> super::super::CompressionType {
-        let lower = url.to_lowercase();
+        compression_hint(url, content_type).unwrap_or(super::super::CompressionType::None)
+    }
+    
+    /// Compression suggested by the URL extension or the content type
+    /// 
+    /// A compressed extension wins over the content type, which wins over a
+    /// `.json` extension. Returns `None` when there is no hint at all.
+    pub fn compression_hint(url: &str, content_type: Option<&str>) -> Option<super::super::CompressionType> {
+        let path = url_path(url);
+        let content_type = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|ct| ct.trim().to_lowercase());
         
-        if lower.ends_with(".gz") || lower.ends_with(".gzip") {
-            super::super::CompressionType::Gzip
-        } else if lower.ends_with(".zip") {
-            super::super::CompressionType::Zip
-        } else if lower.ends_with(".bz2") || lower.ends_with(".bzip2") {
-            super::super::CompressionType::Bzip2
+        if path.ends_with(".gz") || path.ends_with(".gzip") {
+            Some(super::super::CompressionType::Gzip)
+        } else if path.ends_with(".zip") {
+            Some(super::super::CompressionType::Zip)
+        } else if path.ends_with(".bz2") || path.ends_with(".bzip2") {
+            Some(super::super::CompressionType::Bzip2)
         } else if let Some(ct) = content_type {
-            match ct {
-                "application/gzip" | "application/x-gzip" => super::super::CompressionType::Gzip,
-                "application/zip" => super::super::CompressionType::Zip,
-                "application/x-bzip2" => super::super::CompressionType::Bzip2,
-                _ => super::super::CompressionType::None,
+            match ct.as_str() {
+                "application/gzip" | "application/x-gzip" => Some(super::super::CompressionType::Gzip),
+                "application/zip" => Some(super::super::CompressionType::Zip),
+                "application/x-bzip2" => Some(super::super::CompressionType::Bzip2),
+                _ => Some(super::super::CompressionType::None),
             }
+        } else if path.ends_with(".json") {
+            Some(super::super::CompressionType::None)
         } else {
-            super::super::CompressionType::None
+            None
         }
     }
     
-    /// Generate a cache key for a file
-    pub fn cache_key(file_info: &MrfFileInfo) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut hasher = DefaultHasher::new();
-        file_info.url.hash(&mut hasher);
-        if let Some(modified) = &file_info.last_modified {
-            modified.timestamp().hash(&mut hasher);
+    /// Lowercase URL without query string and fragment
+    fn url_path(url: &str) -> String {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        path.to_lowercase()
+    }
+    
+    /// Path of the in-progress download for `path`
+    pub fn part_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".part");
+        PathBuf::from(name)
+    }
+    
+    /// Path of the metadata sidecar for the in-progress download for `path`
+    pub fn part_meta_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".part.meta");
+        PathBuf::from(name)
+    }
+    
+    /// Path of the temporary file used while copying to `path`
+    pub fn temp_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".tmp");
+        PathBuf::from(name)
+    }
+    
+    /// Delete the temporary files of a download to `path`, if any
+    pub async fn remove_partial_download(path: &Path) {
+        for temp in [part_path(path), part_meta_path(path), temp_path(path)] {
+            let _ = tokio::fs::remove_file(temp).await;
         }
-        
-        format!("{:x}", hasher.finish())
     }
     
-    /// Get cache path for a file
-    pub fn cache_path(cache_dir: &str, file_info: &MrfFileInfo) -> std::path::PathBuf {
-        let key = cache_key(file_info);
-        let extension = match file_info.compression {
-            Some(super::super::CompressionType::Gzip) => "json.gz",
-            Some(super::super::CompressionType::Zip) => "zip",
-            Some(super::super::CompressionType::Bzip2) => "json.bz2",
-            _ => "json",
-        };
-        
-        std::path::Path::new(cache_dir)
-            .join(&file_info.id)
-            .join(format!("{}.{}", key, extension))
+    /// First byte position of a `206 Partial Content` response
+    pub fn content_range_start(response: &Response) -> Option<u64> {
+        let value = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)?
+            .to_str()
+            .ok()?;
+        let range = value.strip_prefix("bytes ")?;
+        range.split('-').next()?.trim().parse().ok()
+    }
+    
+    /// Generate a stable cache key for a file
+    /// 
+    /// The key is the SHA-256 digest of the file's URL, see
+    /// [`DownloadCache::url_key`].
+    pub fn cache_key(file_info: &MrfFileInfo) -> String {
+        DownloadCache::url_key(&file_info.url)
     }
 }
 
@@ -235,6 +1308,9 @@ pub struct BaseSource {
     pub source_id: String,
     pub config: SourceConfig,
     pub http_client: HttpClient,
+    
+    /// Caches opened so far by directory, so their size tracking is shared
+    caches: Mutex<HashMap<PathBuf, DownloadCache>>,
 }
 
 impl BaseSource {
@@ -247,42 +1323,60 @@ impl BaseSource {
             source_id,
             config,
             http_client,
+            caches: Mutex::new(HashMap::new()),
         })
     }
     
-    /// Check if a file is cached and still valid
-    pub async fn check_cache(&self, file_info: &MrfFileInfo, options: &FetchOptions) -> Option<Vec<u8>> {
+    /// Resolve the cache directory for `options`, if caching is enabled
+    pub fn cache_dir(&self, options: &FetchOptions) -> Option<PathBuf> {
         if !options.use_cache {
             return None;
         }
         
-        let cache_dir = options.cache_dir.as_ref()
-            .or(self.config.default_options.as_ref()?.cache_dir.as_ref())?;
-        
-        let cache_path = utils::cache_path(cache_dir, file_info);
-        
-        // Use async metadata and read
-        if tokio::fs::try_exists(&cache_path).await.ok()? {
-            // Check if cache is still valid based on last_modified
-            if let (Ok(metadata), Some(last_modified)) = (
-                tokio::fs::metadata(&cache_path).await,
-                file_info.last_modified
-            ) {
-                if let Ok(modified_time) = metadata.modified() {
-                    let cache_time = modified_time
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .ok()?
-                        .as_secs();
-                    
-                    if cache_time > last_modified.timestamp() as u64 {
-                        debug!("Using cached file: {:?}", cache_path);
-                        return tokio::fs::read(&cache_path).await.ok();
-                    }
-                }
-            }
-        }
-        
-        None
+        options.cache_dir.as_ref()
+            .or(self.config.default_options.as_ref()?.cache_dir.as_ref())
+            .map(PathBuf::from)
+    }
+    
+    /// Open the download cache for `options`, if caching is enabled
+    pub fn cache(&self, options: &FetchOptions) -> Option<DownloadCache> {
+        let dir = self.cache_dir(options)?;
+        let mut caches = self.caches.lock().expect("cache registry lock poisoned");
+        let cache = caches
+            .entry(dir)
+            .or_insert_with_key(|dir| DownloadCache::new(dir.clone()).with_limits(self.config.cache_limits.clone()));
+        Some(cache.clone())
+    }
+    
+    /// Get the cache metadata of a file, regardless of freshness
+    pub async fn cache_entry(&self, file_info: &MrfFileInfo, options: &FetchOptions) -> Option<CacheMetadata> {
+        self.cache(options)?.lookup(&file_info.url).await
+    }
+    
+    /// Get the cache metadata of a file if it is younger than the cache TTL
+    /// and was fetched after the file's `last_modified` time, so it can be
+    /// used without asking the server
+    async fn fresh_cache_entry(&self, file_info: &MrfFileInfo, options: &FetchOptions) -> Option<CacheMetadata> {
+        let last_modified = file_info.last_modified?;
+        let cache = self.cache(options)?;
+        cache
+            .lookup(&file_info.url)
+            .await
+            .filter(|metadata| metadata.fetched_at > last_modified && cache.is_fresh(metadata))
+    }
+    
+    /// Get the path of a cached copy of a file, if one exists and is still valid
+    pub async fn cached_path(&self, file_info: &MrfFileInfo, options: &FetchOptions) -> Option<PathBuf> {
+        let metadata = self.fresh_cache_entry(file_info, options).await?;
+        let path = self.cache(options)?.verified_path(&metadata).await?;
+        debug!("Using cached file: {:?}", path);
+        Some(path)
+    }
+    
+    /// Check if a file is cached and still valid
+    pub async fn check_cache(&self, file_info: &MrfFileInfo, options: &FetchOptions) -> Option<Vec<u8>> {
+        let metadata = self.fresh_cache_entry(file_info, options).await?;
+        self.cache(options)?.read(&metadata).await
     }
     
     /// Save file to cache
@@ -296,58 +1390,258 @@ impl BaseSource {
             return Ok(());
         }
         
-        let cache_dir = options.cache_dir.as_ref()
-            .or(self.config.default_options.as_ref().and_then(|o| o.cache_dir.as_ref()))
+        let cache = self.cache(options)
+            .ok_or_else(|| SourceError::Config("No cache directory specified".to_string()))?;
+        cache.insert_bytes(&self.source_id, &file_info.url, data, CacheValidators::default()).await?;
+        
+        Ok(())
+    }
+    
+    /// Copy a downloaded file into the cache
+    pub async fn save_file_to_cache(
+        &self,
+        file_info: &MrfFileInfo,
+        path: &Path,
+        options: &FetchOptions,
+    ) -> SourceResult<()> {
+        if !options.use_cache {
+            return Ok(());
+        }
+        
+        let cache = self.cache(options)
             .ok_or_else(|| SourceError::Config("No cache directory specified".to_string()))?;
+        cache.insert_file(&self.source_id, &file_info.url, path, CacheValidators::default()).await?;
         
-        let cache_path = utils::cache_path(cache_dir, file_info);
+        Ok(())
+    }
+    
+    /// Fetch a file into memory through the cache
+    /// 
+    /// A cached copy is revalidated with a conditional request when its
+    /// metadata sidecar holds validators; a `304 Not Modified` answer is served
+    /// from the cache. Otherwise the file is downloaded and, when caching is
+    /// enabled, stored with the validators of the new response.
+    pub async fn fetch_file(&self, file_info: &MrfFileInfo, options: &FetchOptions) -> SourceResult<Vec<u8>> {
+        if let Some(data) = self.check_cache(file_info, options).await {
+            return Ok(data);
+        }
         
-        if let Some(parent) = cache_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
+        let cache = self.cache(options);
+        let cached = self.cache_entry(file_info, options).await
+            .filter(|metadata| !metadata.validators.is_empty());
+        let headers = cached
+            .as_ref()
+            .map(|metadata| metadata.validators.conditional_headers())
+            .unwrap_or_default();
+        
+        info!("Downloading file: {}", file_info.name);
+        let mut retry = self.http_client.retry_state(options);
+        let (response, permit) = self.http_client.get_retrying(&file_info.url, options, headers, &mut retry).await?;
+        
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let (Some(cache), Some(metadata)) = (&cache, &cached) {
+                if let Some(data) = cache.read(metadata).await {
+                    debug!("Cached copy of {} is still current", file_info.name);
+                    cache.touch(metadata, CacheValidators::from_response(&response)).await?;
+                    return Ok(data);
+                }
+            }
+            
+            // The cached copy was lost after revalidation, fetch it again
+            drop(permit);
+            let (response, _permit) = self
+                .http_client
+                .get_retrying(&file_info.url, options, HeaderMap::new(), &mut retry)
+                .await?;
+            return self.store_response(file_info, response, options, &mut retry).await;
+        }
+        
+        self.store_response(file_info, response, options, &mut retry).await
+    }
+    
+    /// Read a full response and store it in the cache
+    async fn store_response(
+        &self,
+        file_info: &MrfFileInfo,
+        response: Response,
+        options: &FetchOptions,
+        retry: &mut RetryState<'_>,
+    ) -> SourceResult<Vec<u8>> {
+        // Check file size if max_size is specified
+        HttpClient::check_content_length(&response, options)?;
+        
+        let validators = CacheValidators::from_response(&response);
+        let data = self.http_client.read_body_retrying(&file_info.url, options, response, retry).await?;
+        
+        if let Some(cache) = self.cache(options) {
+            if let Err(e) = cache.insert_bytes(&self.source_id, &file_info.url, &data, validators).await {
+                warn!("Failed to cache {}: {}", file_info.name, e);
+            }
+        }
+        
+        Ok(data)
+    }
+    
+    /// Fetch a file to `path` through the cache
+    /// 
+    /// Revalidates cached copies the same way as [`BaseSource::fetch_file`].
+    pub async fn fetch_file_to_path(
+        &self,
+        file_info: &MrfFileInfo,
+        path: &Path,
+        options: &FetchOptions,
+        progress: Option<ProgressCallback>,
+    ) -> SourceResult<()> {
+        if let Some(cache_path) = self.cached_path(file_info, options).await {
+            return copy_file(&cache_path, path).await;
         }
         
-        tokio::fs::write(&cache_path, data).await?;
-        debug!("Saved to cache: {:?}", cache_path);
+        let cache = self.cache(options);
+        let cached = self.cache_entry(file_info, options).await
+            .filter(|metadata| !metadata.validators.is_empty());
+        
+        let downloaded = self.http_client
+            .download_file_if_modified(
+                &file_info.url,
+                path,
+                options,
+                cached.as_ref().map(|metadata| &metadata.validators),
+                progress,
+            )
+            .await?;
+        
+        match downloaded {
+            ConditionalDownload::Downloaded(validators) => {
+                if let Some(cache) = &cache {
+                    if let Err(e) = cache.insert_file(&self.source_id, &file_info.url, path, validators).await {
+                        warn!("Failed to cache {}: {}", file_info.name, e);
+                    }
+                }
+                Ok(())
+            }
+            ConditionalDownload::NotModified(validators) => {
+                if let (Some(cache), Some(metadata)) = (&cache, &cached) {
+                    if let Some(cache_path) = cache.verified_path(metadata).await {
+                        debug!("Using cached copy of {}", file_info.name);
+                        cache.touch(metadata, validators).await?;
+                        return copy_file(&cache_path, path).await;
+                    }
+                }
+                
+                // The cached copy was lost after revalidation, fetch it again
+                self.http_client.download_file_with_options(&file_info.url, path, options, None).await
+            }
+        }
+    }
+    
+    /// Fill in the size, modification time, ETag and content type of files
+    /// with HEAD requests
+    /// 
+    /// At most `max_concurrent` requests are in flight. Values reported by the
+    /// server replace those already known; the ETag and content type are
+    /// added to each file's `metadata`. Files whose request fails are
+    /// returned unchanged. Deduplicated [`UniqueMrfFile`]s are accepted as
+    /// well as plain [`MrfFileInfo`]s.
+    /// 
+    /// [`UniqueMrfFile`]: super::discovery::UniqueMrfFile
+    pub async fn probe_files<F: AsMut<MrfFileInfo> + Send>(
+        &self,
+        files: Vec<F>,
+        options: &FetchOptions,
+        max_concurrent: usize,
+    ) -> Vec<F> {
+        futures_util::stream::iter(files)
+            .map(|mut file| async move {
+                let file_info = file.as_mut();
+                if let Err(e) = self.probe_file(file_info, options).await {
+                    debug!("HEAD request for {} failed: {}", file_info.url, e);
+                }
+                file
+            })
+            .buffered(max_concurrent.max(1))
+            .collect()
+            .await
+    }
+    
+    /// Fill in the metadata of one file with a HEAD request
+    pub async fn probe_file(&self, file_info: &mut MrfFileInfo, options: &FetchOptions) -> SourceResult<()> {
+        let response = self.http_client.head(&file_info.url, options).await?;
+        let header = |name| response.headers().get(name).and_then(|v: &HeaderValue| v.to_str().ok());
         
+        // The body of a HEAD response is empty, so read the header itself
+        if let Some(size) = header(CONTENT_LENGTH).and_then(|v| v.parse().ok()) {
+            file_info.size_bytes = Some(size);
+        }
+        if let Some(modified) = header(LAST_MODIFIED).and_then(|v| DateTime::parse_from_rfc2822(v).ok()) {
+            file_info.last_modified = Some(modified.with_timezone(&Utc));
+        }
+        if let serde_json::Value::Object(metadata) = &mut file_info.metadata {
+            if let Some(etag) = header(ETAG) {
+                metadata.insert("etag".to_string(), etag.into());
+            }
+            if let Some(content_type) = header(CONTENT_TYPE) {
+                metadata.insert("content_type".to_string(), content_type.into());
+            }
+        }
         Ok(())
     }
 }
 
-/// Simple rate limiter implementation
-struct RateLimiter {
-    rate: f64, // requests per second
-    last_request: tokio::sync::Mutex<Option<tokio::time::Instant>>,
+/// End a stream after its first error
+fn fuse_on_error<T, S>(stream: S) -> impl Stream<Item = SourceResult<T>>
+where
+    S: Stream<Item = SourceResult<T>>,
+{
+    stream.scan(false, |failed, item| {
+        if *failed {
+            return future::ready(None);
+        }
+        *failed = item.is_err();
+        future::ready(Some(item))
+    })
 }
 
-impl RateLimiter {
-    fn new(rate: f64) -> Self {
-        Self {
-            rate,
-            last_request: tokio::sync::Mutex::new(None),
-        }
+/// Recover a [`SourceError`] passed through an IO adapter
+fn into_source_error(error: io::Error) -> SourceError {
+    match error.get_ref().map(|inner| inner.is::<SourceError>()) {
+        Some(true) => *error
+            .into_inner()
+            .and_then(|inner| inner.downcast::<SourceError>().ok())
+            .expect("error wraps a SourceError"),
+        _ => SourceError::Io(error),
     }
+}
 
-    async fn acquire(&self) {
-        let mut last = self.last_request.lock().await;
-        let now = tokio::time::Instant::now();
-        
-        if let Some(last_time) = *last {
-            let elapsed = now.duration_since(last_time);
-            let min_interval = Duration::from_secs_f64(1.0 / self.rate);
-            
-            if elapsed < min_interval {
-                let wait_time = min_interval - elapsed;
-                sleep(wait_time).await;
-            }
-        }
-        
-        *last = Some(tokio::time::Instant::now());
+/// Copy a file, creating the destination directory if needed
+/// 
+/// The copy is written to a temporary file next to `to` and renamed into
+/// place once synced, so `to` is never left truncated.
+async fn copy_file(from: &Path, to: &Path) -> SourceResult<()> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    
+    let temp_path = utils::temp_path(to);
+    let result = async {
+        tokio::fs::copy(from, &temp_path).await?;
+        File::open(&temp_path).await?.sync_all().await?;
+        tokio::fs::rename(&temp_path, to).await?;
+        sync_parent_dir(to).await;
+        Ok(())
+    }
+    .await;
+    
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
     }
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sources::retry::RetryPolicy;
+    use crate::sources::FetchOverrides;
     
     #[test]
     fn test_detect_file_type() {
@@ -365,6 +1659,80 @@ mod tests {
         );
     }
     
+    #[test]
+    fn test_detect_file_type_matches_words() {
+        assert_eq!(
+            utils::detect_file_type("https://example.com/2025-06-01_plan_toc.json"),
+            super::super::MrfFileType::TableOfContents
+        );
+        assert_eq!(
+            utils::detect_file_type("https://example.com/Doctors-protocol_in-network-rates.json.gz?sig=toc"),
+            super::super::MrfFileType::InNetwork
+        );
+        assert_eq!(
+            utils::detect_file_type("https://example.com/out_of_network.json"),
+            super::super::MrfFileType::AllowedAmount
+        );
+        assert_eq!(
+            utils::detect_file_type("https://example.com/provider-references.json"),
+            super::super::MrfFileType::ProviderReference
+        );
+        assert_eq!(
+            utils::detect_file_type("https://example.com/Doctors.json"),
+            super::super::MrfFileType::Unknown
+        );
+    }
+    
+    #[test]
+    fn test_compression_hint() {
+        use super::super::CompressionType;
+        
+        let cases = [
+            ("https://example.com/file.json.gz", Some(CompressionType::Gzip)),
+            ("https://example.com/file.gzip", Some(CompressionType::Gzip)),
+            ("https://example.com/file.zip", Some(CompressionType::Zip)),
+            ("https://example.com/file.bz2", Some(CompressionType::Bzip2)),
+            ("https://example.com/file.bzip2", Some(CompressionType::Bzip2)),
+            ("https://example.com/file.json", Some(CompressionType::None)),
+            ("https://example.com/file.dat", None),
+            ("https://example.com/FILE.JSON.GZ", Some(CompressionType::Gzip)),
+            ("https://example.com/file.json.gz?undefined", Some(CompressionType::Gzip)),
+            (
+                "https://mrfstorageprod.blob.core.windows.net/public-mrf/2025-06-01/2025-06-01_allowed-amounts.json.gz?undefined",
+                Some(CompressionType::Gzip),
+            ),
+            ("https://example.com/file.json?param=value&other=123", Some(CompressionType::None)),
+            ("https://example.com/file.zip#section", Some(CompressionType::Zip)),
+            ("https://example.com/file.bz2?param=1#section", Some(CompressionType::Bzip2)),
+            // Directory names are not extensions; sniffing settles these
+            ("https://example.com/gzip/data", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(utils::compression_hint(url, None), expected, "{}", url);
+        }
+        
+        assert_eq!(
+            utils::compression_hint("https://example.com/data", Some("application/gzip; charset=binary")),
+            Some(CompressionType::Gzip)
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_sniff_remote_file() {
+        let mut server = mockito::Server::new_async().await;
+        let body = format!(r#"{{"reporting_entity_name": "Acme", "in_network": [{}]}}"#, "0,".repeat(SNIFF_LEN));
+        server.mock("GET", "/rates")
+            .match_header("range", "bytes=0-8191")
+            .with_body(body)
+            .create_async()
+            .await;
+        
+        let client = fast_retry_client();
+        let sniffed = client.sniff(&format!("{}/rates", server.url()), &FetchOptions::default()).await.unwrap();
+        assert_eq!(sniffed.compression, super::super::CompressionType::None);
+        assert_eq!(sniffed.file_type, super::super::MrfFileType::InNetwork);
+    }
+    
     #[test]
     fn test_detect_compression() {
         assert_eq!(
@@ -380,4 +1748,585 @@ mod tests {
             super::super::CompressionType::Gzip
         );
     }
+    
+    #[tokio::test]
+    async fn test_per_call_options_override_defaults() {
+        let mut server = mockito::Server::new_async().await;
+        let failing = server.mock("GET", "/error")
+            .with_status(500)
+            .expect(1)
+            .create_async()
+            .await;
+        server.mock("GET", "/file.json")
+            .with_body("0123456789")
+            .create_async()
+            .await;
+        
+        let client = HttpClient::new(SourceConfig {
+            rate_limit: None,
+            ..Default::default()
+        })
+        .unwrap();
+        
+        // No retries for this call, even though the default allows three
+        let options = client.resolve_options(Some(&FetchOptions {
+            max_retries: Some(0),
+            ..Default::default()
+        }));
+        assert!(client.get_with_options(&format!("{}/error", server.url()), &options).await.is_err());
+        failing.assert_async().await;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+        let options = FetchOptions {
+            max_size: Some(5),
+            ..Default::default()
+        };
+        let result = client
+            .download_file_with_options(&format!("{}/file.json", server.url()), &path, &options, None)
+            .await;
+        assert!(result.is_err());
+        
+        client.download_file(&format!("{}/file.json", server.url()), &path, None).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+    }
+    
+    #[tokio::test]
+    async fn test_parallel_range_download() {
+        use mockito::Matcher;
+        
+        let content: Vec<u8> = (0..3 * MIN_RANGE_SIZE).map(|i| (i % 251) as u8).collect();
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/file.json.gz")
+            .match_header("range", Matcher::Missing)
+            .with_header("etag", "\"v1\"")
+            .with_header("accept-ranges", "bytes")
+            .with_body(&content)
+            .create_async()
+            .await;
+        let failed = server.mock("GET", "/file.json.gz")
+            .match_header("range", "bytes=1048576-2097151")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let mut ranges = Vec::new();
+        for (start, end) in [(1048576, 2097151), (2097152, 3145727)] {
+            ranges.push(
+                server.mock("GET", "/file.json.gz")
+                    .match_header("range", format!("bytes={}-{}", start, end).as_str())
+                    .match_header("if-range", "\"v1\"")
+                    .with_status(206)
+                    .with_header("etag", "\"v1\"")
+                    .with_header("content-range", &format!("bytes {}-{}/{}", start, end, content.len()))
+                    .with_body(&content[start..=end])
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+        }
+        
+        let client = fast_retry_client();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json.gz");
+        let options = FetchOptions {
+            parallel_ranges: Some(4),
+            ..Default::default()
+        };
+        
+        let progress = Arc::new(AtomicU64::new(0));
+        let reported = Arc::clone(&progress);
+        let callback: ProgressCallback = Box::new(move |done, total| {
+            assert_eq!(total, 3 * MIN_RANGE_SIZE);
+            reported.fetch_max(done, Ordering::Relaxed);
+        });
+        
+        client
+            .download_file_with_options(&format!("{}/file.json.gz", server.url()), &path, &options, Some(callback))
+            .await
+            .unwrap();
+        
+        failed.assert_async().await;
+        for range in ranges {
+            range.assert_async().await;
+        }
+        assert_eq!(progress.load(Ordering::Relaxed), 3 * MIN_RANGE_SIZE);
+        assert!(std::fs::read(&path).unwrap() == content);
+        assert!(!utils::part_path(&path).exists());
+        assert!(!utils::part_meta_path(&path).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_resume_partial_download() {
+        let mut server = mockito::Server::new_async().await;
+        let resumed = server.mock("GET", "/file.json")
+            .match_header("range", "bytes=4-")
+            .match_header("if-range", "\"v1\"")
+            .with_status(206)
+            .with_header("etag", "\"v1\"")
+            .with_header("content-range", "bytes 4-9/10")
+            .with_body("456789")
+            .create_async()
+            .await;
+        
+        let url = format!("{}/file.json", server.url());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+        std::fs::write(utils::part_path(&path), b"0123").unwrap();
+        std::fs::write(
+            utils::part_meta_path(&path),
+            serde_json::json!({
+                "url": url,
+                "etag": "\"v1\"",
+                "last_modified": null,
+                "content_length": 10
+            })
+            .to_string(),
+        )
+        .unwrap();
+        
+        let client = HttpClient::new(SourceConfig::default()).unwrap();
+        client.download_file(&url, &path, None).await.unwrap();
+        
+        resumed.assert_async().await;
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+        assert!(!utils::part_path(&path).exists());
+        assert!(!utils::part_meta_path(&path).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_resume_falls_back_to_full_download() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/file.json")
+            .with_header("etag", "\"v2\"")
+            .with_body("abcdefghij")
+            .create_async()
+            .await;
+        
+        let url = format!("{}/file.json", server.url());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+        std::fs::write(utils::part_path(&path), b"0123").unwrap();
+        std::fs::write(
+            utils::part_meta_path(&path),
+            serde_json::json!({
+                "url": url,
+                "etag": "\"v1\"",
+                "last_modified": null,
+                "content_length": 10
+            })
+            .to_string(),
+        )
+        .unwrap();
+        
+        let client = HttpClient::new(SourceConfig::default()).unwrap();
+        client.download_file(&url, &path, None).await.unwrap();
+        
+        assert_eq!(std::fs::read(&path).unwrap(), b"abcdefghij");
+        assert!(!utils::part_path(&path).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_download_verifies_advertised_digest() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/good.json")
+            .with_header("repr-digest", "sha-256=:hNiYd/DUBB77a/kaFvAkjy/Vc+avBcGflr7bn4gveII=:")
+            .with_body("0123456789")
+            .create_async()
+            .await;
+        let corrupt = server.mock("GET", "/bad.json")
+            .with_header("content-md5", "eB5eJF1ptWaXm4bijSPyxw==")
+            .with_body("0123456788")
+            .expect(2)
+            .create_async()
+            .await;
+        
+        let client = fast_retry_client();
+        let dir = tempfile::tempdir().unwrap();
+        let options = FetchOptions {
+            max_retries: Some(1),
+            ..Default::default()
+        };
+        
+        let good = dir.path().join("good.json");
+        client
+            .download_file_with_options(&format!("{}/good.json", server.url()), &good, &options, None)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&good).unwrap(), b"0123456789");
+        
+        let bad = dir.path().join("bad.json");
+        let result = client
+            .download_file_with_options(&format!("{}/bad.json", server.url()), &bad, &options, None)
+            .await;
+        assert!(matches!(result, Err(SourceError::Integrity(_))));
+        corrupt.assert_async().await;
+        assert!(!bad.exists());
+        assert!(!utils::part_path(&bad).exists());
+        assert!(!utils::part_meta_path(&bad).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_failed_download_removes_unresumable_part() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/file.json")
+            .with_body("0123456789")
+            .create_async()
+            .await;
+        
+        let client = fast_retry_client();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+        let options = FetchOptions {
+            max_size: Some(5),
+            ..Default::default()
+        };
+        
+        std::fs::write(&path, b"previous").unwrap();
+        std::fs::write(utils::part_path(&path), b"0123").unwrap();
+        let result = client
+            .download_file_with_options(&format!("{}/file.json", server.url()), &path, &options, None)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"previous");
+        assert!(!utils::part_path(&path).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_max_size_enforced_without_content_length() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/file.json")
+            .with_chunked_body(|writer| writer.write_all(b"0123456789"))
+            .create_async()
+            .await;
+        
+        let url = format!("{}/file.json", server.url());
+        let client = fast_retry_client();
+        let options = FetchOptions {
+            max_size: Some(5),
+            ..Default::default()
+        };
+        
+        let response = client.get_with_options(&url, &options).await.unwrap();
+        assert!(response.content_length().is_none());
+        let result = client.read_body(&url, &options, response).await;
+        assert!(matches!(result, Err(SourceError::SizeLimitExceeded { limit: 5, received }) if received > 5));
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+        let result = client.download_file_with_options(&url, &path, &options, None).await;
+        assert!(matches!(result, Err(SourceError::SizeLimitExceeded { limit: 5, received }) if received > 5));
+        assert!(!path.exists());
+        assert!(!utils::part_path(&path).exists());
+        assert!(!utils::part_meta_path(&path).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_fetch_stream_decompresses_gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        
+        let content = b"{\"reporting_entity_name\": \"Acme\"}".repeat(1000);
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&content).unwrap();
+        let compressed = encoder.finish().unwrap();
+        
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/file.json.gz")
+            .with_body(&compressed)
+            .create_async()
+            .await;
+        
+        let url = format!("{}/file.json.gz", server.url());
+        let client = fast_retry_client();
+        let options = FetchOptions::default();
+        
+        let collect = |stream: ByteStream| async move {
+            let mut data = Vec::new();
+            let mut stream = stream;
+            while let Some(chunk) = stream.next().await {
+                data.extend_from_slice(&chunk?);
+            }
+            SourceResult::Ok(data)
+        };
+        
+        let raw = client.fetch_stream(&url, &options, None).await.unwrap();
+        assert_eq!(collect(raw).await.unwrap(), compressed);
+        
+        let decoded = client.fetch_stream(&url, &options, Some(CompressionType::Gzip)).await.unwrap();
+        assert_eq!(collect(decoded).await.unwrap(), content);
+        
+        let options = FetchOptions {
+            max_decompressed_size: Some(1000),
+            ..Default::default()
+        };
+        let limited = client.fetch_stream(&url, &options, Some(CompressionType::Gzip)).await.unwrap();
+        assert!(matches!(
+            collect(limited).await,
+            Err(SourceError::DecompressedSizeLimitExceeded { limit: 1000, .. })
+        ));
+        
+        assert!(client.fetch_stream(&url, &options, Some(CompressionType::Zip)).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_conditional_request_serves_cache_on_304() {
+        let mut server = mockito::Server::new_async().await;
+        let revalidated = server.mock("GET", "/file.json")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .with_header("etag", "\"v1\"")
+            .with_header("last-modified", "Wed, 01 Jan 2025 00:00:00 GMT")
+            .expect(2)
+            .create_async()
+            .await;
+        let full = server.mock("GET", "/file.json")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_header("etag", "\"v1\"")
+            .with_body("{\"version\":\"1.0.0\"}")
+            .expect(1)
+            .create_async()
+            .await;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let options = FetchOptions {
+            cache_dir: Some(dir.path().join("cache").to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let source = BaseSource::new("Test".to_string(), "test".to_string(), SourceConfig::default()).unwrap();
+        let file_info = MrfFileInfo {
+            id: "file".to_string(),
+            name: "file.json".to_string(),
+            url: format!("{}/file.json", server.url()),
+            file_type: super::super::MrfFileType::InNetwork,
+            size_bytes: None,
+            last_modified: None,
+            compression: None,
+            reporting_plans: Vec::new(),
+            metadata: Default::default(),
+        };
+        
+        let first = source.fetch_file(&file_info, &options).await.unwrap();
+        let second = source.fetch_file(&file_info, &options).await.unwrap();
+        assert_eq!(first, second);
+        
+        let path = dir.path().join("out").join("file.json");
+        source.fetch_file_to_path(&file_info, &path, &options, None).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), first);
+        
+        full.assert_async().await;
+        revalidated.assert_async().await;
+        
+        let metadata = source.cache_entry(&file_info, &options).await.unwrap();
+        assert_eq!(metadata.validators.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(metadata.validators.last_modified.as_deref(), Some("Wed, 01 Jan 2025 00:00:00 GMT"));
+        assert_eq!(metadata.validators.content_length, Some(first.len() as u64));
+    }
+    
+    /// Client with a fast retry policy for tests
+    fn fast_retry_client() -> HttpClient {
+        HttpClient::new(SourceConfig {
+            rate_limit: None,
+            retry_policy: RetryPolicy {
+                base_delay_ms: 1,
+                max_delay_ms: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap()
+    }
+    
+    /// Serve one scripted raw HTTP response per connection, returning the
+    /// request heads received
+    async fn scripted_server(responses: Vec<Vec<u8>>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::AsyncReadExt;
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0u8; 4096];
+                let read = socket.read(&mut buffer).await.unwrap();
+                requests.push(String::from_utf8_lossy(&buffer[..read]).to_lowercase());
+                socket.write_all(&response).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+    
+    #[tokio::test]
+    async fn test_proxy_and_default_headers() {
+        let (proxy_url, requests) = scripted_server(vec![
+            b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok".to_vec(),
+        ])
+        .await;
+        
+        let client = HttpClient::new(SourceConfig {
+            rate_limit: None,
+            proxy: Some(proxy_url),
+            no_proxy: vec!["internal.example".to_string()],
+            default_headers: [("X-Api-Key".to_string(), "secret".to_string())].into(),
+            ..Default::default()
+        })
+        .unwrap();
+        
+        let response = client.get("http://mrf.example/file.json").await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+        
+        let requests = requests.await.unwrap();
+        assert!(requests[0].starts_with("get http://mrf.example/file.json http/1.1"));
+        assert!(requests[0].contains("x-api-key: secret"));
+    }
+    
+    #[test]
+    fn test_invalid_client_settings_are_config_errors() {
+        let invalid = [
+            SourceConfig {
+                default_headers: [("bad header".to_string(), "value".to_string())].into(),
+                ..Default::default()
+            },
+            SourceConfig {
+                root_certificates: vec![PathBuf::from("/nonexistent/ca.pem")],
+                ..Default::default()
+            },
+            SourceConfig {
+                proxy: Some("not a url".to_string()),
+                ..Default::default()
+            },
+        ];
+        
+        for config in invalid {
+            assert!(matches!(HttpClient::new(config), Err(SourceError::Config(_))));
+        }
+    }
+    
+    #[test]
+    fn test_per_call_verify_ssl_overrides_insecure_default() {
+        let client = HttpClient::new(SourceConfig {
+            default_options: Some(FetchOptions {
+                verify_ssl: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        
+        // Unset per-call options inherit the insecure default client
+        client.client_for(&FetchOptions::default()).unwrap();
+        assert!(client.alternate_client.get().is_none());
+        
+        let secure = FetchOptions {
+            overrides: FetchOverrides {
+                verify_ssl: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(client.resolve_options(Some(&secure)).verify_ssl);
+        client.client_for(&secure).unwrap();
+        assert!(client.alternate_client.get().is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_retries_429_with_retry_after() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server.mock("GET", "/file.json")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create_async()
+            .await;
+        server.mock("GET", "/file.json")
+            .with_body("ok")
+            .create_async()
+            .await;
+        
+        let client = fast_retry_client();
+        let response = client.get(&format!("{}/file.json", server.url())).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+        limited.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_gives_up_on_retry_after_beyond_max_delay() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server.mock("GET", "/file.json")
+            .with_status(429)
+            .with_header("retry-after", "3600")
+            .expect(1)
+            .create_async()
+            .await;
+        
+        let client = fast_retry_client();
+        let result = client.get(&format!("{}/file.json", server.url())).await;
+        assert!(matches!(result, Err(SourceError::RateLimited(3600))));
+        limited.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_resumes_interrupted_body_stream() {
+        let (url, requests) = scripted_server(vec![
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\netag: \"v1\"\r\nconnection: close\r\n\r\n0123".to_vec(),
+            b"HTTP/1.1 206 Partial Content\r\ncontent-length: 6\r\ncontent-range: bytes 4-9/10\r\netag: \"v1\"\r\nconnection: close\r\n\r\n456789".to_vec(),
+        ])
+        .await;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+        let client = fast_retry_client();
+        client.download_file(&format!("{}/file.json", url), &path, None).await.unwrap();
+        
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+        let requests = requests.await.unwrap();
+        assert!(requests[1].contains("range: bytes=4-"));
+    }
+    
+    #[tokio::test]
+    async fn test_download_shares_one_retry_budget() {
+        let unavailable = b"HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_vec();
+        let (url, requests) = scripted_server(vec![
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\netag: \"v1\"\r\nconnection: close\r\n\r\n0123".to_vec(),
+            unavailable.clone(),
+            unavailable,
+        ])
+        .await;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+        let client = fast_retry_client();
+        let options = FetchOptions {
+            max_retries: Some(2),
+            ..Default::default()
+        };
+        
+        // The interruption and the failed resume use up the two retries, so
+        // no fourth request is made
+        let result = client.download_file_with_options(&format!("{}/file.json", url), &path, &options, None).await;
+        assert!(matches!(result, Err(SourceError::Other(message)) if message.contains("503")));
+        assert_eq!(requests.await.unwrap().len(), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_read_body_resumes_interrupted_stream() {
+        let (url, requests) = scripted_server(vec![
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\nlast-modified: Wed, 21 Oct 2015 07:28:00 GMT\r\nconnection: close\r\n\r\n01234".to_vec(),
+            b"HTTP/1.1 206 Partial Content\r\ncontent-length: 5\r\ncontent-range: bytes 5-9/10\r\nconnection: close\r\n\r\n56789".to_vec(),
+        ])
+        .await;
+        
+        let client = fast_retry_client();
+        let options = client.default_options();
+        let url = format!("{}/file.json", url);
+        let response = client.get(&url).await.unwrap();
+        let data = client.read_body(&url, &options, response).await.unwrap();
+        
+        assert_eq!(data, b"0123456789");
+        let requests = requests.await.unwrap();
+        assert!(requests[1].contains("if-range: wed, 21 oct 2015 07:28:00 gmt"));
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,893 @@
+//! Content-addressed download cache
+//! 
+//! Downloaded files are stored once per distinct content, keyed by the
+//! SHA-256 digest of their bytes. A URL index maps each downloaded URL to the
+//! digest of the content it last served, so identical files published under
+//! different URLs share a single object on disk.
+//! 
+//! # Layout
+//! 
+//! ```text
+//! <cache_dir>/
+//!   objects/<first two hex digits>/<sha256>   file content
+//!   index/<sha256 of the URL>.json            metadata sidecar for each URL
+//! ```
+//! 
+//! All keys are SHA-256 digests, so the layout is stable across Rust
+//! toolchains and platforms. Objects are verified against their digest when
+//! read; a corrupted object is removed and reported as a cache miss.
+//! 
+//! # Limits and Eviction
+//! 
+//! [`CacheLimits`] bound the cache by total size and by entry age. Entries
+//! older than the TTL are no longer served without asking the server, but are
+//! kept so their validators can be used to revalidate them. Inserting a file
+//! evicts the least recently used entries until the cache fits its size limit
+//! again; the total size is tracked as files are added and removed, so the
+//! cache is only rescanned when the limit may have been passed.
+//! [`DownloadCache::gc`] applies both limits and removes objects no longer
+//! referenced by any URL, [`DownloadCache::purge`] drops everything fetched
+//! through one source, and [`DownloadCache::stats`] reports disk usage.
+
+use super::{SourceError, SourceResult};
+use chrono::{DateTime, Utc};
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+
+/// HTTP validators of a downloaded response
+/// 
+/// Used to revalidate a cached copy with a conditional request instead of
+/// downloading it again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheValidators {
+    /// `ETag` response header
+    pub etag: Option<String>,
+    
+    /// `Last-Modified` response header
+    pub last_modified: Option<String>,
+    
+    /// Size of the response body in bytes
+    pub content_length: Option<u64>,
+}
+
+impl CacheValidators {
+    /// Read the validators from a response's headers
+    pub fn from_response(response: &Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+                .map(str::to_string)
+        };
+        
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            content_length: response.content_length(),
+        }
+    }
+    
+    /// Whether there is anything to revalidate with
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+    
+    /// Validator for an `If-Range` request header
+    /// 
+    /// Weak ETags cannot be used with ranges, so `Last-Modified` is used
+    /// instead when the ETag is weak.
+    pub fn range_validator(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+    
+    /// Whether a response carries the same ETag as these validators
+    /// 
+    /// Responses without an ETag, or validators without one, are assumed to
+    /// match.
+    pub fn matches(&self, response: &Response) -> bool {
+        let etag = response.headers().get(ETAG).and_then(|v| v.to_str().ok());
+        match (&self.etag, etag) {
+            (Some(expected), Some(actual)) => expected == actual,
+            _ => true,
+        }
+    }
+    
+    /// `If-None-Match` and `If-Modified-Since` headers for a conditional request
+    pub fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = self.etag.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = self.last_modified.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+            headers.insert(IF_MODIFIED_SINCE, modified);
+        }
+        headers
+    }
+}
+
+/// Metadata sidecar recorded for each cached URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheMetadata {
+    /// Identifier of the source the file was fetched through
+    pub source_id: String,
+    
+    /// URL the file was downloaded from
+    pub url: String,
+    
+    /// Hex-encoded SHA-256 digest of the content
+    pub digest: String,
+    
+    /// Size of the content in bytes
+    pub size: u64,
+    
+    /// When the file was downloaded or last revalidated
+    pub fetched_at: DateTime<Utc>,
+    
+    /// When the cached copy was last used, for LRU eviction
+    #[serde(default)]
+    pub accessed_at: Option<DateTime<Utc>>,
+    
+    /// Validators of the response the file was downloaded from
+    #[serde(flatten)]
+    pub validators: CacheValidators,
+}
+
+impl CacheMetadata {
+    /// When the cached copy was last fetched or used
+    pub fn last_used(&self) -> DateTime<Utc> {
+        self.accessed_at.map_or(self.fetched_at, |accessed| accessed.max(self.fetched_at))
+    }
+}
+
+/// Size and age limits of the download cache
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheLimits {
+    /// Maximum total size of cached content in bytes
+    pub max_size: Option<u64>,
+    
+    /// Time after which an entry must be fetched again, in seconds
+    pub ttl_secs: Option<u64>,
+}
+
+/// Disk usage of the download cache
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Number of cached URLs
+    pub entries: usize,
+    
+    /// Number of stored objects
+    pub objects: usize,
+    
+    /// Total size of stored objects in bytes
+    pub total_bytes: u64,
+    
+    /// Number of cached URLs older than the TTL
+    pub expired_entries: usize,
+    
+    /// Size of objects no URL refers to, reclaimable by [`DownloadCache::gc`]
+    pub unreferenced_bytes: u64,
+    
+    /// Usage by source identifier
+    pub sources: BTreeMap<String, SourceCacheStats>,
+}
+
+/// Disk usage of the files cached for one source
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceCacheStats {
+    /// Number of cached URLs
+    pub entries: usize,
+    
+    /// Size of the content of those URLs in bytes; content shared between
+    /// URLs is counted once per URL
+    pub bytes: u64,
+}
+
+/// Outcome of a cache cleanup
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcReport {
+    /// Number of URL entries removed
+    pub removed_entries: usize,
+    
+    /// Number of objects deleted
+    pub removed_objects: usize,
+    
+    /// Disk space reclaimed in bytes
+    pub freed_bytes: u64,
+}
+
+/// Objects and temporary files younger than this are never collected as
+/// orphans, as they may belong to a download that is still being recorded
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(3600);
+
+/// Content-addressed store of downloaded files
+#[derive(Debug, Clone)]
+pub struct DownloadCache {
+    root: PathBuf,
+    limits: CacheLimits,
+    
+    /// Total size of stored objects once it has been measured, shared
+    /// between clones. Changes made by other processes are only picked up
+    /// by the next rescan.
+    usage: Arc<Mutex<Option<u64>>>,
+}
+
+impl DownloadCache {
+    /// Open the cache rooted at `root`; directories are created on first write
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            limits: CacheLimits::default(),
+            usage: Arc::default(),
+        }
+    }
+    
+    /// Apply size and age limits to the cache
+    pub fn with_limits(mut self, limits: CacheLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+    
+    /// Limits applied to the cache
+    pub fn limits(&self) -> &CacheLimits {
+        &self.limits
+    }
+    
+    /// Root directory of the cache
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    
+    /// Stable index key for a URL
+    pub fn url_key(url: &str) -> String {
+        format!("{:x}", Sha256::digest(url.as_bytes()))
+    }
+    
+    /// Path of the object holding content with the given digest
+    pub fn object_path(&self, digest: &str) -> PathBuf {
+        let prefix = digest.get(..2).unwrap_or(digest);
+        self.root.join("objects").join(prefix).join(digest)
+    }
+    
+    /// Path of the metadata sidecar for a URL
+    pub fn index_path(&self, url: &str) -> PathBuf {
+        self.root.join("index").join(format!("{}.json", Self::url_key(url)))
+    }
+    
+    /// Look up the metadata recorded for a URL
+    /// 
+    /// Returns `None` when the URL is not cached or its object is missing.
+    /// Entries older than the TTL are returned as well, so that they can be
+    /// revalidated; check [`DownloadCache::is_fresh`] before serving one
+    /// without asking the server.
+    pub async fn lookup(&self, url: &str) -> Option<CacheMetadata> {
+        let json = tokio::fs::read(self.index_path(url)).await.ok()?;
+        let metadata: CacheMetadata = serde_json::from_slice(&json).ok()?;
+        if metadata.url != url {
+            return None;
+        }
+        if !tokio::fs::try_exists(self.object_path(&metadata.digest)).await.ok()? {
+            return None;
+        }
+        Some(metadata)
+    }
+    
+    /// Whether an entry is younger than the TTL
+    pub fn is_fresh(&self, metadata: &CacheMetadata) -> bool {
+        !self.is_expired(metadata, Utc::now())
+    }
+    
+    /// Read the cached content of a URL, verifying its digest
+    pub async fn read(&self, metadata: &CacheMetadata) -> Option<Vec<u8>> {
+        let data = tokio::fs::read(self.object_path(&metadata.digest)).await.ok()?;
+        let digest = format!("{:x}", Sha256::digest(&data));
+        if digest != metadata.digest {
+            self.discard_corrupt(metadata, &digest).await;
+            return None;
+        }
+        self.mark_accessed(metadata).await;
+        Some(data)
+    }
+    
+    /// Get the path of the cached content of a URL, verifying its digest
+    pub async fn verified_path(&self, metadata: &CacheMetadata) -> Option<PathBuf> {
+        let path = self.object_path(&metadata.digest);
+        let (digest, _) = hash_file(&path).await.ok()?;
+        if digest != metadata.digest {
+            self.discard_corrupt(metadata, &digest).await;
+            return None;
+        }
+        self.mark_accessed(metadata).await;
+        Some(path)
+    }
+    
+    /// Store downloaded bytes for a URL
+    pub async fn insert_bytes(
+        &self,
+        source_id: &str,
+        url: &str,
+        data: &[u8],
+        validators: CacheValidators,
+    ) -> SourceResult<CacheMetadata> {
+        let digest = format!("{:x}", Sha256::digest(data));
+        let object_path = self.object_path(&digest);
+        let size = data.len() as u64;
+        
+        let mut added = 0;
+        if !tokio::fs::try_exists(&object_path).await? {
+            let temp_path = self.temp_path();
+            create_parent(&temp_path).await?;
+            tokio::fs::write(&temp_path, data).await?;
+            self.commit_object(&temp_path, &object_path).await?;
+            added = size;
+        }
+        
+        self.record(source_id, url, digest, size, added, validators).await
+    }
+    
+    /// Store a downloaded file for a URL
+    /// 
+    /// The file is copied into the cache; `path` is left in place.
+    pub async fn insert_file(
+        &self,
+        source_id: &str,
+        url: &str,
+        path: &Path,
+        validators: CacheValidators,
+    ) -> SourceResult<CacheMetadata> {
+        let temp_path = self.temp_path();
+        create_parent(&temp_path).await?;
+        
+        // Hash while copying so the file is only read once
+        let mut input = tokio::fs::File::open(path).await?;
+        let mut output = tokio::fs::File::create(&temp_path).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+        let mut buffer = vec![0u8; 1 << 20];
+        loop {
+            let read = input.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            output.write_all(&buffer[..read]).await?;
+            size += read as u64;
+        }
+        output.flush().await?;
+        drop(output);
+        
+        let digest = format!("{:x}", hasher.finalize());
+        let object_path = self.object_path(&digest);
+        if tokio::fs::try_exists(&object_path).await? {
+            debug!("Content of {} is already cached as {}", url, digest);
+            tokio::fs::remove_file(&temp_path).await?;
+            self.record(source_id, url, digest, size, 0, validators).await
+        } else {
+            self.commit_object(&temp_path, &object_path).await?;
+            self.record(source_id, url, digest, size, size, validators).await
+        }
+    }
+    
+    /// Refresh the fetch time and validators of a cached URL after revalidation
+    /// 
+    /// Validators missing from `validators` keep their cached values, as does
+    /// the content length, which a `304 Not Modified` response does not carry.
+    pub async fn touch(&self, metadata: &CacheMetadata, validators: CacheValidators) -> SourceResult<CacheMetadata> {
+        let mut metadata = metadata.clone();
+        metadata.fetched_at = Utc::now();
+        metadata.accessed_at = Some(metadata.fetched_at);
+        if validators.etag.is_some() {
+            metadata.validators.etag = validators.etag;
+        }
+        if validators.last_modified.is_some() {
+            metadata.validators.last_modified = validators.last_modified;
+        }
+        self.write_index(&metadata).await?;
+        Ok(metadata)
+    }
+    
+    /// Remove the index entry of a URL
+    /// 
+    /// The object is kept, as other URLs may still reference it.
+    pub async fn remove(&self, url: &str) -> SourceResult<()> {
+        match tokio::fs::remove_file(self.index_path(url)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+    
+    async fn record(
+        &self,
+        source_id: &str,
+        url: &str,
+        digest: String,
+        size: u64,
+        added: u64,
+        validators: CacheValidators,
+    ) -> SourceResult<CacheMetadata> {
+        let metadata = CacheMetadata {
+            source_id: source_id.to_string(),
+            url: url.to_string(),
+            digest,
+            size,
+            fetched_at: Utc::now(),
+            accessed_at: None,
+            validators,
+        };
+        self.write_index(&metadata).await?;
+        debug!("Cached {} as {}", url, metadata.digest);
+        
+        if let Some(max_size) = self.limits.max_size {
+            // Only rescan when the running total is unknown or over the limit
+            let total = self.usage.lock().expect("cache usage lock poisoned").as_mut().map(|total| {
+                *total += added;
+                *total
+            });
+            if total.is_none_or(|total| total > max_size) {
+                let entries = self.entries().await?;
+                self.evict(entries, Some(url), &mut GcReport::default()).await?;
+            }
+        }
+        
+        Ok(metadata)
+    }
+    
+    /// Report the disk usage of the cache
+    pub async fn stats(&self) -> SourceResult<CacheStats> {
+        let now = Utc::now();
+        let entries = self.entries().await?;
+        let objects = self.objects().await?;
+        
+        let mut stats = CacheStats {
+            entries: entries.len(),
+            objects: objects.len(),
+            total_bytes: objects.values().map(|(size, _)| size).sum(),
+            ..Default::default()
+        };
+        
+        for metadata in &entries {
+            if self.is_expired(metadata, now) {
+                stats.expired_entries += 1;
+            }
+            let source = stats.sources.entry(metadata.source_id.clone()).or_default();
+            source.entries += 1;
+            source.bytes += metadata.size;
+        }
+        
+        let referenced = reference_counts(&entries);
+        stats.unreferenced_bytes = objects
+            .iter()
+            .filter(|(digest, _)| !referenced.contains_key(digest.as_str()))
+            .map(|(_, (size, _))| size)
+            .sum();
+        
+        Ok(stats)
+    }
+    
+    /// Apply the cache limits and reclaim unused space
+    /// 
+    /// Removes entries older than the TTL, deletes objects and temporary
+    /// files no entry refers to, and evicts least recently used entries until
+    /// the cache fits its size limit.
+    pub async fn gc(&self) -> SourceResult<GcReport> {
+        let now = Utc::now();
+        let mut report = GcReport::default();
+        
+        let mut entries = Vec::new();
+        for metadata in self.entries().await? {
+            if self.is_expired(&metadata, now) {
+                debug!("Expiring cached {}", metadata.url);
+                self.remove(&metadata.url).await?;
+                report.removed_entries += 1;
+            } else {
+                entries.push(metadata);
+            }
+        }
+        
+        let referenced = reference_counts(&entries);
+        let grace_cutoff = SystemTime::now() - ORPHAN_GRACE_PERIOD;
+        for (digest, (size, modified)) in self.objects().await? {
+            if !referenced.contains_key(digest.as_str()) && modified < grace_cutoff {
+                tokio::fs::remove_file(self.object_path(&digest)).await?;
+                report.removed_objects += 1;
+                report.freed_bytes += size;
+            }
+        }
+        self.remove_stale_temp_files(grace_cutoff).await?;
+        
+        self.evict(entries, None, &mut report).await?;
+        
+        info!(
+            "Cache gc removed {} entries and {} objects, freeing {} bytes",
+            report.removed_entries, report.removed_objects, report.freed_bytes
+        );
+        Ok(report)
+    }
+    
+    /// Remove every file cached through the given source
+    /// 
+    /// Content shared with URLs of other sources is kept.
+    pub async fn purge(&self, source_id: &str) -> SourceResult<GcReport> {
+        let mut report = GcReport::default();
+        let (purged, kept): (Vec<_>, Vec<_>) = self
+            .entries()
+            .await?
+            .into_iter()
+            .partition(|metadata| metadata.source_id == source_id);
+        
+        let referenced = reference_counts(&kept);
+        for metadata in &purged {
+            self.remove(&metadata.url).await?;
+            report.removed_entries += 1;
+        }
+        
+        let mut seen = std::collections::HashSet::new();
+        for metadata in &purged {
+            if !referenced.contains_key(metadata.digest.as_str()) && seen.insert(metadata.digest.as_str()) {
+                report.freed_bytes += self.remove_object(&metadata.digest).await?;
+                report.removed_objects += 1;
+            }
+        }
+        
+        info!("Purged {} cached files of source {}", report.removed_entries, source_id);
+        Ok(report)
+    }
+    
+    /// Whether an entry is older than the TTL
+    fn is_expired(&self, metadata: &CacheMetadata, now: DateTime<Utc>) -> bool {
+        match self.limits.ttl_secs {
+            Some(ttl) => (now - metadata.fetched_at).num_seconds() >= ttl as i64,
+            None => false,
+        }
+    }
+    
+    /// Evict least recently used entries until the cache fits its size limit
+    /// 
+    /// The entry for `keep` is never evicted.
+    async fn evict(&self, mut entries: Vec<CacheMetadata>, keep: Option<&str>, report: &mut GcReport) -> SourceResult<()> {
+        let Some(max_size) = self.limits.max_size else {
+            return Ok(());
+        };
+        
+        let objects = self.objects().await?;
+        let mut total: u64 = objects.values().map(|(size, _)| size).sum();
+        if total <= max_size {
+            self.set_usage(total);
+            return Ok(());
+        }
+        
+        let mut references = reference_counts(&entries)
+            .into_iter()
+            .map(|(digest, count)| (digest.to_string(), count))
+            .collect::<HashMap<_, _>>();
+        entries.sort_by_key(|metadata| metadata.last_used());
+        
+        for metadata in &entries {
+            if total <= max_size {
+                break;
+            }
+            if Some(metadata.url.as_str()) == keep {
+                continue;
+            }
+            
+            debug!("Evicting cached {}", metadata.url);
+            self.remove(&metadata.url).await?;
+            report.removed_entries += 1;
+            
+            let count = references.entry(metadata.digest.clone()).or_default();
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                let size = self.remove_object(&metadata.digest).await?;
+                total = total.saturating_sub(size);
+                report.removed_objects += 1;
+                report.freed_bytes += size;
+            }
+        }
+        
+        if total > max_size {
+            warn!("Cache size {} exceeds limit {} after eviction", total, max_size);
+        }
+        self.set_usage(total);
+        Ok(())
+    }
+    
+    fn set_usage(&self, total: u64) {
+        *self.usage.lock().expect("cache usage lock poisoned") = Some(total);
+    }
+    
+    /// All readable URL entries
+    async fn entries(&self) -> SourceResult<Vec<CacheMetadata>> {
+        let mut entries = Vec::new();
+        let mut dir = match tokio::fs::read_dir(self.root.join("index")).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => return Err(e.into()),
+        };
+        
+        while let Some(entry) = dir.next_entry().await? {
+            let json = match tokio::fs::read(entry.path()).await {
+                Ok(json) => json,
+                Err(_) => continue,
+            };
+            match serde_json::from_slice::<CacheMetadata>(&json) {
+                Ok(metadata) => entries.push(metadata),
+                Err(e) => warn!("Ignoring unreadable cache entry {:?}: {}", entry.path(), e),
+            }
+        }
+        Ok(entries)
+    }
+    
+    /// All stored objects by digest, with their size and modification time
+    async fn objects(&self) -> SourceResult<HashMap<String, (u64, SystemTime)>> {
+        let mut objects = HashMap::new();
+        let mut prefixes = match tokio::fs::read_dir(self.root.join("objects")).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(objects),
+            Err(e) => return Err(e.into()),
+        };
+        
+        while let Some(prefix) = prefixes.next_entry().await? {
+            if !prefix.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut dir = tokio::fs::read_dir(prefix.path()).await?;
+            while let Some(object) = dir.next_entry().await? {
+                let metadata = object.metadata().await?;
+                if metadata.is_file() {
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    objects.insert(object.file_name().to_string_lossy().into_owned(), (metadata.len(), modified));
+                }
+            }
+        }
+        Ok(objects)
+    }
+    
+    /// Delete an object, returning its size
+    async fn remove_object(&self, digest: &str) -> SourceResult<u64> {
+        let path = self.object_path(digest);
+        let size = match tokio::fs::metadata(&path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        tokio::fs::remove_file(&path).await?;
+        if let Some(total) = self.usage.lock().expect("cache usage lock poisoned").as_mut() {
+            *total = total.saturating_sub(size);
+        }
+        Ok(size)
+    }
+    
+    async fn remove_stale_temp_files(&self, cutoff: SystemTime) -> SourceResult<()> {
+        let mut dir = match tokio::fs::read_dir(self.root.join("tmp")).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        
+        while let Some(entry) = dir.next_entry().await? {
+            let modified = entry.metadata().await?.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            if modified < cutoff {
+                let _ = tokio::fs::remove_file(entry.path()).await;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Record that a cached copy was used
+    async fn mark_accessed(&self, metadata: &CacheMetadata) {
+        let mut metadata = metadata.clone();
+        metadata.accessed_at = Some(Utc::now());
+        if let Err(e) = self.write_index(&metadata).await {
+            debug!("Failed to update access time of {}: {}", metadata.url, e);
+        }
+    }
+    
+    async fn write_index(&self, metadata: &CacheMetadata) -> SourceResult<()> {
+        let index_path = self.index_path(&metadata.url);
+        let temp_path = self.temp_path();
+        create_parent(&temp_path).await?;
+        create_parent(&index_path).await?;
+        
+        let json = serde_json::to_vec_pretty(metadata).map_err(|e| SourceError::Parse(e.to_string()))?;
+        tokio::fs::write(&temp_path, json).await?;
+        tokio::fs::rename(&temp_path, &index_path).await?;
+        Ok(())
+    }
+    
+    async fn commit_object(&self, temp_path: &Path, object_path: &Path) -> SourceResult<()> {
+        create_parent(object_path).await?;
+        tokio::fs::rename(temp_path, object_path).await?;
+        Ok(())
+    }
+    
+    async fn discard_corrupt(&self, metadata: &CacheMetadata, actual: &str) {
+        warn!(
+            "Cached content of {} is corrupt (expected {}, got {}), discarding",
+            metadata.url, metadata.digest, actual
+        );
+        let _ = self.remove_object(&metadata.digest).await;
+        let _ = self.remove(&metadata.url).await;
+    }
+    
+    /// Unique path for writing a file before moving it into place
+    fn temp_path(&self) -> PathBuf {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        self.root.join("tmp").join(format!("{}-{}.tmp", std::process::id(), n))
+    }
+}
+
+/// Number of entries referring to each object
+fn reference_counts(entries: &[CacheMetadata]) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for metadata in entries {
+        *counts.entry(metadata.digest.as_str()).or_default() += 1;
+    }
+    counts
+}
+
+/// Compute the hex-encoded SHA-256 digest and size of a file
+pub async fn hash_file(path: &Path) -> SourceResult<(String, u64)> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+async fn create_parent(path: &Path) -> SourceResult<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_url_key_is_stable() {
+        assert_eq!(
+            DownloadCache::url_key("https://example.com/file.json"),
+            "be0a43604e7748f244d4e2677a2742ed2d322f564590051d489c399fa31c208e"
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_identical_content_is_stored_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path());
+        
+        let first = cache
+            .insert_bytes("test", "https://a.example.com/file.json", b"{}", CacheValidators::default())
+            .await
+            .unwrap();
+        
+        let path = dir.path().join("download.json");
+        std::fs::write(&path, b"{}").unwrap();
+        let second = cache
+            .insert_file("test", "https://b.example.com/file.json", &path, CacheValidators::default())
+            .await
+            .unwrap();
+        
+        assert_eq!(first.digest, second.digest);
+        assert_eq!(second.size, 2);
+        let objects: Vec<_> = walk(&dir.path().join("objects"));
+        assert_eq!(objects.len(), 1);
+        
+        let metadata = cache.lookup("https://b.example.com/file.json").await.unwrap();
+        assert_eq!(metadata.source_id, "test");
+        assert_eq!(cache.read(&metadata).await.unwrap(), b"{}");
+    }
+    
+    #[tokio::test]
+    async fn test_corrupt_object_is_a_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path());
+        let url = "https://example.com/file.json";
+        
+        let metadata = cache.insert_bytes("test", url, b"{}", CacheValidators::default()).await.unwrap();
+        std::fs::write(cache.object_path(&metadata.digest), b"[]").unwrap();
+        
+        assert!(cache.read(&metadata).await.is_none());
+        assert!(cache.lookup(url).await.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_lru_eviction_and_purge() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path()).with_limits(CacheLimits {
+            max_size: Some(10),
+            ttl_secs: None,
+        });
+        
+        let first = cache.insert_bytes("a", "https://example.com/1", b"11111", CacheValidators::default()).await.unwrap();
+        cache.insert_bytes("a", "https://example.com/2", b"22222", CacheValidators::default()).await.unwrap();
+        // Using the first entry makes the second the least recently used
+        assert!(cache.read(&first).await.is_some());
+        cache.insert_bytes("b", "https://example.com/3", b"33333", CacheValidators::default()).await.unwrap();
+        
+        assert!(cache.lookup("https://example.com/1").await.is_some());
+        assert!(cache.lookup("https://example.com/2").await.is_none());
+        assert!(cache.lookup("https://example.com/3").await.is_some());
+        
+        let stats = cache.stats().await.unwrap();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.objects, 2);
+        assert_eq!(stats.total_bytes, 10);
+        assert_eq!(stats.sources["a"], SourceCacheStats { entries: 1, bytes: 5 });
+        // The running total follows the eviction and is shared with clones
+        assert_eq!(*cache.clone().usage.lock().unwrap(), Some(10));
+        
+        let report = cache.purge("a").await.unwrap();
+        assert_eq!(report, GcReport { removed_entries: 1, removed_objects: 1, freed_bytes: 5 });
+        assert_eq!(cache.stats().await.unwrap().total_bytes, 5);
+        assert_eq!(*cache.usage.lock().unwrap(), Some(5));
+        
+        // Known to fit, so no entry is evicted
+        cache.insert_bytes("b", "https://example.com/4", b"44444", CacheValidators::default()).await.unwrap();
+        assert!(cache.lookup("https://example.com/3").await.is_some());
+        assert_eq!(*cache.usage.lock().unwrap(), Some(10));
+    }
+    
+    #[tokio::test]
+    async fn test_gc_removes_expired_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path()).with_limits(CacheLimits {
+            max_size: None,
+            ttl_secs: Some(3600),
+        });
+        let url = "https://example.com/file.json";
+        
+        let mut metadata = cache.insert_bytes("test", url, b"{}", CacheValidators::default()).await.unwrap();
+        assert!(cache.lookup(url).await.is_some());
+        
+        metadata.fetched_at = Utc::now() - chrono::Duration::hours(2);
+        cache.write_index(&metadata).await.unwrap();
+        // Expired entries are still found so they can be revalidated
+        let expired = cache.lookup(url).await.unwrap();
+        assert!(!cache.is_fresh(&expired));
+        assert_eq!(cache.stats().await.unwrap().expired_entries, 1);
+        
+        let report = cache.gc().await.unwrap();
+        assert_eq!(report.removed_entries, 1);
+        assert_eq!(cache.stats().await.unwrap().entries, 0);
+    }
+    
+    fn walk(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(walk(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+}
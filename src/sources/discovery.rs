@@ -0,0 +1,483 @@
+//! Discovery output
+//! 
+//! Payers often publish one index file per employer, and thousands of them
+//! point at the same shared in-network files. [`dedupe_files`] collapses the
+//! discovered [`MrfFileInfo`]s to one [`UniqueMrfFile`] per file, keeping a
+//! [`FileReference`] for each index file that listed it.
+//! 
+//! Files are matched by URL without the query string, which for blob storage
+//! carries access tokens that can differ between index files.
+//! 
+//! A [`DiscoveryReport`] records what happened to every index file, so an
+//! outage or a format change shows up as errors instead of as fewer files.
+//! 
+//! A [`DiscoveryFilter`] narrows discovery down to the employers, dates,
+//! plans and file types of interest, skipping the download of index files
+//! that cannot match.
+
+use super::{MrfFileInfo, MrfFileType, SourceError, SourceResult};
+use crate::types::{MarketType, PlanIdType, ReportingPlan};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Number of leading bytes of an unparseable index file kept in its report
+pub const PREVIEW_LEN: usize = 100;
+
+/// Index file that lists a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReference {
+    /// URL of the index file
+    pub index_url: Option<String>,
+    
+    /// Entity publishing the index file
+    pub reporting_entity_name: Option<String>,
+    
+    /// Type of the publishing entity
+    pub reporting_entity_type: Option<String>,
+    
+    /// Plans the index file lists this file for
+    pub reporting_plans: Vec<ReportingPlan>,
+}
+
+/// A file listed by one or more index files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniqueMrfFile {
+    /// The file, with the plans of all references merged
+    pub file: MrfFileInfo,
+    
+    /// Every index file that lists the file, in discovery order
+    pub references: Vec<FileReference>,
+}
+
+impl AsRef<MrfFileInfo> for UniqueMrfFile {
+    fn as_ref(&self) -> &MrfFileInfo {
+        &self.file
+    }
+}
+
+impl AsMut<MrfFileInfo> for UniqueMrfFile {
+    fn as_mut(&mut self) -> &mut MrfFileInfo {
+        &mut self.file
+    }
+}
+
+/// Keys of [`MrfFileInfo::metadata`] that describe the listing index file
+/// rather than the file itself
+const REFERENCE_KEYS: [&str; 3] = ["index_url", "reporting_entity_name", "reporting_entity_type"];
+
+/// Collapse files listed by several index files into one entry each
+/// 
+/// The first listing of a file provides its ID, name and metadata. Its plans
+/// are the union of the plans of all listings, its size the first one known
+/// and its modification time the latest one known.
+pub fn dedupe_files(files: Vec<MrfFileInfo>) -> Vec<UniqueMrfFile> {
+    let mut unique: Vec<UniqueMrfFile> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut seen_plans: Vec<HashSet<(String, String)>> = Vec::new();
+    
+    for mut file in files {
+        let reference = FileReference {
+            index_url: metadata_str(&file, "index_url"),
+            reporting_entity_name: metadata_str(&file, "reporting_entity_name"),
+            reporting_entity_type: metadata_str(&file, "reporting_entity_type"),
+            reporting_plans: std::mem::take(&mut file.reporting_plans),
+        };
+        
+        let key = dedupe_key(&file.url).to_string();
+        let position = *positions.entry(key).or_insert_with(|| {
+            if let serde_json::Value::Object(metadata) = &mut file.metadata {
+                for key in REFERENCE_KEYS {
+                    metadata.remove(key);
+                }
+            }
+            unique.push(UniqueMrfFile {
+                file: MrfFileInfo {
+                    reporting_plans: Vec::new(),
+                    ..file.clone()
+                },
+                references: Vec::new(),
+            });
+            seen_plans.push(HashSet::new());
+            unique.len() - 1
+        });
+        
+        let entry = &mut unique[position];
+        entry.file.size_bytes = entry.file.size_bytes.or(file.size_bytes);
+        entry.file.last_modified = latest(entry.file.last_modified, file.last_modified);
+        for plan in &reference.reporting_plans {
+            if seen_plans[position].insert((plan.plan_id.clone(), plan.plan_name.clone())) {
+                entry.file.reporting_plans.push(plan.clone());
+            }
+        }
+        entry.references.push(reference);
+    }
+    
+    unique
+}
+
+/// What became of one index file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum IndexOutcome {
+    /// The index file listed this many files
+    Ok { files: usize },
+    
+    /// The index file had no content or listed no files
+    Empty,
+    
+    /// The index file could not be downloaded
+    HttpError { error: String },
+    
+    /// The index file is not a valid index
+    ParseError {
+        error: String,
+        
+        /// Start of the content, lossily decoded as UTF-8
+        preview: String,
+    },
+    
+    /// The index file was downloaded but the task parsing it panicked or
+    /// was cancelled; this points at a bug here, not at the upstream data
+    TaskFailed { error: String },
+}
+
+impl IndexOutcome {
+    /// Parse failure of `content`, keeping a preview of it
+    pub fn parse_error(error: impl ToString, content: &[u8]) -> Self {
+        let mut preview = String::from_utf8_lossy(&content[..content.len().min(PREVIEW_LEN)]).into_owned();
+        if content.len() > PREVIEW_LEN {
+            preview.push_str("...");
+        }
+        IndexOutcome::ParseError {
+            error: error.to_string(),
+            preview,
+        }
+    }
+    
+    /// Whether the index file could not be read
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            IndexOutcome::HttpError { .. } | IndexOutcome::ParseError { .. } | IndexOutcome::TaskFailed { .. }
+        )
+    }
+}
+
+/// Outcome of one index file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexFileReport {
+    /// Name of the index file
+    pub name: String,
+    
+    /// URL of the index file, without its query string so that access
+    /// tokens do not end up in logs
+    pub url: String,
+    
+    /// What became of the index file
+    pub outcome: IndexOutcome,
+    
+    /// Time taken to download and parse the index file
+    pub duration: Duration,
+}
+
+/// Outcome of every index file read during discovery
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiscoveryReport {
+    /// One entry per index file, in the order they were listed
+    pub index_files: Vec<IndexFileReport>,
+    
+    /// Time taken by the whole discovery
+    pub duration: Duration,
+}
+
+impl DiscoveryReport {
+    /// Number of index files that listed files
+    pub fn ok_count(&self) -> usize {
+        self.index_files
+            .iter()
+            .filter(|report| matches!(report.outcome, IndexOutcome::Ok { .. }))
+            .count()
+    }
+    
+    /// Number of index files that listed no files
+    pub fn empty_count(&self) -> usize {
+        self.index_files
+            .iter()
+            .filter(|report| report.outcome == IndexOutcome::Empty)
+            .count()
+    }
+    
+    /// Index files that could not be downloaded or parsed
+    pub fn errors(&self) -> impl Iterator<Item = &IndexFileReport> {
+        self.index_files.iter().filter(|report| report.outcome.is_error())
+    }
+    
+    /// Number of index files that could not be downloaded or parsed
+    pub fn error_count(&self) -> usize {
+        self.errors().count()
+    }
+    
+    /// Share of index files that could not be downloaded or parsed, from 0 to 1
+    pub fn error_rate(&self) -> f64 {
+        if self.index_files.is_empty() {
+            return 0.0;
+        }
+        self.error_count() as f64 / self.index_files.len() as f64
+    }
+    
+    /// Fail with [`SourceError::DiscoveryFailed`] if more than
+    /// `max_error_rate` of the index files could not be read
+    pub fn check(self, max_error_rate: f64) -> SourceResult<Self> {
+        if self.error_rate() > max_error_rate {
+            return Err(SourceError::DiscoveryFailed {
+                failed: self.error_count(),
+                total: self.index_files.len(),
+                report: Box::new(self),
+            });
+        }
+        Ok(self)
+    }
+}
+
+/// Criteria limiting which files discovery returns
+/// 
+/// Index file criteria are checked against the name and date of each index
+/// file before it is downloaded. File criteria are checked against each
+/// listed file once its index file is parsed. Empty criteria match
+/// everything, so the default filter keeps all files.
+/// 
+/// ```
+/// use mrf_rs::sources::discovery::DiscoveryFilter;
+/// use mrf_rs::sources::MrfFileType;
+/// use mrf_rs::types::MarketType;
+/// use regex::Regex;
+/// 
+/// let filter = DiscoveryFilter {
+///     employer_patterns: vec![Regex::new("(?i)acme").unwrap()],
+///     market_types: vec![MarketType::Group],
+///     file_types: vec![MrfFileType::InNetwork],
+///     ..Default::default()
+/// };
+/// assert!(filter.matches_index("2025-06-01_ACME-CORP_index.json", None));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryFilter {
+    /// Patterns for the index file name, which names the employer or
+    /// reporting entity; an index file must match at least one
+    pub employer_patterns: Vec<Regex>,
+    
+    /// Earliest publication date of index files, inclusive
+    pub since: Option<DateTime<Utc>>,
+    
+    /// Latest publication date of index files, inclusive
+    pub until: Option<DateTime<Utc>>,
+    
+    /// Plan IDs (EIN or HIOS) a file must cover one of
+    pub plan_ids: Vec<String>,
+    
+    /// Plan ID types a covered plan must have, such as only EINs
+    pub plan_id_types: Vec<PlanIdType>,
+    
+    /// Markets a covered plan must be offered in
+    pub market_types: Vec<MarketType>,
+    
+    /// File types to keep
+    pub file_types: Vec<MrfFileType>,
+}
+
+impl DiscoveryFilter {
+    /// Whether an index file with this name and publication date may list
+    /// matching files
+    /// 
+    /// An index file without a known date does not match a date range.
+    pub fn matches_index(&self, name: &str, date: Option<DateTime<Utc>>) -> bool {
+        if !self.employer_patterns.is_empty() && !self.employer_patterns.iter().any(|p| p.is_match(name)) {
+            return false;
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        date.is_some_and(|date| {
+            self.since.is_none_or(|since| date >= since) && self.until.is_none_or(|until| date <= until)
+        })
+    }
+    
+    /// Whether a reporting plan meets the plan criteria
+    pub fn matches_plan(&self, plan: &ReportingPlan) -> bool {
+        (self.plan_ids.is_empty() || self.plan_ids.contains(&plan.plan_id))
+            && (self.plan_id_types.is_empty() || self.plan_id_types.contains(&plan.plan_id_type))
+            && (self.market_types.is_empty() || self.market_types.contains(&plan.plan_market_type))
+    }
+    
+    /// Whether a listed file has a wanted type and covers a matching plan
+    pub fn matches_file(&self, file: &MrfFileInfo) -> bool {
+        if !self.file_types.is_empty() && !self.file_types.contains(&file.file_type) {
+            return false;
+        }
+        !self.has_plan_criteria() || file.reporting_plans.iter().any(|plan| self.matches_plan(plan))
+    }
+    
+    fn has_plan_criteria(&self) -> bool {
+        !self.plan_ids.is_empty() || !self.plan_id_types.is_empty() || !self.market_types.is_empty()
+    }
+}
+
+/// URL without its query string
+fn dedupe_key(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
+
+fn metadata_str(file: &MrfFileInfo, key: &str) -> Option<String> {
+    file.metadata.get(key).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+fn latest(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::MrfFileType;
+    
+    fn listing(url: &str, index_url: &str, entity: &str, plan_ids: &[&str]) -> MrfFileInfo {
+        MrfFileInfo {
+            id: url.to_string(),
+            name: "rates".to_string(),
+            url: url.to_string(),
+            file_type: MrfFileType::InNetwork,
+            size_bytes: None,
+            last_modified: None,
+            compression: None,
+            reporting_plans: plan_ids
+                .iter()
+                .map(|id| {
+                    serde_json::from_value(serde_json::json!({
+                        "plan_name": format!("Plan {}", id),
+                        "plan_id_type": "EIN",
+                        "plan_id": id,
+                        "plan_market_type": "group",
+                    }))
+                    .unwrap()
+                })
+                .collect(),
+            metadata: serde_json::json!({
+                "source": "united_health",
+                "index_url": index_url,
+                "reporting_entity_name": entity,
+                "reporting_entity_type": "Employer",
+            }),
+        }
+    }
+    
+    #[test]
+    fn test_dedupe_merges_listings() {
+        let mut late = listing("https://example.com/shared.json.gz?sig=2", "https://example.com/b_index.json", "Beta", &["2", "1"]);
+        late.size_bytes = Some(4096);
+        late.last_modified = Some("2025-06-01T00:00:00Z".parse().unwrap());
+        
+        let files = vec![
+            listing("https://example.com/shared.json.gz?sig=1", "https://example.com/a_index.json", "Acme", &["1"]),
+            listing("https://example.com/own.json.gz", "https://example.com/a_index.json", "Acme", &["1"]),
+            late,
+        ];
+        let unique = dedupe_files(files);
+        
+        assert_eq!(unique.len(), 2);
+        let shared = &unique[0];
+        assert_eq!(shared.file.url, "https://example.com/shared.json.gz?sig=1");
+        assert_eq!(shared.file.size_bytes, Some(4096));
+        assert!(shared.file.last_modified.is_some());
+        assert_eq!(shared.file.metadata, serde_json::json!({"source": "united_health"}));
+        
+        let plan_ids: Vec<&str> = shared.file.reporting_plans.iter().map(|p| p.plan_id.as_str()).collect();
+        assert_eq!(plan_ids, vec!["1", "2"]);
+        
+        let entities: Vec<_> = shared.references.iter().map(|r| r.reporting_entity_name.as_deref()).collect();
+        assert_eq!(entities, vec![Some("Acme"), Some("Beta")]);
+        assert_eq!(shared.references[1].reporting_plans.len(), 2);
+        assert_eq!(shared.references[1].index_url.as_deref(), Some("https://example.com/b_index.json"));
+        
+        assert_eq!(unique[1].references.len(), 1);
+    }
+    
+    #[test]
+    fn test_filter_criteria() {
+        let date = |s: &str| Some(s.parse::<DateTime<Utc>>().unwrap());
+        let filter = DiscoveryFilter {
+            employer_patterns: vec![Regex::new("(?i)_acme").unwrap(), Regex::new("_Beta_").unwrap()],
+            since: date("2025-06-01T00:00:00Z"),
+            ..Default::default()
+        };
+        assert!(filter.matches_index("2025-06-01_ACME-CORP_index.json", date("2025-06-01T00:00:00Z")));
+        assert!(filter.matches_index("2025-07-01_Beta_index.json", date("2025-07-01T00:00:00Z")));
+        assert!(!filter.matches_index("2025-05-01_Acme_index.json", date("2025-05-01T00:00:00Z")));
+        assert!(!filter.matches_index("Acme_index.json", None));
+        assert!(!filter.matches_index("2025-06-01_Gamma_index.json", date("2025-06-01T00:00:00Z")));
+        assert!(DiscoveryFilter::default().matches_index("anything", None));
+        
+        let filter = DiscoveryFilter {
+            plan_ids: vec!["2".to_string(), "3".to_string()],
+            market_types: vec![MarketType::Group],
+            file_types: vec![MrfFileType::InNetwork],
+            ..Default::default()
+        };
+        let file = listing("https://example.com/a.json", "https://example.com/index.json", "Acme", &["1", "2"]);
+        assert!(filter.matches_file(&file));
+        assert!(!filter.matches_file(&listing("https://example.com/b.json", "https://example.com/index.json", "Acme", &["1"])));
+        assert!(!filter.matches_file(&MrfFileInfo {
+            file_type: MrfFileType::AllowedAmount,
+            ..file.clone()
+        }));
+        
+        let filter = DiscoveryFilter {
+            market_types: vec![MarketType::Individual],
+            ..Default::default()
+        };
+        assert!(!filter.matches_file(&file));
+        assert!(DiscoveryFilter::default().matches_file(&file));
+    }
+    
+    #[test]
+    fn test_report_error_threshold() {
+        let report = |outcome| IndexFileReport {
+            name: "index.json".to_string(),
+            url: "https://example.com/index.json".to_string(),
+            outcome,
+            duration: Duration::ZERO,
+        };
+        let report = DiscoveryReport {
+            index_files: vec![
+                report(IndexOutcome::Ok { files: 3 }),
+                report(IndexOutcome::Empty),
+                report(IndexOutcome::HttpError { error: "HTTP error: 503".to_string() }),
+                report(IndexOutcome::parse_error("expected value", &[b'<'; 150])),
+                report(IndexOutcome::TaskFailed { error: "task panicked".to_string() }),
+            ],
+            duration: Duration::ZERO,
+        };
+        
+        assert_eq!(report.ok_count(), 1);
+        assert_eq!(report.empty_count(), 1);
+        assert_eq!(report.error_count(), 3);
+        assert_eq!(report.error_rate(), 0.6);
+        match &report.index_files[3].outcome {
+            IndexOutcome::ParseError { preview, .. } => assert_eq!(preview.len(), PREVIEW_LEN + 3),
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+        
+        let report = report.check(0.6).unwrap();
+        match report.check(0.25) {
+            Err(SourceError::DiscoveryFailed { failed: 3, total: 5, report }) => {
+                assert_eq!(report.index_files.len(), 5);
+            }
+            other => panic!("Expected DiscoveryFailed, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -31,12 +31,23 @@
 //! ```
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use crate::types::ReportingPlan;
 use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 pub mod base;
+pub mod cache;
+pub mod discovery;
+pub mod plans;
+pub mod rate_limit;
+pub mod retry;
+pub mod size_limit;
+pub mod sniff;
 pub mod united_health;
 
 // Re-export insurer modules when they're implemented
@@ -70,6 +81,27 @@ pub enum SourceError {
     #[error("Invalid configuration: {0}")]
     Config(String),
     
+    /// The response body exceeded `FetchOptions::max_size`
+    #[error("Size limit of {limit} bytes exceeded after receiving {received} bytes")]
+    SizeLimitExceeded { limit: u64, received: u64 },
+    
+    /// The gzip-compressed body would decompress to more than
+    /// `FetchOptions::max_decompressed_size`
+    #[error("Decompressed size limit of {limit} bytes exceeded after receiving {received} bytes")]
+    DecompressedSizeLimitExceeded { limit: u64, received: u64 },
+    
+    /// Downloaded content does not match its advertised length or digest
+    #[error("Integrity check failed: {0}")]
+    Integrity(String),
+    
+    /// More index files than allowed could not be read in strict discovery
+    #[error("Discovery failed: {failed} of {total} index files could not be read")]
+    DiscoveryFailed {
+        failed: usize,
+        total: usize,
+        report: Box<discovery::DiscoveryReport>,
+    },
+    
     /// Generic source error
     #[error("Source error: {0}")]
     Other(String),
@@ -102,10 +134,26 @@ pub struct MrfFileInfo {
     /// Compression format (if any)
     pub compression: Option<CompressionType>,
     
+    /// Plans whose rates this file reports, as listed in the table of contents
+    #[serde(default)]
+    pub reporting_plans: Vec<ReportingPlan>,
+    
     /// Additional metadata specific to the source
     pub metadata: serde_json::Value,
 }
 
+impl AsRef<MrfFileInfo> for MrfFileInfo {
+    fn as_ref(&self) -> &MrfFileInfo {
+        self
+    }
+}
+
+impl AsMut<MrfFileInfo> for MrfFileInfo {
+    fn as_mut(&mut self) -> &mut MrfFileInfo {
+        self
+    }
+}
+
 /// Type of MRF file
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum MrfFileType {
@@ -154,44 +202,128 @@ pub enum CompressionType {
     None,
 }
 
+/// Request timeout used when no options set one
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Number of retry attempts used when no options set one
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
 /// Options for fetching MRF files
+/// 
+/// Settings left unset in per-call options fall back to the source's
+/// `SourceConfig::default_options`; see [`FetchOptions::with_defaults`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FetchOptions {
     /// Maximum file size to download (in bytes)
+    /// 
+    /// Enforced on the bytes actually received while streaming, not only
+    /// against the `Content-Length` header.
     pub max_size: Option<u64>,
     
+    /// Maximum size of a gzip-compressed file once decompressed (in bytes)
+    /// 
+    /// Protects against small files that expand to enormous sizes.
+    pub max_decompressed_size: Option<u64>,
+    
     /// Whether to use local cache
     pub use_cache: bool,
     
     /// Cache directory path
     pub cache_dir: Option<String>,
     
-    /// Request timeout in seconds
+    /// Request timeout in seconds, [`DEFAULT_TIMEOUT_SECS`] if unset
     pub timeout_secs: Option<u64>,
     
-    /// Number of retry attempts
+    /// Number of retry attempts, [`DEFAULT_MAX_RETRIES`] if unset
     pub max_retries: Option<u32>,
     
     /// Whether to verify SSL certificates
     pub verify_ssl: bool,
+    
+    /// Number of concurrent range requests for one large file
+    /// 
+    /// Used by downloads to disk when the server supports byte ranges. Each
+    /// range is at least [`base::MIN_RANGE_SIZE`] bytes.
+    pub parallel_ranges: Option<usize>,
+    
+    /// Settings that take precedence over the source defaults outright
+    #[serde(default)]
+    pub overrides: FetchOverrides,
 }
 
 impl Default for FetchOptions {
     fn default() -> Self {
         Self {
             max_size: None,
+            max_decompressed_size: None,
             use_cache: true,
             cache_dir: None,
-            timeout_secs: Some(300), // 5 minutes default
-            max_retries: Some(3),
+            timeout_secs: None,
+            max_retries: None,
             verify_ssl: true,
+            parallel_ranges: None,
+            overrides: FetchOverrides::default(),
         }
     }
 }
 
+/// Per-call settings that replace the source defaults instead of merging
+/// with them
+/// 
+/// `use_cache` and `verify_ssl` are on unless either the per-call options or
+/// the source defaults turn them off. Set them here to decide them for one
+/// call regardless of the defaults, e.g. to verify certificates for one
+/// request to a source configured with `verify_ssl: false`. Overrides are
+/// only read from per-call options.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchOverrides {
+    /// Whether to use local cache
+    pub use_cache: Option<bool>,
+    
+    /// Whether to verify SSL certificates
+    pub verify_ssl: Option<bool>,
+}
+
+impl FetchOptions {
+    /// Fill settings left unset in these options from `defaults`
+    /// 
+    /// Optional settings present in `self` take precedence. Settings unset in
+    /// both fall back to the built-in defaults only when they are read, via
+    /// the accessors below. `use_cache` and `verify_ssl` stay on only if both
+    /// sides leave them on, unless [`FetchOptions::overrides`] sets them.
+    pub fn with_defaults(mut self, defaults: Option<&FetchOptions>) -> Self {
+        if let Some(defaults) = defaults {
+            self.max_size = self.max_size.or(defaults.max_size);
+            self.max_decompressed_size = self.max_decompressed_size.or(defaults.max_decompressed_size);
+            self.use_cache &= defaults.use_cache;
+            self.cache_dir = self.cache_dir.or_else(|| defaults.cache_dir.clone());
+            self.timeout_secs = self.timeout_secs.or(defaults.timeout_secs);
+            self.max_retries = self.max_retries.or(defaults.max_retries);
+            self.verify_ssl &= defaults.verify_ssl;
+            self.parallel_ranges = self.parallel_ranges.or(defaults.parallel_ranges);
+        }
+        self.use_cache = self.overrides.use_cache.unwrap_or(self.use_cache);
+        self.verify_ssl = self.overrides.verify_ssl.unwrap_or(self.verify_ssl);
+        self
+    }
+    
+    /// Request timeout in seconds
+    pub fn effective_timeout_secs(&self) -> u64 {
+        self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)
+    }
+    
+    /// Number of retry attempts
+    pub fn effective_max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)
+    }
+}
+
 /// Progress callback for download operations
 pub type ProgressCallback = Box<dyn Fn(u64, u64) + Send + Sync>;
 
+/// Stream of file content returned by [`MrfSource::fetch_stream`]
+pub type ByteStream = BoxStream<'static, SourceResult<Bytes>>;
+
 /// Main trait for MRF data sources
 #[async_trait]
 pub trait MrfSource: Send + Sync {
@@ -211,6 +343,10 @@ pub trait MrfSource: Send + Sync {
     /// 
     /// Downloads and returns the raw content of an MRF file.
     /// For large files, consider using `fetch_file_to_path` instead.
+    /// 
+    /// Settings in `options` override the source's configured defaults for
+    /// this call only; settings left unset fall back to
+    /// `SourceConfig::default_options`.
     async fn fetch_file(
         &self,
         file_info: &MrfFileInfo,
@@ -220,6 +356,7 @@ pub trait MrfSource: Send + Sync {
     /// Fetch an MRF file and save it to a path
     /// 
     /// More efficient for large files as it streams directly to disk.
+    /// `options` is resolved against the source defaults as in `fetch_file`.
     async fn fetch_file_to_path(
         &self,
         file_info: &MrfFileInfo,
@@ -228,6 +365,45 @@ pub trait MrfSource: Send + Sync {
         progress: Option<ProgressCallback>,
     ) -> SourceResult<()>;
     
+    /// Stream the content of an MRF file
+    /// 
+    /// Yields the file in chunks as it downloads, so it can be piped into a
+    /// streaming parser without holding it in memory or writing it to disk.
+    /// With `decompress` set, a file whose `compression` is gzip is
+    /// decompressed on the fly. `options` is resolved against the source
+    /// defaults as in `fetch_file`; the cache is not used.
+    /// 
+    /// The default implementation streams `file_info.url` through the client
+    /// returned by [`MrfSource::http_client`], or through an
+    /// [`HttpClient`](base::HttpClient) with the default configuration when
+    /// the source has none.
+    async fn fetch_stream(
+        &self,
+        file_info: &MrfFileInfo,
+        options: Option<FetchOptions>,
+        decompress: bool,
+    ) -> SourceResult<ByteStream> {
+        let fallback;
+        let client = match self.http_client() {
+            Some(client) => client,
+            None => {
+                fallback = base::HttpClient::new(SourceConfig::default())?;
+                &fallback
+            }
+        };
+        let options = client.resolve_options(options.as_ref());
+        let compression = if decompress { file_info.compression } else { None };
+        client.fetch_stream(&file_info.url, &options, compression).await
+    }
+    
+    /// HTTP client used by the default implementations
+    /// 
+    /// Sources should return their configured client so that its proxy,
+    /// certificates, headers, rate limit and retry policy apply.
+    fn http_client(&self) -> Option<&base::HttpClient> {
+        None
+    }
+    
     /// Get metadata about available files without full discovery
     /// 
     /// Some sources may provide a summary or table of contents
@@ -257,9 +433,58 @@ pub struct SourceConfig {
     /// Rate limit (requests per second)
     pub rate_limit: Option<f64>,
     
+    /// Rate limiter to use instead of one built from `rate_limit`
+    /// 
+    /// Share one limiter between the configs of several sources to limit
+    /// their combined traffic.
+    #[serde(skip)]
+    pub rate_limiter: Option<rate_limit::RateLimiter>,
+    
+    /// Download bandwidth limit in bytes per second, across all downloads
+    #[serde(default)]
+    pub max_bytes_per_second: Option<u64>,
+    
+    /// Bandwidth throttle to use instead of one built from
+    /// `max_bytes_per_second`, to share one budget between several sources
+    #[serde(skip)]
+    pub bandwidth_throttle: Option<rate_limit::BandwidthThrottle>,
+    
     /// Default fetch options
     pub default_options: Option<FetchOptions>,
     
+    /// Size and age limits of the download cache
+    #[serde(default)]
+    pub cache_limits: cache::CacheLimits,
+    
+    /// Retry behavior for failed requests
+    #[serde(default)]
+    pub retry_policy: retry::RetryPolicy,
+    
+    /// Proxy URL for all requests, e.g. `http://proxy.corp:3128`
+    /// 
+    /// Without one, the `HTTP_PROXY`/`HTTPS_PROXY` environment variables are
+    /// honored.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    
+    /// Hosts, domains and IP ranges reached without the proxy, in the syntax
+    /// of the `NO_PROXY` environment variable
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+    
+    /// PEM files with root certificates to trust in addition to the built-in
+    /// ones, such as the CA of a TLS-inspecting proxy
+    #[serde(default)]
+    pub root_certificates: Vec<PathBuf>,
+    
+    /// PEM file with a client certificate chain and its private key
+    #[serde(default)]
+    pub client_identity: Option<PathBuf>,
+    
+    /// Headers sent with every request
+    #[serde(default)]
+    pub default_headers: BTreeMap<String, String>,
+    
     /// Additional source-specific configuration
     pub extra: serde_json::Value,
 }
@@ -270,7 +495,17 @@ impl Default for SourceConfig {
             base_url: String::new(),
             user_agent: Some("mrf-rs/0.1.0".to_string()),
             rate_limit: Some(100.0), // 100 requests per second
+            rate_limiter: None,
+            max_bytes_per_second: None,
+            bandwidth_throttle: None,
             default_options: Some(FetchOptions::default()),
+            cache_limits: cache::CacheLimits::default(),
+            retry_policy: retry::RetryPolicy::default(),
+            proxy: None,
+            no_proxy: Vec::new(),
+            root_certificates: Vec::new(),
+            client_identity: None,
+            default_headers: BTreeMap::new(),
             extra: serde_json::Value::Null,
         }
     }
@@ -285,8 +520,83 @@ mod tests {
         let options = FetchOptions::default();
         assert!(options.use_cache);
         assert!(options.verify_ssl);
-        assert_eq!(options.timeout_secs, Some(300));
-        assert_eq!(options.max_retries, Some(3));
+        assert_eq!(options.effective_timeout_secs(), 300);
+        assert_eq!(options.effective_max_retries(), 3);
+    }
+    
+    #[test]
+    fn test_fetch_options_with_defaults() {
+        let defaults = FetchOptions {
+            max_size: Some(1024),
+            cache_dir: Some("/tmp/mrf-cache".to_string()),
+            timeout_secs: Some(60),
+            max_retries: Some(5),
+            ..Default::default()
+        };
+        let options = FetchOptions {
+            max_size: None,
+            max_decompressed_size: Some(4096),
+            use_cache: false,
+            cache_dir: None,
+            timeout_secs: Some(10),
+            max_retries: None,
+            verify_ssl: false,
+            parallel_ranges: Some(4),
+            overrides: FetchOverrides::default(),
+        };
+        
+        let resolved = options.with_defaults(Some(&defaults));
+        assert_eq!(resolved.max_size, Some(1024));
+        assert_eq!(resolved.max_decompressed_size, Some(4096));
+        assert_eq!(resolved.cache_dir.as_deref(), Some("/tmp/mrf-cache"));
+        assert_eq!(resolved.timeout_secs, Some(10));
+        assert_eq!(resolved.max_retries, Some(5));
+        assert!(!resolved.use_cache);
+        assert!(!resolved.verify_ssl);
+    }
+    
+    #[test]
+    fn test_partial_options_keep_configured_defaults() {
+        let defaults = FetchOptions {
+            use_cache: false,
+            timeout_secs: Some(30),
+            max_retries: Some(0),
+            verify_ssl: false,
+            ..Default::default()
+        };
+        let options = FetchOptions {
+            max_size: Some(1024),
+            ..Default::default()
+        };
+        
+        let resolved = options.with_defaults(Some(&defaults));
+        assert_eq!(resolved.max_size, Some(1024));
+        assert_eq!(resolved.effective_timeout_secs(), 30);
+        assert_eq!(resolved.effective_max_retries(), 0);
+        assert!(!resolved.use_cache);
+        assert!(!resolved.verify_ssl);
+        
+        let resolved = FetchOptions {
+            overrides: FetchOverrides {
+                use_cache: None,
+                verify_ssl: Some(true),
+            },
+            ..Default::default()
+        }
+        .with_defaults(Some(&defaults));
+        assert!(!resolved.use_cache);
+        assert!(resolved.verify_ssl);
+        
+        let resolved = FetchOptions {
+            overrides: FetchOverrides {
+                use_cache: Some(false),
+                verify_ssl: None,
+            },
+            ..Default::default()
+        }
+        .with_defaults(None);
+        assert!(!resolved.use_cache);
+        assert!(resolved.verify_ssl);
     }
     
     #[test]
@@ -295,4 +605,78 @@ mod tests {
         assert_eq!(config.user_agent, Some("mrf-rs/0.1.0".to_string()));
         assert_eq!(config.rate_limit, Some(100.0));
     }
+    
+    /// Source that only provides its HTTP client
+    struct ClientOnlySource {
+        client: base::HttpClient,
+    }
+    
+    #[async_trait]
+    impl MrfSource for ClientOnlySource {
+        fn name(&self) -> &str {
+            "Client only"
+        }
+        
+        fn source_id(&self) -> &str {
+            "client-only"
+        }
+        
+        async fn discover_files(&self) -> SourceResult<Vec<MrfFileInfo>> {
+            Ok(Vec::new())
+        }
+        
+        async fn fetch_file(&self, _: &MrfFileInfo, _: Option<FetchOptions>) -> SourceResult<Vec<u8>> {
+            unimplemented!()
+        }
+        
+        async fn fetch_file_to_path(
+            &self,
+            _: &MrfFileInfo,
+            _: &Path,
+            _: Option<FetchOptions>,
+            _: Option<ProgressCallback>,
+        ) -> SourceResult<()> {
+            unimplemented!()
+        }
+        
+        fn http_client(&self) -> Option<&base::HttpClient> {
+            Some(&self.client)
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_default_fetch_stream_uses_source_client() {
+        use futures_util::TryStreamExt;
+        
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("GET", "/file.json")
+            .match_header("x-api-key", "secret")
+            .with_body("{}")
+            .create_async()
+            .await;
+        
+        let config = SourceConfig {
+            rate_limit: None,
+            default_headers: BTreeMap::from([("x-api-key".to_string(), "secret".to_string())]),
+            ..Default::default()
+        };
+        let source = ClientOnlySource {
+            client: base::HttpClient::new(config).unwrap(),
+        };
+        let file_info = MrfFileInfo {
+            id: "file".to_string(),
+            name: "file.json".to_string(),
+            url: format!("{}/file.json", server.url()),
+            file_type: MrfFileType::InNetwork,
+            size_bytes: None,
+            last_modified: None,
+            compression: None,
+            reporting_plans: Vec::new(),
+            metadata: serde_json::Value::Null,
+        };
+        
+        let chunks: Vec<Bytes> = source.fetch_stream(&file_info, None, false).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), b"{}");
+        mock.assert_async().await;
+    }
 }
@@ -0,0 +1,152 @@
+//! Plan-to-file lookups over discovered MRF files
+//! 
+//! Each [`MrfFileInfo`] lists the reporting plans whose rates it contains.
+//! [`PlanIndex`] inverts that list so the files for a plan can be found
+//! without scanning every discovered file.
+//! 
+//! # Examples
+//! 
+//! ```no_run
+//! use mrf_rs::sources::{plans::PlanIndex, united_health::UnitedHealthSource, MrfSource};
+//! 
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let source = UnitedHealthSource::new()?;
+//! let files = source.discover_files().await?;
+//! 
+//! let index = PlanIndex::new(&files);
+//! for file in index.files_for_plan("123456789") {
+//!     println!("{} {:?}", file.url, file.file_type);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::MrfFileInfo;
+use crate::types::ReportingPlan;
+use std::collections::{HashMap, HashSet};
+
+/// Lookup between reporting plans and the files that cover them
+#[derive(Debug)]
+pub struct PlanIndex<'a> {
+    files: &'a [MrfFileInfo],
+    
+    /// Positions in `files` by plan ID, in discovery order
+    by_plan: HashMap<&'a str, Vec<usize>>,
+    
+    /// Positions in `files` by file ID
+    by_file: HashMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> PlanIndex<'a> {
+    /// Index the reporting plans of `files`
+    pub fn new(files: &'a [MrfFileInfo]) -> Self {
+        let mut by_plan: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut by_file: HashMap<&str, Vec<usize>> = HashMap::new();
+        
+        for (position, file) in files.iter().enumerate() {
+            by_file.entry(file.id.as_str()).or_default().push(position);
+            for plan in &file.reporting_plans {
+                let positions = by_plan.entry(plan.plan_id.as_str()).or_default();
+                // A file may list the same plan ID under several names
+                if positions.last() != Some(&position) {
+                    positions.push(position);
+                }
+            }
+        }
+        
+        Self {
+            files,
+            by_plan,
+            by_file,
+        }
+    }
+    
+    /// Files covering the plan with `plan_id`, an EIN or HIOS identifier as
+    /// published in the table of contents
+    pub fn files_for_plan(&self, plan_id: &str) -> impl Iterator<Item = &'a MrfFileInfo> + '_ {
+        self.by_plan
+            .get(plan_id)
+            .into_iter()
+            .flatten()
+            .map(|&position| &self.files[position])
+    }
+    
+    /// Plans covered by the file with `file_id`
+    /// 
+    /// A file listed by several reporting structures covers the plans of all
+    /// of them; each plan is returned once.
+    pub fn plans_for_file(&self, file_id: &str) -> Vec<&'a ReportingPlan> {
+        let mut seen = HashSet::new();
+        self.by_file
+            .get(file_id)
+            .into_iter()
+            .flatten()
+            .flat_map(|&position| &self.files[position].reporting_plans)
+            .filter(|plan| seen.insert((plan.plan_id.as_str(), plan.plan_name.as_str())))
+            .collect()
+    }
+    
+    /// IDs of all plans covered by at least one file
+    pub fn plan_ids(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.by_plan.keys().copied()
+    }
+    
+    /// Number of distinct plan IDs
+    pub fn plan_count(&self) -> usize {
+        self.by_plan.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::MrfFileType;
+    
+    fn plan(plan_id: &str, plan_name: &str) -> ReportingPlan {
+        serde_json::from_value(serde_json::json!({
+            "plan_name": plan_name,
+            "plan_id_type": "EIN",
+            "plan_id": plan_id,
+            "plan_market_type": "group",
+        }))
+        .unwrap()
+    }
+    
+    fn file(id: &str, reporting_plans: Vec<ReportingPlan>) -> MrfFileInfo {
+        MrfFileInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            url: format!("https://example.com/{}.json.gz", id),
+            file_type: MrfFileType::InNetwork,
+            size_bytes: None,
+            last_modified: None,
+            compression: None,
+            reporting_plans,
+            metadata: serde_json::Value::Null,
+        }
+    }
+    
+    #[test]
+    fn test_plan_and_file_lookups() {
+        let files = vec![
+            file("a", vec![plan("111", "Acme PPO"), plan("222", "Widgets HMO")]),
+            file("b", vec![plan("111", "Acme PPO"), plan("111", "Acme PPO Plus")]),
+            file("a", vec![plan("111", "Acme PPO"), plan("333", "Gadgets EPO")]),
+            file("c", Vec::new()),
+        ];
+        let index = PlanIndex::new(&files);
+        
+        assert_eq!(index.plan_count(), 3);
+        let urls: Vec<&str> = index.files_for_plan("111").map(|f| f.url.as_str()).collect();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(urls[1], "https://example.com/b.json.gz");
+        assert_eq!(index.files_for_plan("222").count(), 1);
+        assert_eq!(index.files_for_plan("999").count(), 0);
+        
+        let plans: Vec<&str> = index.plans_for_file("a").iter().map(|p| p.plan_id.as_str()).collect();
+        assert_eq!(plans, vec!["111", "222", "333"]);
+        assert_eq!(index.plans_for_file("b").len(), 2);
+        assert!(index.plans_for_file("c").is_empty());
+        assert!(index.plans_for_file("missing").is_empty());
+    }
+}
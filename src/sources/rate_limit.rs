@@ -0,0 +1,422 @@
+//! Request rate limiting and bandwidth throttling
+//! 
+//! [`RateLimiter`] is a token-bucket limiter. Each bucket refills at a steady
+//! number of requests per second and holds up to `burst` tokens, so short
+//! bursts go out immediately while the long-run rate stays bounded. Limits can
+//! be applied globally, per host, or both, and a concurrency cap bounds the
+//! number of requests in flight.
+//! 
+//! A limiter is cheap to clone and all clones share their buckets. Put the
+//! same limiter in the [`SourceConfig`](super::SourceConfig) of several
+//! sources to keep their combined traffic to one CDN within its limits.
+//! 
+//! # Examples
+//! 
+//! ```no_run
+//! use mrf_rs::sources::rate_limit::{RateLimitConfig, RateLimiter};
+//! use mrf_rs::sources::SourceConfig;
+//! 
+//! let limiter = RateLimiter::new(RateLimitConfig {
+//!     per_host_requests_per_second: Some(10.0),
+//!     per_host_burst: 20,
+//!     max_concurrent: Some(8),
+//!     ..Default::default()
+//! });
+//! 
+//! let config = SourceConfig {
+//!     rate_limiter: Some(limiter.clone()),
+//!     ..Default::default()
+//! };
+//! ```
+//! 
+//! [`BandwidthThrottle`] caps the combined transfer rate of all downloads
+//! sharing it. Its limit can be changed at any time, for example to slow bulk
+//! downloads during business hours.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{sleep, Instant};
+
+/// Settings of a [`RateLimiter`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Requests per second across all hosts
+    pub requests_per_second: Option<f64>,
+    
+    /// Number of requests that may be sent at once before the global rate applies
+    pub burst: u32,
+    
+    /// Requests per second to each host
+    pub per_host_requests_per_second: Option<f64>,
+    
+    /// Number of requests to one host that may be sent at once before the
+    /// per-host rate applies
+    pub per_host_burst: u32,
+    
+    /// Maximum number of requests in flight
+    pub max_concurrent: Option<usize>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: None,
+            burst: 1,
+            per_host_requests_per_second: None,
+            per_host_burst: 1,
+            max_concurrent: None,
+        }
+    }
+}
+
+/// Shareable token-bucket rate limiter
+#[derive(Clone)]
+pub struct RateLimiter {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: RateLimitConfig,
+    global: Option<TokenBucket>,
+    hosts: Mutex<HashMap<String, Arc<TokenBucket>>>,
+    concurrency: Option<Arc<Semaphore>>,
+}
+
+/// Permission to send a request, released when dropped
+/// 
+/// Holds a slot of the concurrency cap, if there is one.
+#[derive(Debug)]
+pub struct RateLimitPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    /// Create a limiter from its settings
+    pub fn new(config: RateLimitConfig) -> Self {
+        let global = config
+            .requests_per_second
+            .map(|rate| TokenBucket::new(rate, config.burst));
+        let concurrency = config
+            .max_concurrent
+            .map(|max| Arc::new(Semaphore::new(max.max(1))));
+        
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                global,
+                hosts: Mutex::new(HashMap::new()),
+                concurrency,
+            }),
+        }
+    }
+    
+    /// Create a limiter allowing `rate` requests per second across all hosts
+    pub fn per_second(rate: f64) -> Self {
+        Self::new(RateLimitConfig {
+            requests_per_second: Some(rate),
+            ..Default::default()
+        })
+    }
+    
+    /// Settings of this limiter
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.inner.config
+    }
+    
+    /// Wait until a request to `url` may be sent
+    /// 
+    /// Hold the returned permit for as long as the request is in flight.
+    pub async fn acquire(&self, url: &str) -> RateLimitPermit {
+        let permit = match &self.inner.concurrency {
+            Some(semaphore) => Some(
+                Arc::clone(semaphore)
+                    .acquire_owned()
+                    .await
+                    .expect("rate limiter semaphore is never closed"),
+            ),
+            None => None,
+        };
+        
+        self.wait_for_token(url).await;
+        
+        RateLimitPermit { _permit: permit }
+    }
+    
+    /// Wait for a rate token without taking a concurrency slot
+    /// 
+    /// Used for retries of a request that already holds a permit.
+    pub async fn wait_for_token(&self, url: &str) {
+        let mut wait = self
+            .inner
+            .global
+            .as_ref()
+            .map_or(Duration::ZERO, TokenBucket::reserve);
+        
+        if let Some(bucket) = self.host_bucket(url) {
+            wait = wait.max(bucket.reserve());
+        }
+        
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+    
+    fn host_bucket(&self, url: &str) -> Option<Arc<TokenBucket>> {
+        let rate = self.inner.config.per_host_requests_per_second?;
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        
+        let mut hosts = self.inner.hosts.lock().expect("rate limiter lock poisoned");
+        let bucket = hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(TokenBucket::new(rate, self.inner.config.per_host_burst)));
+        Some(Arc::clone(bucket))
+    }
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("config", &self.inner.config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Token bucket handing out reservations
+/// 
+/// Tokens may go negative: a request that finds the bucket empty reserves
+/// the next token and is told how long to wait for it, so waiting requests
+/// are served in order without holding the lock while they sleep.
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            rate: rate.max(f64::MIN_POSITIVE),
+            capacity,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                updated: Instant::now(),
+            }),
+        }
+    }
+    
+    /// Take a token, returning how long to wait before using it
+    fn reserve(&self) -> Duration {
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+        let now = Instant::now();
+        let refill = now.duration_since(state.updated).as_secs_f64() * self.rate;
+        state.tokens = (state.tokens + refill).min(self.capacity) - 1.0;
+        state.updated = now;
+        
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        }
+    }
+}
+
+/// Shared, runtime-adjustable cap on download bandwidth
+/// 
+/// All clones share one byte budget, so the limit applies to the sum of all
+/// downloads using the throttle. Up to one second's worth of bytes may be
+/// transferred in a burst.
+#[derive(Clone)]
+pub struct BandwidthThrottle {
+    inner: Arc<ThrottleInner>,
+}
+
+struct ThrottleInner {
+    /// Bytes per second, 0 for unlimited
+    limit: AtomicU64,
+    state: Mutex<BucketState>,
+}
+
+impl BandwidthThrottle {
+    /// Create a throttle allowing `bytes_per_second`, or no limit for `None`
+    /// 
+    /// A limit of zero is raised to one byte per second.
+    pub fn new(bytes_per_second: Option<u64>) -> Self {
+        let limit = Self::stored_limit(bytes_per_second);
+        Self {
+            inner: Arc::new(ThrottleInner {
+                limit: AtomicU64::new(limit),
+                state: Mutex::new(BucketState {
+                    tokens: limit as f64,
+                    updated: Instant::now(),
+                }),
+            }),
+        }
+    }
+    
+    /// Create a throttle without a limit
+    pub fn unlimited() -> Self {
+        Self::new(None)
+    }
+    
+    /// Current limit in bytes per second
+    pub fn limit(&self) -> Option<u64> {
+        match self.inner.limit.load(Ordering::Relaxed) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+    
+    /// Change the limit; takes effect for all downloads immediately
+    /// 
+    /// A limit of zero is raised to one byte per second.
+    pub fn set_limit(&self, bytes_per_second: Option<u64>) {
+        self.inner.limit.store(Self::stored_limit(bytes_per_second), Ordering::Relaxed);
+    }
+    
+    /// Value stored for a limit, keeping 0 free to mean unlimited
+    fn stored_limit(bytes_per_second: Option<u64>) -> u64 {
+        bytes_per_second.map_or(0, |limit| limit.max(1))
+    }
+    
+    /// Account for `bytes` received, waiting as long as needed to stay
+    /// within the limit
+    pub async fn consume(&self, bytes: usize) {
+        let wait = {
+            let rate = self.inner.limit.load(Ordering::Relaxed);
+            let mut state = self.inner.state.lock().expect("bandwidth throttle lock poisoned");
+            let now = Instant::now();
+            
+            if rate == 0 {
+                state.tokens = 0.0;
+                state.updated = now;
+                return;
+            }
+            
+            let rate = rate as f64;
+            let refill = now.duration_since(state.updated).as_secs_f64() * rate;
+            state.tokens = (state.tokens + refill).min(rate) - bytes as f64;
+            state.updated = now;
+            
+            if state.tokens >= 0.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(-state.tokens / rate)
+            }
+        };
+        
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+}
+
+impl Default for BandwidthThrottle {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl std::fmt::Debug for BandwidthThrottle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BandwidthThrottle")
+            .field("limit", &self.limit())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_burst_then_steady_rate() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: Some(20.0),
+            burst: 2,
+            ..Default::default()
+        });
+        
+        let start = Instant::now();
+        for _ in 0..2 {
+            limiter.acquire("https://example.com/a").await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+        
+        for _ in 0..2 {
+            limiter.acquire("https://example.com/a").await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+    
+    #[tokio::test]
+    async fn test_per_host_buckets_and_sharing() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_host_requests_per_second: Some(1.0),
+            ..Default::default()
+        });
+        let shared = limiter.clone();
+        
+        let start = Instant::now();
+        limiter.acquire("https://a.example.com/file.json").await;
+        shared.acquire("https://b.example.com/file.json").await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+        
+        let third = tokio::time::timeout(
+            Duration::from_millis(100),
+            shared.acquire("https://a.example.com/other.json"),
+        )
+        .await;
+        assert!(third.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_concurrent: Some(1),
+            ..Default::default()
+        });
+        
+        let permit = limiter.acquire("https://example.com/a").await;
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("https://example.com/b")).await;
+        assert!(blocked.is_err());
+        
+        drop(permit);
+        limiter.acquire("https://example.com/b").await;
+    }
+    
+    #[tokio::test]
+    async fn test_bandwidth_throttle_adjusts_at_runtime() {
+        let throttle = BandwidthThrottle::new(Some(10_000));
+        let shared = throttle.clone();
+        
+        let start = Instant::now();
+        throttle.consume(10_000).await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+        shared.consume(2_000).await;
+        assert!(start.elapsed() >= Duration::from_millis(180));
+        
+        shared.set_limit(Some(0));
+        assert_eq!(throttle.limit(), Some(1));
+        assert_eq!(BandwidthThrottle::new(Some(0)).limit(), Some(1));
+        
+        shared.set_limit(None);
+        assert_eq!(throttle.limit(), None);
+        let start = Instant::now();
+        throttle.consume(1_000_000).await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+}
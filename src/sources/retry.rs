@@ -0,0 +1,230 @@
+//! Retry policy for HTTP requests
+//! 
+//! [`RetryPolicy`] controls how [`HttpClient`](super::base::HttpClient)
+//! retries failed requests: which status codes are retried, how long to wait
+//! between attempts and how long to keep trying overall. The number of
+//! attempts comes from [`FetchOptions::max_retries`](super::FetchOptions).
+//! 
+//! Waits grow exponentially from `base_delay_ms` up to `max_delay_ms`. With
+//! jitter enabled each wait is drawn uniformly from the upper half of the
+//! backoff interval, so concurrent downloads do not retry in lockstep. A
+//! `Retry-After` header, in seconds or HTTP-date form, replaces the computed
+//! backoff; when it asks for a longer wait than `max_delay_ms` the request is
+//! not retried, and a `429` response is reported as
+//! [`SourceError::RateLimited`](super::SourceError::RateLimited).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+
+/// Policy for retrying failed HTTP requests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Delay before the first retry, in milliseconds
+    pub base_delay_ms: u64,
+    
+    /// Upper bound for any wait between attempts, in milliseconds
+    pub max_delay_ms: u64,
+    
+    /// Whether to randomize delays
+    pub jitter: bool,
+    
+    /// HTTP status codes that are retried
+    pub retryable_statuses: Vec<u16>,
+    
+    /// Total time allowed for a request including all retries, in seconds.
+    /// A download shares one deadline across all of its requests, including
+    /// resumed and ranged ones.
+    pub deadline_secs: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay_ms: 1_000,
+            max_delay_ms: 60_000,
+            jitter: true,
+            retryable_statuses: vec![408, 425, 429, 500, 502, 503, 504],
+            deadline_secs: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a response with this status should be retried
+    pub fn is_retryable_status(&self, status: u16) -> bool {
+        self.retryable_statuses.contains(&status)
+    }
+    
+    /// Backoff before retry number `attempt`, starting at 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32);
+        let delay = self
+            .base_delay_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_ms);
+        
+        let delay = if self.jitter && delay > 0 {
+            let half = delay / 2;
+            half + random_u64() % (delay - half + 1)
+        } else {
+            delay
+        };
+        
+        Duration::from_millis(delay)
+    }
+}
+
+/// Parse a `Retry-After` header value, in delay-seconds or HTTP-date form
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Retry bookkeeping for one request
+#[derive(Debug)]
+pub(crate) struct RetryState<'a> {
+    policy: &'a RetryPolicy,
+    max_retries: u32,
+    attempt: u32,
+    deadline: Option<Instant>,
+}
+
+impl<'a> RetryState<'a> {
+    pub(crate) fn new(policy: &'a RetryPolicy, max_retries: u32) -> Self {
+        Self {
+            policy,
+            max_retries,
+            attempt: 0,
+            deadline: policy
+                .deadline_secs
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+        }
+    }
+    
+    /// Number of retries made so far
+    pub(crate) fn attempt(&self) -> u32 {
+        self.attempt
+    }
+    
+    /// State for one of several parts of the request that are retried
+    /// concurrently, with the retries and deadline left in this one
+    pub(crate) fn split(&self) -> Self {
+        Self {
+            policy: self.policy,
+            max_retries: self.max_retries.saturating_sub(self.attempt),
+            attempt: 0,
+            deadline: self.deadline,
+        }
+    }
+    
+    /// Delay before the next retry, or `None` when retries are exhausted, the
+    /// server asks for a longer wait than `max_delay_ms`, or the wait would
+    /// run past the deadline
+    pub(crate) fn next_delay(&mut self, retry_after: Option<Duration>) -> Option<Duration> {
+        if self.attempt >= self.max_retries {
+            return None;
+        }
+        if retry_after.is_some_and(|delay| delay > Duration::from_millis(self.policy.max_delay_ms)) {
+            return None;
+        }
+        
+        let delay = retry_after.unwrap_or_else(|| self.policy.backoff(self.attempt + 1));
+        if let Some(deadline) = self.deadline {
+            if Instant::now() + delay >= deadline {
+                return None;
+            }
+        }
+        
+        self.attempt += 1;
+        Some(delay)
+    }
+}
+
+/// Random number for jitter, without pulling in a RNG crate
+fn random_u64() -> u64 {
+    RandomState::new().hash_one(Instant::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:30:00 GMT", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+    
+    #[test]
+    fn test_backoff_and_deadline() {
+        let policy = RetryPolicy {
+            base_delay_ms: 100,
+            max_delay_ms: 1_000,
+            jitter: false,
+            deadline_secs: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_millis(1_000));
+        
+        let jittered = RetryPolicy { jitter: true, ..policy.clone() };
+        for attempt in 1..5 {
+            let delay = jittered.backoff(attempt);
+            assert!(delay >= policy.backoff(attempt) / 2 && delay <= policy.backoff(attempt));
+        }
+        
+        let mut state = RetryState::new(&policy, 2);
+        assert_eq!(state.next_delay(None), Some(Duration::from_millis(100)));
+        assert_eq!(state.next_delay(Some(Duration::from_secs(120))), None);
+        assert_eq!(state.next_delay(None), Some(Duration::from_millis(200)));
+        assert_eq!(state.next_delay(None), None);
+    }
+    
+    #[test]
+    fn test_retry_after_is_bounded_by_max_delay() {
+        let policy = RetryPolicy {
+            max_delay_ms: 5_000,
+            jitter: false,
+            ..Default::default()
+        };
+        
+        let mut state = RetryState::new(&policy, 3);
+        assert_eq!(state.next_delay(Some(Duration::from_secs(5))), Some(Duration::from_secs(5)));
+        assert_eq!(state.next_delay(Some(Duration::from_secs(3600))), None);
+        // Giving up on a long wait does not use up a retry
+        assert_eq!(state.attempt(), 1);
+    }
+    
+    #[test]
+    fn test_split_keeps_remaining_retries_and_deadline() {
+        let policy = RetryPolicy {
+            jitter: false,
+            deadline_secs: Some(60),
+            ..Default::default()
+        };
+        
+        let mut state = RetryState::new(&policy, 3);
+        state.next_delay(None).unwrap();
+        let mut part = state.split();
+        assert_eq!(part.deadline, state.deadline);
+        assert!(part.next_delay(None).is_some());
+        assert!(part.next_delay(None).is_some());
+        assert_eq!(part.next_delay(None), None);
+    }
+}
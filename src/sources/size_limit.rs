@@ -0,0 +1,245 @@
+//! Size limits for downloaded content
+//! 
+//! [`SizeGuard`] enforces [`FetchOptions::max_size`] and
+//! [`FetchOptions::max_decompressed_size`] on a body while it streams in, so
+//! an oversized response is aborted without being buffered or written out
+//! first. Bytes are counted as the HTTP client yields them, that is after any
+//! transparent `Content-Encoding` decoding, so a body the client decoded also
+//! counts against the decompressed size limit.
+//! 
+//! When the body is itself gzip-compressed, as most `.json.gz` MRF files are,
+//! it is also inflated on the fly and the output counted and discarded. A
+//! small file that would expand to terabytes (a "gzip bomb") is rejected
+//! during the download rather than when it is parsed.
+
+use super::{FetchOptions, SourceError, SourceResult};
+use flate2::write::MultiGzDecoder;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+use tracing::debug;
+
+/// Leading bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Streaming check of a response body against the limits in [`FetchOptions`]
+#[derive(Debug)]
+pub struct SizeGuard {
+    max_size: Option<u64>,
+    max_decompressed_size: Option<u64>,
+    received: u64,
+    inflater: Inflater,
+}
+
+impl SizeGuard {
+    /// Create a guard for the limits set in `options`
+    pub fn new(options: &FetchOptions) -> Self {
+        Self {
+            max_size: options.max_size,
+            max_decompressed_size: options.max_decompressed_size,
+            received: 0,
+            inflater: Inflater::Pending(Vec::new()),
+        }
+    }
+    
+    /// Number of bytes received so far
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+    
+    /// Account for the next chunk of the body
+    /// 
+    /// Fails with [`SourceError::SizeLimitExceeded`] or
+    /// [`SourceError::DecompressedSizeLimitExceeded`] as soon as a limit is
+    /// passed.
+    pub fn update(&mut self, chunk: &[u8]) -> SourceResult<()> {
+        self.count(chunk.len() as u64)?;
+        self.inflate(chunk)
+    }
+    
+    /// Account for `bytes` already received without inspecting them, when
+    /// resuming a download and no decompressed size limit is set
+    pub fn skip(&mut self, bytes: u64) -> SourceResult<()> {
+        self.count(bytes)
+    }
+    
+    fn count(&mut self, bytes: u64) -> SourceResult<()> {
+        self.received += bytes;
+        if let Some(limit) = self.max_size {
+            if self.received > limit {
+                return Err(SourceError::SizeLimitExceeded {
+                    limit,
+                    received: self.received,
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// Account for the body already stored in `path`, when resuming a download
+    pub async fn update_from_file(&mut self, path: &Path) -> SourceResult<()> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut buffer = vec![0u8; 1 << 16];
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            self.update(&buffer[..read])?;
+        }
+    }
+    
+    /// Start over for a body that is received again from the beginning
+    pub fn reset(&mut self) {
+        self.received = 0;
+        self.inflater = Inflater::Pending(Vec::new());
+    }
+    
+    fn inflate(&mut self, chunk: &[u8]) -> SourceResult<()> {
+        let Some(limit) = self.max_decompressed_size else {
+            return Ok(());
+        };
+        
+        let head;
+        let input = match &mut self.inflater {
+            Inflater::Pending(pending) => {
+                pending.extend_from_slice(chunk);
+                if pending.len() < GZIP_MAGIC.len() {
+                    return Ok(());
+                }
+                head = std::mem::take(pending);
+                self.inflater = if head.starts_with(&GZIP_MAGIC) {
+                    Inflater::Gzip(Box::new(MultiGzDecoder::new(ByteCounter { written: 0, limit })))
+                } else {
+                    Inflater::Off
+                };
+                &head[..]
+            }
+            _ => chunk,
+        };
+        
+        let Inflater::Gzip(decoder) = &mut self.inflater else {
+            // A body that is not gzip was either never compressed or already
+            // decoded by the HTTP client, so it arrives at its full size
+            if self.received > limit {
+                return Err(SourceError::DecompressedSizeLimitExceeded {
+                    limit,
+                    received: self.received,
+                });
+            }
+            return Ok(());
+        };
+        if let Err(e) = decoder.write_all(input) {
+            if decoder.get_ref().written > limit {
+                return Err(SourceError::DecompressedSizeLimitExceeded {
+                    limit,
+                    received: self.received,
+                });
+            }
+            
+            // Corrupt data is for the parser to report, stop measuring
+            debug!("Cannot measure decompressed size: {}", e);
+            self.inflater = Inflater::Off;
+        }
+        Ok(())
+    }
+}
+
+/// Decompression state of a guarded body
+enum Inflater {
+    /// Waiting for enough bytes to tell whether the body is gzip
+    Pending(Vec<u8>),
+    
+    /// Inflating a gzip body
+    Gzip(Box<MultiGzDecoder<ByteCounter>>),
+    
+    /// The body is not gzip, or cannot be decoded
+    Off,
+}
+
+impl fmt::Debug for Inflater {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inflater::Pending(head) => f.debug_tuple("Pending").field(head).finish(),
+            Inflater::Gzip(decoder) => f.debug_tuple("Gzip").field(&decoder.get_ref().written).finish(),
+            Inflater::Off => f.write_str("Off"),
+        }
+    }
+}
+
+/// Sink counting the bytes written to it, failing once `limit` is passed
+struct ByteCounter {
+    written: u64,
+    limit: u64,
+}
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written += buf.len() as u64;
+        if self.written > self.limit {
+            return Err(io::Error::other("decompressed size limit exceeded"));
+        }
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    
+    fn options(max_size: Option<u64>, max_decompressed_size: Option<u64>) -> FetchOptions {
+        FetchOptions {
+            max_size,
+            max_decompressed_size,
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_max_size_counts_streamed_bytes() {
+        let mut guard = SizeGuard::new(&options(Some(10), None));
+        guard.update(b"01234").unwrap();
+        guard.update(b"56789").unwrap();
+        
+        match guard.update(b"a") {
+            Err(SourceError::SizeLimitExceeded { limit, received }) => {
+                assert_eq!(limit, 10);
+                assert_eq!(received, 11);
+            }
+            other => panic!("Expected SizeLimitExceeded, got {:?}", other),
+        }
+        
+        guard.reset();
+        guard.update(b"0123456789").unwrap();
+    }
+    
+    #[test]
+    fn test_gzip_bomb_is_rejected() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![b'0'; 1 << 20]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() < 4096);
+        
+        let mut guard = SizeGuard::new(&options(Some(4096), Some(1 << 20)));
+        for chunk in compressed.chunks(1) {
+            guard.update(chunk).unwrap();
+        }
+        
+        let mut guard = SizeGuard::new(&options(Some(4096), Some(1000)));
+        let result = compressed.chunks(100).try_for_each(|chunk| guard.update(chunk));
+        assert!(matches!(result, Err(SourceError::DecompressedSizeLimitExceeded { limit: 1000, .. })));
+        
+        // Plain or transparently decoded bodies count at their received size
+        let mut guard = SizeGuard::new(&options(None, Some(20)));
+        guard.update(b"{\"plain\": true}").unwrap();
+        let result = guard.update(b"{\"plain\": true}");
+        assert!(matches!(result, Err(SourceError::DecompressedSizeLimitExceeded { limit: 20, received: 30 })));
+    }
+}
@@ -0,0 +1,256 @@
+//! Content sniffing for MRF files
+//! 
+//! URLs and file names are only hints: payers publish gzip files without a
+//! `.gz` extension and name files freely. [`sniff`] looks at the first
+//! [`SNIFF_LEN`] bytes of a file instead. It detects compression from magic
+//! bytes and, after decompressing the start of gzip data, the MRF type from
+//! the keys of the top-level JSON object.
+//! 
+//! [`Sniffed::apply_to`] reconciles the result with the URL-based hints on an
+//! [`MrfFileInfo`]; the content wins wherever it is conclusive.
+//! 
+//! # Examples
+//! 
+//! ```no_run
+//! use mrf_rs::sources::sniff;
+//! 
+//! # async fn example() -> mrf_rs::sources::SourceResult<()> {
+//! let sniffed = sniff::sniff_file("download.bin".as_ref()).await?;
+//! println!("{:?} {:?}", sniffed.compression, sniffed.file_type);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{CompressionType, MrfFileInfo, MrfFileType, SourceResult};
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+use tracing::debug;
+
+/// Number of leading bytes examined when sniffing
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+/// Most decompressed bytes examined for the top-level keys
+const MAX_INFLATED_LEN: usize = 64 * 1024;
+
+/// What the start of a file reveals about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sniffed {
+    /// Compression detected from magic bytes
+    pub compression: CompressionType,
+    
+    /// MRF type detected from top-level JSON keys, `Unknown` if the keys
+    /// seen were not conclusive
+    pub file_type: MrfFileType,
+}
+
+impl Sniffed {
+    /// Update the URL-based hints of `file_info` with the sniffed content
+    /// 
+    /// Compression is always taken from the content. The file type is taken
+    /// from the content unless it could not be determined.
+    pub fn apply_to(&self, file_info: &mut MrfFileInfo) {
+        if file_info.compression.is_some_and(|hint| hint != self.compression) {
+            debug!(
+                "{} looks {:?} compressed, not {:?} as its URL suggests",
+                file_info.url, self.compression, file_info.compression
+            );
+        }
+        file_info.compression = Some(self.compression);
+        
+        if self.file_type != MrfFileType::Unknown {
+            if file_info.file_type != self.file_type && file_info.file_type != MrfFileType::Unknown {
+                debug!(
+                    "{} contains a {:?} file, not {:?} as its URL suggests",
+                    file_info.url, self.file_type, file_info.file_type
+                );
+            }
+            file_info.file_type = self.file_type;
+        }
+    }
+}
+
+/// Sniff the leading bytes of a file
+pub fn sniff(head: &[u8]) -> Sniffed {
+    let compression = detect_compression(head);
+    let file_type = match compression {
+        CompressionType::None => detect_file_type(head),
+        CompressionType::Gzip => detect_file_type(&inflate_head(head)),
+        // Archives and bzip2 would need more than the first bytes to peek inside
+        CompressionType::Zip | CompressionType::Bzip2 => MrfFileType::Unknown,
+    };
+    
+    Sniffed {
+        compression,
+        file_type,
+    }
+}
+
+/// Sniff the start of a file on disk
+pub async fn sniff_file(path: &Path) -> SourceResult<Sniffed> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    (&mut file).take(SNIFF_LEN as u64).read_to_end(&mut head).await?;
+    Ok(sniff(&head))
+}
+
+/// Detect compression from magic bytes
+pub fn detect_compression(head: &[u8]) -> CompressionType {
+    if head.starts_with(&[0x1f, 0x8b]) {
+        CompressionType::Gzip
+    } else if head.starts_with(b"PK\x03\x04") {
+        CompressionType::Zip
+    } else if head.starts_with(b"BZh") {
+        CompressionType::Bzip2
+    } else {
+        CompressionType::None
+    }
+}
+
+/// Detect the MRF type of uncompressed JSON from its top-level keys
+/// 
+/// Each MRF schema has a key no other one uses at the top level. Keys that
+/// fall beyond the examined bytes are not seen, so a file whose header is
+/// followed by a long array may come out `Unknown`.
+pub fn detect_file_type(json: &[u8]) -> MrfFileType {
+    top_level_keys(json)
+        .iter()
+        .find_map(|key| match key.as_str() {
+            "reporting_structure" => Some(MrfFileType::TableOfContents),
+            "in_network" | "provider_references" => Some(MrfFileType::InNetwork),
+            "out_of_network" => Some(MrfFileType::AllowedAmount),
+            "provider_groups" => Some(MrfFileType::ProviderReference),
+            _ => None,
+        })
+        .unwrap_or(MrfFileType::Unknown)
+}
+
+/// Keys of the top-level JSON object found in a possibly truncated document
+pub fn top_level_keys(json: &[u8]) -> Vec<String> {
+    let json = json.strip_prefix(b"\xef\xbb\xbf").unwrap_or(json);
+    let mut keys = Vec::new();
+    if json.iter().find(|b| !b.is_ascii_whitespace()) != Some(&b'{') {
+        return keys;
+    }
+    
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < json.len() {
+        match json[i] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            b'"' => {
+                let start = i + 1;
+                let Some(len) = string_len(&json[start..]) else {
+                    break;
+                };
+                i = start + len + 1;
+                
+                let is_key = json[i..].iter().find(|b| !b.is_ascii_whitespace()) == Some(&b':');
+                if depth == 1 && is_key {
+                    keys.push(String::from_utf8_lossy(&json[start..start + len]).into_owned());
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+/// Length of a JSON string body up to its closing quote, if it is complete
+fn string_len(bytes: &[u8]) -> Option<usize> {
+    let mut escaped = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'"' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Decompress as much of a truncated gzip stream as possible
+fn inflate_head(head: &[u8]) -> Vec<u8> {
+    let mut decoder = MultiGzDecoder::new(head);
+    let mut inflated = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while inflated.len() < MAX_INFLATED_LEN {
+        match decoder.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => inflated.extend_from_slice(&buffer[..read]),
+        }
+    }
+    inflated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    
+    #[test]
+    fn test_top_level_keys_of_truncated_json() {
+        let json = br#"{"reporting_entity_name": "A \"quoted\" {name}", "nested": {"in_network": []}, "version": "1.0", "provider_refer"#;
+        assert_eq!(top_level_keys(json), vec!["reporting_entity_name", "nested", "version"]);
+        assert!(top_level_keys(b"[1, 2]").is_empty());
+    }
+    
+    #[test]
+    fn test_sniff_mrf_types() {
+        let toc = br#"{"reporting_entity_name": "Acme", "reporting_structure": [{"#;
+        assert_eq!(sniff(toc).file_type, MrfFileType::TableOfContents);
+        
+        let provider_reference = br#"{"version": "1.0.0", "provider_groups": [{"npi": [1"#;
+        assert_eq!(sniff(provider_reference).file_type, MrfFileType::ProviderReference);
+        
+        let allowed_amount = br#"{"reporting_entity_name": "Acme", "out_of_network": [{"#;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(allowed_amount).unwrap();
+        let compressed = encoder.finish().unwrap();
+        
+        let sniffed = sniff(&compressed[..compressed.len() - 4]);
+        assert_eq!(sniffed.compression, CompressionType::Gzip);
+        assert_eq!(sniffed.file_type, MrfFileType::AllowedAmount);
+        
+        assert_eq!(sniff(b"PK\x03\x04rest").compression, CompressionType::Zip);
+        assert_eq!(sniff(b"<html>").file_type, MrfFileType::Unknown);
+    }
+    
+    #[test]
+    fn test_apply_to_file_info() {
+        let mut file_info = MrfFileInfo {
+            id: "1".to_string(),
+            name: "Doctors protocol".to_string(),
+            url: "https://example.com/doctors-protocol".to_string(),
+            file_type: MrfFileType::TableOfContents,
+            size_bytes: None,
+            last_modified: None,
+            compression: None,
+            reporting_plans: Vec::new(),
+            metadata: serde_json::Value::Null,
+        };
+        
+        Sniffed {
+            compression: CompressionType::Gzip,
+            file_type: MrfFileType::InNetwork,
+        }
+        .apply_to(&mut file_info);
+        assert_eq!(file_info.compression, Some(CompressionType::Gzip));
+        assert_eq!(file_info.file_type, MrfFileType::InNetwork);
+        
+        Sniffed {
+            compression: CompressionType::None,
+            file_type: MrfFileType::Unknown,
+        }
+        .apply_to(&mut file_info);
+        assert_eq!(file_info.compression, Some(CompressionType::None));
+        assert_eq!(file_info.file_type, MrfFileType::InNetwork);
+    }
+}
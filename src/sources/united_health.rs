@@ -25,15 +25,20 @@
 //! ```
 
 use super::{
-    base::{BaseSource, utils},
-    CompressionType, FetchOptions, MrfFileInfo, MrfFileType, MrfSource, ProgressCallback,
+    base::{utils, BaseSource, HttpClient},
+    discovery::{self, DiscoveryFilter, DiscoveryReport, IndexFileReport, IndexOutcome, UniqueMrfFile},
+    rate_limit::BandwidthThrottle,
+    FetchOptions, MrfFileInfo, MrfFileType, MrfSource, ProgressCallback,
     SourceConfig, SourceError, SourceResult,
 };
+use crate::types::ReportingPlan;
 use async_trait::async_trait;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use futures_util::stream::{self, StreamExt, FuturesUnordered};
+use futures_util::stream::{self, StreamExt};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
@@ -45,6 +50,15 @@ pub struct UnitedHealthConfig {
     pub transparency_url: String,
     /// API endpoint for fetching blob list
     pub api_endpoint: String,
+    /// Send a HEAD request for every discovered file, with at most this many
+    /// in flight, to fill in sizes, modification times, ETags and content
+    /// types; `None` skips the requests
+    #[serde(default)]
+    pub head_concurrency: Option<usize>,
+    /// Strict mode: fail discovery when more than this share (0 to 1) of
+    /// the index files cannot be downloaded or parsed; `None` only logs them
+    #[serde(default)]
+    pub max_index_error_rate: Option<f64>,
 }
 
 impl Default for UnitedHealthConfig {
@@ -52,6 +66,8 @@ impl Default for UnitedHealthConfig {
         Self {
             transparency_url: "https://transparency-in-coverage.uhc.com/".to_string(),
             api_endpoint: "https://transparency-in-coverage.uhc.com/api/v1/uhc/blobs".to_string(),
+            head_concurrency: None,
+            max_index_error_rate: None,
         }
     }
 }
@@ -60,6 +76,7 @@ impl Default for UnitedHealthConfig {
 pub struct UnitedHealthSource {
     base: BaseSource,
     config: UnitedHealthConfig,
+    filter: DiscoveryFilter,
 }
 
 impl UnitedHealthSource {
@@ -70,17 +87,56 @@ impl UnitedHealthSource {
     
     /// Create a new United Health source with custom configuration
     pub fn with_config(config: UnitedHealthConfig) -> SourceResult<Self> {
-        let mut source_config = SourceConfig::default();
-        source_config.base_url = config.transparency_url.clone();
-        source_config.user_agent = Some("mrf-rs/0.1.0 (United Health MRF Fetcher)".to_string());
+        let source_config = SourceConfig {
+            base_url: config.transparency_url.clone(),
+            user_agent: Some("mrf-rs/0.1.0 (United Health MRF Fetcher)".to_string()),
+            ..Default::default()
+        };
         
+        Self::with_source_config(config, source_config)
+    }
+    
+    /// Create a new United Health source with custom HTTP settings
+    /// 
+    /// `source_config` controls the HTTP client: rate limit, retry policy,
+    /// default fetch options and cache limits.
+    pub fn with_source_config(config: UnitedHealthConfig, source_config: SourceConfig) -> SourceResult<Self> {
         let base = BaseSource::new(
             "United Health".to_string(),
             "united_health".to_string(),
             source_config,
         )?;
         
-        Ok(Self { base, config })
+        Ok(Self {
+            base,
+            config,
+            filter: DiscoveryFilter::default(),
+        })
+    }
+    
+    /// Limit discovery to the files matching `filter`
+    /// 
+    /// Index files whose name or date does not match are not downloaded at
+    /// all, which turns a full discovery into a handful of requests when
+    /// only a few employers are of interest.
+    /// 
+    /// ```no_run
+    /// # use mrf_rs::sources::united_health::UnitedHealthSource;
+    /// # use mrf_rs::sources::{discovery::DiscoveryFilter, MrfSource};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let filter = DiscoveryFilter {
+    ///     employer_patterns: vec![regex::Regex::new("(?i)acme")?],
+    ///     plan_ids: vec!["123456789".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let source = UnitedHealthSource::new()?.with_filter(filter);
+    /// let files = source.discover_files().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_filter(mut self, filter: DiscoveryFilter) -> Self {
+        self.filter = filter;
+        self
     }
     
     /// Fetch all index files from the API
@@ -97,9 +153,10 @@ impl UnitedHealthSource {
         let entries: Vec<IndexFileEntry> = stream::iter(api_response.blobs)
             .map(|blob| async move {
                 IndexFileEntry {
-                    name: blob.name.clone(),
-                    url: blob.download_url,
                     date: extract_date_from_filename(&blob.name),
+                    name: blob.name,
+                    url: blob.download_url,
+                    size: Some(blob.size),
                 }
             })
             .buffer_unordered(usize::MAX) // No concurrency limit
@@ -111,52 +168,38 @@ impl UnitedHealthSource {
     }
     
     /// Fetch and parse a single index file to get MRF file listings
-    async fn fetch_index_file(&self, url: &str) -> SourceResult<Vec<MrfFileInfo>> {
-        debug!("Fetching index file: {}", url);
-        
-        let response = self.base.http_client.get(url).await?;
+    async fn fetch_index_file(&self, url: &str) -> (Vec<MrfFileInfo>, IndexOutcome) {
+        debug!("Fetching index file: {}", strip_query(url));
         
-        // Check Content-Length header to skip empty files before downloading
-        if let Some(content_length) = response.headers()
-            .get("content-length")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.parse::<u64>().ok())
-        {
-            if content_length == 0 {
-                debug!("Skipping empty index file (Content-Length: 0): {}", url);
-                return Ok(Vec::new());
+        let content = match self.download_index_file(url).await {
+            Ok(content) => content,
+            Err(e) => {
+                debug!("Failed to fetch index file {}: {}", strip_query(url), e);
+                return (Vec::new(), IndexOutcome::HttpError { error: e.to_string() });
             }
-        }
-        
-        let content = response.bytes().await.map_err(SourceError::Http)?;
+        };
         
         // Skip empty files
-        if content.is_empty() {
-            debug!("Skipping empty index file: {}", url);
-            return Ok(Vec::new());
-        }
-        
-        // Check if content is too small to be valid JSON (less than 2 bytes for "{}")
-        if content.len() < 2 {
-            debug!("Skipping index file with insufficient content ({}B): {}", content.len(), url);
-            return Ok(Vec::new());
+        if content.iter().all(u8::is_ascii_whitespace) {
+            debug!("Skipping empty index file: {}", strip_query(url));
+            return (Vec::new(), IndexOutcome::Empty);
         }
         
         // Offload deserialization to a blocking thread
-        let index: IndexFile = match tokio::task::spawn_blocking({
+        let parsed = tokio::task::spawn_blocking({
             let content = content.clone();
-            move || serde_json::from_slice(&content)
-        }).await.map_err(|e| SourceError::Other(format!("Join error: {}", e)))? {
-            Ok(idx) => idx,
+            move || serde_json::from_slice::<IndexFile>(&content)
+        }).await;
+        let index = match parsed {
+            Ok(Ok(idx)) => idx,
+            Ok(Err(e)) => {
+                let outcome = IndexOutcome::parse_error(&e, &content);
+                debug!("Failed to parse index file {}: {:?}", strip_query(url), outcome);
+                return (Vec::new(), outcome);
+            }
             Err(e) => {
-                // Log the first few bytes to help debug
-                let preview = if content.len() > 100 {
-                    format!("{:?}...", &content[..100])
-                } else {
-                    format!("{:?}", &content[..])
-                };
-                debug!("Failed to parse index file {}: {}. Content preview: {}", url, e, preview);
-                return Ok(Vec::new());
+                warn!("Parsing index file {} did not complete: {}", strip_query(url), e);
+                return (Vec::new(), IndexOutcome::TaskFailed { error: e.to_string() });
             }
         };
         
@@ -164,6 +207,8 @@ impl UnitedHealthSource {
         let files: Vec<MrfFileInfo> = index.reporting_structure
             .into_iter()
             .flat_map(|structure| {
+                let plans = parse_reporting_plans(structure.reporting_plans.unwrap_or_default(), url);
+                
                 // Chain in-network and allowed amount files
                 let in_network_files = structure.in_network_files
                     .unwrap_or_default()
@@ -171,6 +216,7 @@ impl UnitedHealthSource {
                     .map(|file| create_mrf_file_info(
                         file,
                         MrfFileType::InNetwork,
+                        &plans,
                         &index.reporting_entity_name,
                         &index.reporting_entity_type,
                         url,
@@ -182,17 +228,57 @@ impl UnitedHealthSource {
                     .map(|file| create_mrf_file_info(
                         file,
                         MrfFileType::AllowedAmount,
+                        &plans,
                         &index.reporting_entity_name,
                         &index.reporting_entity_type,
                         url,
                     ));
                 
-                in_network_files.chain(allowed_amount_files)
+                in_network_files.chain(allowed_amount_files).collect::<Vec<_>>()
             })
             .collect();
         
         debug!("Found {} files in index", files.len());
-        Ok(files)
+        let outcome = if files.is_empty() {
+            IndexOutcome::Empty
+        } else {
+            IndexOutcome::Ok { files: files.len() }
+        };
+        (files, outcome)
+    }
+    
+    /// Download an index file, empty if the server announces no content
+    async fn download_index_file(&self, url: &str) -> SourceResult<Bytes> {
+        let response = self.base.http_client.get(url).await?;
+        
+        // Check Content-Length header to skip empty files before downloading
+        if let Some(content_length) = response.headers()
+            .get("content-length")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            if content_length == 0 {
+                return Ok(Bytes::new());
+            }
+        }
+        
+        response.bytes().await.map_err(SourceError::Http)
+    }
+    
+    /// The bandwidth throttle shared by all downloads of this source
+    /// 
+    /// The limit can be changed while bulk downloads are running:
+    /// 
+    /// ```no_run
+    /// # use mrf_rs::sources::united_health::UnitedHealthSource;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let source = UnitedHealthSource::new()?;
+    /// source.bandwidth_throttle().set_limit(Some(50 * 1024 * 1024));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bandwidth_throttle(&self) -> &BandwidthThrottle {
+        self.base.http_client.bandwidth_throttle()
     }
     
     /// Fetch multiple MRF files in parallel
@@ -202,8 +288,8 @@ impl UnitedHealthSource {
     /// 
     /// # Arguments
     /// 
-    /// * `files` - Vector of MRF file information to download
-    /// * `options` - Optional fetch options to apply to all downloads
+    /// * `files` - MRF files to download, either as discovered or deduplicated
+    /// * `options` - Optional fetch options to apply to all downloads, overriding the source defaults
     /// * `max_concurrent_downloads` - Maximum number of concurrent downloads (defaults to no limit)
     /// 
     /// # Example
@@ -230,14 +316,13 @@ impl UnitedHealthSource {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn fetch_all_files(
+    pub async fn fetch_all_files<F: AsRef<MrfFileInfo> + Send>(
         &self,
-        files: Vec<MrfFileInfo>,
+        files: Vec<F>,
         options: Option<FetchOptions>,
         max_concurrent_downloads: Option<usize>,
-    ) -> Vec<(MrfFileInfo, SourceResult<Vec<u8>>)> {
+    ) -> Vec<(F, SourceResult<Vec<u8>>)> {
         let max_concurrency = max_concurrent_downloads.unwrap_or(usize::MAX);
-        let options = options.unwrap_or_default();
         
         info!("Fetching {} MRF files with max concurrency of {}", 
               files.len(), if max_concurrency == usize::MAX { "unlimited".to_string() } else { max_concurrency.to_string() });
@@ -247,15 +332,14 @@ impl UnitedHealthSource {
         let options_arc = Arc::new(options);
         
         // Process all files in parallel with maximum concurrency
-        let results: Vec<(MrfFileInfo, SourceResult<Vec<u8>>)> = stream::iter(files)
-            .map(|file_info| {
+        let results: Vec<(F, SourceResult<Vec<u8>>)> = stream::iter(files)
+            .map(|file| {
                 let self_clone = Arc::clone(&self_arc);
                 let options_clone = Arc::clone(&options_arc);
-                let file_info_clone = file_info.clone();
                 
                 async move {
-                    let result = self_clone.fetch_file(&file_info_clone, Some((*options_clone).clone())).await;
-                    (file_info, result)
+                    let result = self_clone.fetch_file(file.as_ref(), (*options_clone).clone()).await;
+                    (file, result)
                 }
             })
             .buffer_unordered(max_concurrency)
@@ -277,23 +361,23 @@ impl UnitedHealthSource {
     /// 
     /// # Arguments
     /// 
-    /// * `files` - Vector of MRF file information to download
+    /// * `files` - MRF files to download, either as discovered or deduplicated
     /// * `output_dir` - Directory to save files to
-    /// * `options` - Optional fetch options to apply to all downloads
+    /// * `options` - Optional fetch options to apply to all downloads, overriding the source defaults
     /// * `max_concurrent_downloads` - Maximum number of concurrent downloads
     /// * `progress` - Optional progress callback that receives (completed_files, total_files)
     /// 
     /// # Returns
     /// 
     /// Vector of tuples containing the file info and the result (either the saved path or error)
-    pub async fn fetch_all_files_to_disk(
+    pub async fn fetch_all_files_to_disk<F: AsRef<MrfFileInfo> + Send>(
         &self,
-        files: Vec<MrfFileInfo>,
+        files: Vec<F>,
         output_dir: &Path,
         options: Option<FetchOptions>,
         max_concurrent_downloads: Option<usize>,
         progress: Option<Box<dyn Fn(usize, usize) + Send + Sync>>,
-    ) -> Vec<(MrfFileInfo, SourceResult<std::path::PathBuf>)> {
+    ) -> Vec<(F, SourceResult<std::path::PathBuf>)> {
         use std::sync::atomic::{AtomicUsize, Ordering};
         
         let max_concurrency = max_concurrent_downloads.unwrap_or(usize::MAX);
@@ -312,31 +396,31 @@ impl UnitedHealthSource {
         
         // Create shared references
         let self_arc = Arc::new(self);
-        let options_arc = Arc::new(options.unwrap_or_default());
+        let options_arc = Arc::new(options);
         let progress_arc = Arc::new(progress);
         let completed_count = Arc::new(AtomicUsize::new(0));
         
         // Process all files in parallel
-        let results: Vec<(MrfFileInfo, SourceResult<std::path::PathBuf>)> = stream::iter(files)
-            .map(|file_info| {
+        let results: Vec<(F, SourceResult<std::path::PathBuf>)> = stream::iter(files)
+            .map(|file| {
                 let self_clone = Arc::clone(&self_arc);
                 let options_clone = Arc::clone(&options_arc);
                 let progress_clone = Arc::clone(&progress_arc);
                 let completed_clone = Arc::clone(&completed_count);
-                let file_info_clone = file_info.clone();
                 let output_dir = output_dir.to_path_buf();
                 
                 async move {
+                    let file_info = file.as_ref();
                     // Generate filename from file ID and URL extension
-                    let extension = file_info_clone.url
-                        .split('/')
-                        .last()
-                        .and_then(|name| name.split('.').last())
+                    let extension = file_info.url
+                        .rsplit('/')
+                        .next()
+                        .and_then(|name| name.rsplit('.').next())
                         .unwrap_or("json");
                     
                     let filename = format!("{}_{}.{}", 
-                        file_info_clone.file_type.as_str(),
-                        file_info_clone.id,
+                        file_info.file_type.as_str(),
+                        file_info.id,
                         extension
                     );
                     let file_path = output_dir.join(filename);
@@ -344,13 +428,21 @@ impl UnitedHealthSource {
                     // Download file
                     let result = self_clone
                         .fetch_file_to_path(
-                            &file_info_clone,
+                            file_info,
                             &file_path,
-                            Some((*options_clone).clone()),
+                            (*options_clone).clone(),
                             None
                         )
-                        .await
-                        .map(|_| file_path);
+                        .await;
+                    
+                    // Never leave a half-written file behind for a failed download
+                    let result = match result {
+                        Ok(()) => Ok(file_path),
+                        Err(e) => {
+                            self_clone.base.http_client.discard_partial_download(&file_info.url, &file_path).await;
+                            Err(e)
+                        }
+                    };
                     
                     // Update progress
                     let completed = completed_clone.fetch_add(1, Ordering::SeqCst) + 1;
@@ -358,7 +450,7 @@ impl UnitedHealthSource {
                         callback(completed, total_files);
                     }
                     
-                    (file_info, result)
+                    (file, result)
                 }
             })
             .buffer_unordered(max_concurrency)
@@ -372,27 +464,34 @@ impl UnitedHealthSource {
         
         results
     }
-}
-
-#[async_trait]
-impl MrfSource for UnitedHealthSource {
-    fn name(&self) -> &str {
-        &self.base.name
-    }
-    
-    fn source_id(&self) -> &str {
-        &self.base.source_id
-    }
     
-    async fn discover_files(&self) -> SourceResult<Vec<MrfFileInfo>> {
+    /// Every file listed by every index file, before any HEAD requests
+    /// 
+    /// Fails in strict mode if too many index files could not be read.
+    async fn list_files(&self) -> SourceResult<(Vec<MrfFileInfo>, DiscoveryReport)> {
         // Fetch all index files
         let index_entries = self.fetch_all_index_files().await?;
         
         if index_entries.is_empty() {
             warn!("No index files found");
-            return Ok(Vec::new());
+            return Ok((Vec::new(), DiscoveryReport::default()));
         }
         
+        let listed_count = index_entries.len();
+        
+        // Every file is a blob, so the blob list knows the size of each one
+        let blob_sizes: HashMap<String, u64> = index_entries
+            .iter()
+            .filter_map(|entry| Some((strip_query(&entry.url).to_string(), entry.size?)))
+            .collect();
+        
+        let index_entries: Vec<IndexFileEntry> = index_entries
+            .into_iter()
+            .filter(|entry| self.filter.matches_index(&entry.name, entry.date))
+            .collect();
+        if index_entries.len() < listed_count {
+            info!("Filter kept {} of {} index files", index_entries.len(), listed_count);
+        }
         let index_count = index_entries.len();
         info!("Processing {} index files with unlimited concurrency", index_count);
         
@@ -403,23 +502,21 @@ impl MrfSource for UnitedHealthSource {
         let start_time = std::time::Instant::now();
         
         // Create all futures immediately to start them in parallel        
-        let all_files: Vec<MrfFileInfo> = stream::iter(index_entries.into_iter().enumerate())
+        let mut results: Vec<(usize, Vec<MrfFileInfo>, IndexFileReport)> = stream::iter(index_entries.into_iter().enumerate())
             .map(|(idx, entry)| {
                 let self_clone = Arc::clone(&self_arc);
-                let start_time_clone = start_time.clone();
+                let start_time_clone = start_time;
                 async move {
                     let task_start = std::time::Instant::now();
                     let time_since_start = start_time_clone.elapsed();
                     info!("Starting fetch for index {} at {:.2?}: {}", idx + 1, time_since_start, entry.name);
                     
-                    // Use the fetch_index_file method
-                    let files = match self_clone.fetch_index_file(&entry.url).await {
-                        Ok(files) => files,
-                        Err(e) => {
-                            debug!("Failed to fetch index file {}: {}", entry.name, e);
-                            Vec::new()
-                        }
-                    };
+                    let (mut files, outcome) = self_clone.fetch_index_file(&entry.url).await;
+                    files.retain(|file| self_clone.filter.matches_file(file));
+                    // Files are published with the index that lists them
+                    for file in &mut files {
+                        file.last_modified = file.last_modified.or(entry.date);
+                    }
                     
                     let task_duration = task_start.elapsed();
                     let total_elapsed = start_time_clone.elapsed();
@@ -428,23 +525,122 @@ impl MrfSource for UnitedHealthSource {
                              idx + 1, total_elapsed, task_duration, entry.name, files.len());
                     }
                     
-                    files
+                    let report = IndexFileReport {
+                        url: strip_query(&entry.url).to_string(),
+                        name: entry.name,
+                        outcome,
+                        duration: task_duration,
+                    };
+                    (idx, files, report)
                 }
             })
             .buffer_unordered(usize::MAX)
-            .collect::<Vec<Vec<MrfFileInfo>>>()
-            .await
-            .into_iter()
-            .flatten()
-            .collect();
+            .collect()
+            .await;
+        results.sort_by_key(|(idx, _, _)| *idx);
+        
+        let mut report = DiscoveryReport::default();
+        let mut all_files = Vec::new();
+        for (_, files, index_report) in results {
+            all_files.extend(files.into_iter().map(|mut file| {
+                file.size_bytes = file.size_bytes.or_else(|| blob_sizes.get(strip_query(&file.url)).copied());
+                file
+            }));
+            report.index_files.push(index_report);
+        }
         
         let total_duration = start_time.elapsed();
+        report.duration = total_duration;
         info!("Processed all index files in {:.2?} ({:.2} files/sec)", 
               total_duration, 
               index_count as f64 / total_duration.as_secs_f64());
         
         info!("Total MRF files discovered: {}", all_files.len());
-        Ok(all_files)
+        if report.error_count() > 0 {
+            warn!(
+                "{} of {} index files could not be read, {} were empty",
+                report.error_count(), index_count, report.empty_count()
+            );
+        }
+        
+        if let Some(max_error_rate) = self.config.max_index_error_rate {
+            report = report.check(max_error_rate)?;
+        }
+        Ok((all_files, report))
+    }
+    
+    /// Discover files like [`MrfSource::discover_files`], together with the
+    /// outcome of every index file
+    /// 
+    /// ```no_run
+    /// # use mrf_rs::sources::united_health::UnitedHealthSource;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let source = UnitedHealthSource::new()?;
+    /// let (files, report) = source.discover_files_with_report().await?;
+    /// for failed in report.errors() {
+    ///     eprintln!("{}: {:?}", failed.url, failed.outcome);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn discover_files_with_report(&self) -> SourceResult<(Vec<MrfFileInfo>, DiscoveryReport)> {
+        let (files, report) = self.list_files().await?;
+        Ok((self.probe_if_enabled(files).await, report))
+    }
+    
+    /// Discover files like [`MrfSource::discover_files`], listing each file
+    /// once
+    /// 
+    /// Many employer index files point at the same shared in-network files.
+    /// Each of them is returned once here, with a reference to every index
+    /// file, reporting entity and plan that lists it. HEAD requests, when
+    /// enabled, are only made for the unique files.
+    /// 
+    /// ```no_run
+    /// # use mrf_rs::sources::united_health::UnitedHealthSource;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let source = UnitedHealthSource::new()?;
+    /// let files = source.discover_unique_files().await?;
+    /// let results = source.fetch_all_files_to_disk(files, "mrf".as_ref(), None, Some(8), None).await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn discover_unique_files(&self) -> SourceResult<Vec<UniqueMrfFile>> {
+        Ok(self.discover_unique_files_with_report().await?.0)
+    }
+    
+    /// Discover files like [`Self::discover_unique_files`], together with the
+    /// outcome of every index file
+    pub async fn discover_unique_files_with_report(&self) -> SourceResult<(Vec<UniqueMrfFile>, DiscoveryReport)> {
+        let (files, report) = self.list_files().await?;
+        let files = discovery::dedupe_files(files);
+        info!("Unique MRF files: {}", files.len());
+        Ok((self.probe_if_enabled(files).await, report))
+    }
+    
+    /// Fill in file metadata with HEAD requests if `head_concurrency` is set
+    async fn probe_if_enabled<F: AsMut<MrfFileInfo> + Send>(&self, files: Vec<F>) -> Vec<F> {
+        let Some(max_concurrent) = self.config.head_concurrency else {
+            return files;
+        };
+        info!("Probing {} files with HEAD requests", files.len());
+        let options = self.base.http_client.default_options();
+        self.base.probe_files(files, &options, max_concurrent).await
+    }
+}
+
+#[async_trait]
+impl MrfSource for UnitedHealthSource {
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+    
+    fn source_id(&self) -> &str {
+        &self.base.source_id
+    }
+    
+    async fn discover_files(&self) -> SourceResult<Vec<MrfFileInfo>> {
+        Ok(self.discover_files_with_report().await?.0)
     }
     
     async fn fetch_file(
@@ -452,40 +648,23 @@ impl MrfSource for UnitedHealthSource {
         file_info: &MrfFileInfo,
         options: Option<FetchOptions>,
     ) -> SourceResult<Vec<u8>> {
-        let options = options.unwrap_or_default();
-        
-        info!("Downloading file: {}", file_info.name);
-        let response = self.base.http_client.get(&file_info.url).await?;
-        
-        // Check file size if max_size is specified
-        if let Some(max_size) = options.max_size {
-            if let Some(content_length) = response.headers()
-                .get("content-length")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|v| v.parse::<u64>().ok())
-            {
-                if content_length > max_size {
-                    return Err(SourceError::Other(format!(
-                        "File size {} exceeds maximum allowed size {}",
-                        content_length, max_size
-                    )));
-                }
-            }
-        }
-        
-        let data = response.bytes().await.map_err(SourceError::Http)?;
-        Ok(data.to_vec())
+        let options = self.base.http_client.resolve_options(options.as_ref());
+        self.base.fetch_file(file_info, &options).await
     }
     
     async fn fetch_file_to_path(
         &self,
         file_info: &MrfFileInfo,
         path: &Path,
-        _options: Option<FetchOptions>,
+        options: Option<FetchOptions>,
         progress: Option<ProgressCallback>,
     ) -> SourceResult<()> {
-        // Direct download to path
-        self.base.http_client.download_file(&file_info.url, path, progress).await
+        let options = self.base.http_client.resolve_options(options.as_ref());
+        self.base.fetch_file_to_path(file_info, path, &options, progress).await
+    }
+    
+    fn http_client(&self) -> Option<&HttpClient> {
+        Some(&self.base.http_client)
     }
     
     async fn get_metadata(&self) -> SourceResult<serde_json::Value> {
@@ -519,12 +698,13 @@ impl MrfSource for UnitedHealthSource {
 fn create_mrf_file_info(
     file: FileEntry,
     file_type: MrfFileType,
+    plans: &[ReportingPlan],
     entity_name: &str,
     entity_type: &str,
     index_url: &str,
 ) -> MrfFileInfo {
     // Determine compression type from the file URL
-    let compression = determine_compression_from_url(&file.location);
+    let compression = utils::compression_hint(&file.location, None);
     
     MrfFileInfo {
         id: generate_file_id(&file.location),
@@ -538,6 +718,7 @@ fn create_mrf_file_info(
         size_bytes: None,
         last_modified: None,
         compression,
+        reporting_plans: plans.to_vec(),
         metadata: serde_json::json!({
             "source": "united_health",
             "index_url": index_url,
@@ -547,34 +728,26 @@ fn create_mrf_file_info(
     }
 }
 
-/// Determine compression type from URL
-fn determine_compression_from_url(url: &str) -> Option<CompressionType> {
-    let lower_url = url.to_lowercase();
-    
-    // Remove query parameters and fragments from URL for extension checking
-    let path = lower_url.split('?').next().unwrap_or(&lower_url);
-    let path = path.split('#').next().unwrap_or(path);
-    
-    if path.ends_with(".gz") || path.ends_with(".gzip") {
-        Some(CompressionType::Gzip)
-    } else if path.ends_with(".zip") {
-        Some(CompressionType::Zip)
-    } else if path.ends_with(".bz2") || path.ends_with(".bzip2") {
-        Some(CompressionType::Bzip2)
-    } else if path.ends_with(".json") {
-        // Uncompressed JSON
-        Some(CompressionType::None)
-    } else {
-        // For URLs without clear extensions, check for compression indicators in the path
-        if path.contains("gzip") || path.contains(".gz") {
-            Some(CompressionType::Gzip)
-        } else if path.contains("zip") {
-            Some(CompressionType::Zip)
-        } else {
-            // Default to None if we can't determine
-            None
-        }
-    }
+/// Typed reporting plans of a reporting structure
+/// 
+/// A plan that does not match the schema is skipped rather than failing the
+/// whole index file, which would lose its files as well.
+fn parse_reporting_plans(plans: Vec<serde_json::Value>, index_url: &str) -> Vec<ReportingPlan> {
+    plans
+        .into_iter()
+        .filter_map(|plan| match serde_json::from_value(plan) {
+            Ok(plan) => Some(plan),
+            Err(e) => {
+                debug!("Skipping invalid reporting plan in {}: {}", index_url, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// URL without its query string, which holds access tokens for blob URLs
+fn strip_query(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
 }
 
 /// Entry for an index file found on the transparency page
@@ -583,6 +756,9 @@ pub struct IndexFileEntry {
     pub name: String,
     pub url: String,
     pub date: Option<DateTime<Utc>>,
+    /// Size in bytes reported by the blob API
+    #[serde(default)]
+    pub size: Option<u64>,
 }
 
 /// Structure of a United Health index file
@@ -602,7 +778,7 @@ struct ReportingStructure {
     #[serde(rename = "allowed_amount_files")]
     allowed_amount_files: Option<Vec<FileEntry>>,
     
-    reporting_plans: Option<Vec<ReportingPlan>>,
+    reporting_plans: Option<Vec<serde_json::Value>>,
 }
 
 /// Entry in an index file
@@ -612,15 +788,6 @@ struct FileEntry {
     location: String,
 }
 
-/// Reporting plan information
-#[derive(Debug, Deserialize)]
-struct ReportingPlan {
-    plan_id: String,
-    plan_id_type: String,
-    plan_market_type: String,
-    plan_name: String,
-}
-
 /// Response from the blobs API endpoint
 #[derive(Debug, Deserialize)]
 struct BlobsApiResponse {
@@ -667,85 +834,6 @@ fn generate_file_id(url: &str) -> String {
 mod tests {
     use super::*;
     
-    #[test]
-    fn test_compression_detection() {
-        // Test gzip detection
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.json.gz"),
-            Some(CompressionType::Gzip)
-        );
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.gzip"),
-            Some(CompressionType::Gzip)
-        );
-        
-        // Test zip detection
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.zip"),
-            Some(CompressionType::Zip)
-        );
-        
-        // Test bzip2 detection
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.bz2"),
-            Some(CompressionType::Bzip2)
-        );
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.bzip2"),
-            Some(CompressionType::Bzip2)
-        );
-        
-        // Test uncompressed JSON
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.json"),
-            Some(CompressionType::None)
-        );
-        
-        // Test URL with compression in path
-        assert_eq!(
-            determine_compression_from_url("https://example.com/gzip/data"),
-            Some(CompressionType::Gzip)
-        );
-        
-        // Test unknown format
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.dat"),
-            None
-        );
-        
-        // Test case insensitivity
-        assert_eq!(
-            determine_compression_from_url("https://example.com/FILE.JSON.GZ"),
-            Some(CompressionType::Gzip)
-        );
-        
-        // Test URLs with query parameters
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.json.gz?undefined"),
-            Some(CompressionType::Gzip)
-        );
-        assert_eq!(
-            determine_compression_from_url("https://mrfstorageprod.blob.core.windows.net/public-mrf/2025-06-01/2025-06-01_allowed-amounts.json.gz?undefined"),
-            Some(CompressionType::Gzip)
-        );
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.json?param=value&other=123"),
-            Some(CompressionType::None)
-        );
-        
-        // Test URLs with fragments
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.zip#section"),
-            Some(CompressionType::Zip)
-        );
-        
-        // Test URLs with both query params and fragments
-        assert_eq!(
-            determine_compression_from_url("https://example.com/file.bz2?param=1#section"),
-            Some(CompressionType::Bzip2)
-        );
-    }
-    
     #[test]
     fn test_date_extraction() {
         assert_eq!(
@@ -771,6 +859,262 @@ mod tests {
             None
         );
     }
+    
+    /// Blob listing entry for the index file `<stem>_index.json`, served at
+    /// the same path with `query` appended to its download URL
+    fn index_blob(server: &mockito::ServerGuard, stem: &str, query: &str) -> serde_json::Value {
+        serde_json::json!({
+            "name": format!("{}_index.json", stem),
+            "downloadUrl": format!("{}/{}_index.json{}", server.url(), stem, query),
+            "size": 512,
+        })
+    }
+    
+    /// Serve `blobs` from the blob listing endpoint
+    async fn mock_blob_listing(server: &mut mockito::ServerGuard, blobs: Vec<serde_json::Value>) {
+        server.mock("GET", "/api/blobs")
+            .with_body(serde_json::json!({ "blobs": blobs }).to_string())
+            .create_async()
+            .await;
+    }
+    
+    /// Source discovering from `server` without rate limiting or retries,
+    /// with `configure` applied to its configuration
+    fn test_source(server: &mockito::ServerGuard, configure: impl FnOnce(&mut UnitedHealthConfig)) -> UnitedHealthSource {
+        let mut config = UnitedHealthConfig {
+            transparency_url: server.url(),
+            api_endpoint: format!("{}/api/blobs", server.url()),
+            head_concurrency: None,
+            max_index_error_rate: None,
+        };
+        configure(&mut config);
+        
+        let source_config = SourceConfig {
+            rate_limit: None,
+            default_options: Some(FetchOptions {
+                max_retries: Some(0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        UnitedHealthSource::with_source_config(config, source_config).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_discovery_fills_size_and_dates() {
+        let mut server = mockito::Server::new_async().await;
+        let rates_url = format!("{}/2025-06-01_Acme_in-network-rates.json.gz", server.url());
+        
+        let blobs = vec![
+            index_blob(&server, "2025-06-01_Acme", "?sig=a"),
+            serde_json::json!({
+                "name": "2025-06-01_Acme_in-network-rates.json.gz",
+                "downloadUrl": format!("{}?sig=b", rates_url),
+                "size": 4096,
+            }),
+        ];
+        mock_blob_listing(&mut server, blobs).await;
+        server.mock("GET", "/2025-06-01_Acme_index.json")
+            .match_query(mockito::Matcher::Any)
+            .with_body(serde_json::json!({
+                "reporting_entity_name": "Acme",
+                "reporting_entity_type": "Health Insurance Issuer",
+                "reporting_structure": [{
+                    "reporting_plans": [
+                        {"plan_name": "Acme PPO", "plan_id_type": "EIN", "plan_id": "123456789", "plan_market_type": "group"},
+                        {"plan_name": "Broken", "plan_id_type": "SSN", "plan_id": "1", "plan_market_type": "group"}
+                    ],
+                    "in_network_files": [{"description": "rates", "location": rates_url}]
+                }]
+            }).to_string())
+            .create_async()
+            .await;
+        server.mock("HEAD", "/2025-06-01_Acme_in-network-rates.json.gz")
+            .with_header("etag", "\"0x8DD\"")
+            .with_header("content-type", "application/gzip")
+            .with_header("last-modified", "Mon, 02 Jun 2025 10:00:00 GMT")
+            .with_header("content-length", "4100")
+            .create_async()
+            .await;
+        
+        let files = test_source(&server, |_| {}).discover_files().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size_bytes, Some(4096));
+        assert_eq!(files[0].last_modified, extract_date_from_filename("2025-06-01"));
+        assert_eq!(files[0].reporting_plans.len(), 1);
+        assert_eq!(files[0].reporting_plans[0].plan_id, "123456789");
+        assert_eq!(files[0].reporting_plans[0].plan_id_type, crate::types::PlanIdType::Ein);
+        
+        let source = test_source(&server, |config| config.head_concurrency = Some(4));
+        let files = source.discover_files().await.unwrap();
+        assert_eq!(files[0].size_bytes, Some(4100));
+        assert_eq!(
+            files[0].last_modified,
+            Some(DateTime::parse_from_rfc3339("2025-06-02T10:00:00Z").unwrap().with_timezone(&Utc))
+        );
+        assert_eq!(files[0].metadata["etag"], "\"0x8DD\"");
+        assert_eq!(files[0].metadata["content_type"], "application/gzip");
+    }
+    
+    #[tokio::test]
+    async fn test_unique_discovery_and_download() {
+        let mut server = mockito::Server::new_async().await;
+        let shared_url = format!("{}/2025-06-01_shared_in-network-rates.json", server.url());
+        let own_url = format!("{}/2025-06-01_Beta_in-network-rates.json", server.url());
+        
+        let blobs = ["Acme", "Beta"]
+            .iter()
+            .map(|name| index_blob(&server, &format!("2025-06-01_{}", name), ""))
+            .collect();
+        mock_blob_listing(&mut server, blobs).await;
+        for (name, locations) in [("Acme", vec![&shared_url]), ("Beta", vec![&shared_url, &own_url])] {
+            let files: Vec<_> = locations.iter().map(|url| serde_json::json!({"location": url})).collect();
+            server.mock("GET", format!("/2025-06-01_{}_index.json", name).as_str())
+                .with_body(serde_json::json!({
+                    "reporting_entity_name": name,
+                    "reporting_entity_type": "Employer",
+                    "reporting_structure": [{
+                        "reporting_plans": [
+                            {"plan_name": format!("{} PPO", name), "plan_id_type": "EIN", "plan_id": name, "plan_market_type": "group"}
+                        ],
+                        "in_network_files": files
+                    }]
+                }).to_string())
+                .create_async()
+                .await;
+        }
+        let shared = server.mock("GET", "/2025-06-01_shared_in-network-rates.json")
+            .with_body("{}")
+            .expect(1)
+            .create_async()
+            .await;
+        server.mock("GET", "/2025-06-01_Beta_in-network-rates.json")
+            .with_body("{}")
+            .create_async()
+            .await;
+        
+        let source = test_source(&server, |_| {});
+        assert_eq!(source.discover_files().await.unwrap().len(), 3);
+        
+        let files = source.discover_unique_files().await.unwrap();
+        assert_eq!(files.len(), 2);
+        let shared_file = files.iter().find(|f| f.file.url == shared_url).unwrap();
+        assert_eq!(shared_file.references.len(), 2);
+        assert_eq!(shared_file.file.reporting_plans.len(), 2);
+        
+        let dir = tempfile::tempdir().unwrap();
+        let results = source.fetch_all_files_to_disk(files, dir.path(), None, Some(2), None).await;
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        shared.assert_async().await;
+    }
+    
+    #[tokio::test]
+    async fn test_discovery_report_and_strict_mode() {
+        let mut server = mockito::Server::new_async().await;
+        let blobs = ["ok", "empty", "down", "html"]
+            .iter()
+            .map(|name| index_blob(&server, &format!("2025-06-01_{}", name), "?sig=secret"))
+            .collect();
+        mock_blob_listing(&mut server, blobs).await;
+        server.mock("GET", "/2025-06-01_ok_index.json")
+            .match_query(mockito::Matcher::Any)
+            .with_body(serde_json::json!({
+                "reporting_entity_name": "Acme",
+                "reporting_entity_type": "Employer",
+                "reporting_structure": [{
+                    "in_network_files": [{"location": format!("{}/rates.json", server.url())}]
+                }]
+            }).to_string())
+            .create_async()
+            .await;
+        server.mock("GET", "/2025-06-01_empty_index.json")
+            .match_query(mockito::Matcher::Any)
+            .with_body("")
+            .create_async()
+            .await;
+        server.mock("GET", "/2025-06-01_down_index.json")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .create_async()
+            .await;
+        server.mock("GET", "/2025-06-01_html_index.json")
+            .match_query(mockito::Matcher::Any)
+            .with_body("<html>Maintenance</html>")
+            .create_async()
+            .await;
+        
+        let (files, report) = test_source(&server, |_| {}).discover_files_with_report().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(report.index_files.len(), 4);
+        assert_eq!(report.index_files[0].outcome, IndexOutcome::Ok { files: 1 });
+        assert_eq!(report.index_files[1].outcome, IndexOutcome::Empty);
+        assert!(matches!(report.index_files[2].outcome, IndexOutcome::HttpError { .. }));
+        match &report.index_files[3].outcome {
+            IndexOutcome::ParseError { preview, .. } => assert_eq!(preview, "<html>Maintenance</html>"),
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+        assert!(report.index_files.iter().all(|r| !r.url.contains("secret")));
+        
+        let source = test_source(&server, |config| config.max_index_error_rate = Some(0.25));
+        match source.discover_files().await {
+            Err(SourceError::DiscoveryFailed { failed, total, .. }) => {
+                assert_eq!((failed, total), (2, 4));
+            }
+            other => panic!("Expected DiscoveryFailed, got {:?}", other.map(|files| files.len())),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_filter_skips_index_files_and_plans() {
+        let mut server = mockito::Server::new_async().await;
+        let blobs = ["2025-05-01_Acme", "2025-06-01_Acme", "2025-06-01_Beta"]
+            .iter()
+            .map(|stem| index_blob(&server, stem, ""))
+            .collect();
+        mock_blob_listing(&mut server, blobs).await;
+        let mut skipped = Vec::new();
+        for path in ["/2025-05-01_Acme_index.json", "/2025-06-01_Beta_index.json"] {
+            skipped.push(server.mock("GET", path).expect(0).create_async().await);
+        }
+        let plan = |id: &str, market: &str| serde_json::json!({
+            "plan_name": id, "plan_id_type": "EIN", "plan_id": id, "plan_market_type": market,
+        });
+        server.mock("GET", "/2025-06-01_Acme_index.json")
+            .with_body(serde_json::json!({
+                "reporting_entity_name": "Acme",
+                "reporting_entity_type": "Employer",
+                "reporting_structure": [
+                    {
+                        "reporting_plans": [plan("111", "group")],
+                        "in_network_files": [{"location": format!("{}/group.json", server.url())}],
+                        "allowed_amount_files": [{"location": format!("{}/group-oon.json", server.url())}]
+                    },
+                    {
+                        "reporting_plans": [plan("222", "individual")],
+                        "in_network_files": [{"location": format!("{}/individual.json", server.url())}]
+                    }
+                ]
+            }).to_string())
+            .create_async()
+            .await;
+        
+        let filter = DiscoveryFilter {
+            employer_patterns: vec![Regex::new("(?i)_acme_").unwrap()],
+            since: extract_date_from_filename("2025-06-01"),
+            market_types: vec![crate::types::MarketType::Group],
+            file_types: vec![MrfFileType::InNetwork],
+            ..Default::default()
+        };
+        let source = test_source(&server, |_| {}).with_filter(filter);
+        
+        let (files, report) = source.discover_files_with_report().await.unwrap();
+        assert_eq!(report.index_files.len(), 1);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].url, format!("{}/group.json", server.url()));
+        for mock in skipped {
+            mock.assert_async().await;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 //! Out-of-Network Allowed Amount file types
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use super::common::{
     EntityType, PlanIdType, MarketType, BillingCodeType, 
     BillingClass, TaxIdentifier
@@ -37,6 +38,14 @@ pub struct AllowedAmountFile {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub plan_market_type: Option<MarketType>,
     
+    /// The legal name of the issuer offering the plan (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer_name: Option<String>,
+    
+    /// The legal name of the plan sponsor (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_sponsor_name: Option<String>,
+    
     /// Array of out-of-network allowed amounts
     pub out_of_network: Vec<OutOfNetworkRate>,
     
@@ -50,6 +59,10 @@ pub struct AllowedAmountFile {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "sourceSystem_plan")]
     pub source_system_plan: Option<String>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Out-of-network rate information.
@@ -63,18 +76,22 @@ pub struct OutOfNetworkRate {
     /// Common billing code type
     pub billing_code_type: BillingCodeType,
     
-    /// Version of the billing code type
-    pub billing_code_type_version: String,
-    
     /// The billing code for the item/service
     pub billing_code: String,
     
+    /// Version of the billing code type
+    pub billing_code_type_version: String,
+    
     /// Brief description of the item or service.
     /// For NDCs, must include proprietary and nonproprietary names.
     pub description: String,
     
     /// Array of allowed amounts
     pub allowed_amounts: Vec<AllowedAmount>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Allowed amount for out-of-network services.
@@ -95,6 +112,10 @@ pub struct AllowedAmount {
     
     /// Array of payment information
     pub payments: Vec<Payment>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Payment information.
@@ -113,6 +134,10 @@ pub struct Payment {
     
     /// Array of providers who billed for this service
     pub providers: Vec<Provider>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Provider information.
@@ -125,4 +150,8 @@ pub struct Provider {
     
     /// Array of provider NPIs
     pub npi: Vec<i64>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 } 
\ No newline at end of file
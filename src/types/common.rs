@@ -1,6 +1,7 @@
 //! Common types shared across all MRF file formats
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 /// Type of entity publishing the Machine-Readable File (MRF).
 /// 
@@ -100,6 +101,17 @@ pub enum BillingCodeType {
     /// Current Dental Terminology - ADA
     CDT,
     
+    /// Case Mix Groups - CMS Inpatient Rehabilitation Facility PPS
+    CMG,
+    
+    /// Medicare Severity Long-Term Care Diagnosis Related Groups - CMS
+    #[serde(rename = "MS-LTC-DRG")]
+    MSLTCDRG,
+    
+    /// TRICARE Diagnosis Related Groups
+    #[serde(rename = "TRIS-DRG")]
+    TRISDRG,
+    
     /// Custom Code Type: All - Represents all possible coding types under the contractual arrangement
     #[serde(rename = "CSTM-ALL")]
     CSTMALL,
@@ -164,10 +176,12 @@ pub enum BillingClass {
 pub enum PlanIdType {
     /// Employer Identification Number
     #[serde(rename = "EIN")]
+    #[serde(alias = "ein")]
     Ein,
     
     /// Health Insurance Oversight System identifier
     #[serde(rename = "HIOS")]
+    #[serde(alias = "hios")]
     Hios,
 }
 
@@ -226,6 +240,10 @@ pub struct ProviderGroup {
     
     /// Tax identification information for the provider group
     pub tin: TaxIdentifier,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Tax identifier.
@@ -239,4 +257,8 @@ pub struct TaxIdentifier {
     
     /// The identifier value (EIN or NPI number)
     pub value: String,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 } 
\ No newline at end of file
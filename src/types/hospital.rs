@@ -0,0 +1,361 @@
+//! Hospital price transparency standard charge file types
+//! 
+//! These types follow the CMS hospital price transparency template (45 CFR 180.50).
+//! The JSON template maps onto these structs directly; the CSV "tall" and "wide"
+//! templates are converted into the same shape by the parser.
+
+use serde::{Deserialize, Serialize};
+use super::common::{BillingCodeType, NegotiatedType};
+
+/// Hospital standard charge file structure.
+/// 
+/// Contains the standard charges a hospital publishes for its items and services,
+/// including payer-specific negotiated charges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HospitalStandardChargeFile {
+    /// The legal business name of the licensee
+    pub hospital_name: String,
+    
+    /// The date on which the file was last updated (ISO 8601 format: YYYY-MM-DD)
+    pub last_updated_on: String,
+    
+    /// The version of the CMS template used to produce the file
+    pub version: String,
+    
+    /// The unique names of the hospital locations covered by the file
+    #[serde(default)]
+    pub hospital_location: Vec<String>,
+    
+    /// The physical addresses of the hospital locations covered by the file
+    #[serde(default)]
+    pub hospital_address: Vec<String>,
+    
+    /// Hospital licensure information
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_information: Option<LicenseInformation>,
+    
+    /// Affirmation that the file is complete and accurate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affirmation: Option<Affirmation>,
+    
+    /// Modifiers that change the standard charges of items and services
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modifier_information: Option<Vec<ModifierInformation>>,
+    
+    /// Array of items and services with their standard charges
+    pub standard_charge_information: Vec<StandardChargeInformation>,
+}
+
+/// Hospital license information.
+/// 
+/// Identifies the license under which the hospital operates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseInformation {
+    /// The hospital license number, if the state issues one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_number: Option<String>,
+    
+    /// The two-letter state code in which the hospital is licensed
+    pub state: String,
+}
+
+/// Hospital affirmation statement.
+/// 
+/// The statement required by CMS confirming the accuracy of the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Affirmation {
+    /// The text of the affirmation statement
+    pub affirmation: String,
+    
+    /// Whether the hospital confirms the affirmation
+    pub confirm_affirmation: bool,
+}
+
+/// Modifier information.
+/// 
+/// Describes how a modifier changes the standard charges for a set of payers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierInformation {
+    /// Description of the modifier
+    pub description: String,
+    
+    /// The modifier code
+    pub code: String,
+    
+    /// Payer-specific descriptions of how the modifier applies
+    #[serde(default)]
+    pub modifier_payer_information: Vec<ModifierPayerInformation>,
+}
+
+/// Payer-specific modifier information.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifierPayerInformation {
+    /// Name of the third-party payer
+    pub payer_name: String,
+    
+    /// Name of the payer's plan
+    pub plan_name: String,
+    
+    /// How the modifier changes the standard charge for this payer and plan
+    pub description: String,
+}
+
+/// Standard charge information for an item or service.
+/// 
+/// Groups the billing codes describing an item or service with its standard charges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandardChargeInformation {
+    /// Description of the item or service
+    pub description: String,
+    
+    /// Drug unit and type of measurement, required when the item is a drug
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drug_information: Option<DrugInformation>,
+    
+    /// Billing codes that identify the item or service
+    pub code_information: Vec<CodeInformation>,
+    
+    /// Standard charges for the item or service
+    pub standard_charges: Vec<StandardCharge>,
+}
+
+/// Drug measurement information.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrugInformation {
+    /// The number of units of the drug
+    pub unit: String,
+    
+    /// The type of measurement (e.g., "GR", "ME", "ML", "UN")
+    #[serde(rename = "type")]
+    pub unit_type: String,
+}
+
+/// Billing code for an item or service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeInformation {
+    /// The billing code
+    pub code: String,
+    
+    /// Common billing code type
+    #[serde(rename = "type")]
+    pub code_type: BillingCodeType,
+}
+
+/// Setting in which an item or service is provided.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HospitalSetting {
+    /// Inpatient setting
+    Inpatient,
+    
+    /// Outpatient setting
+    Outpatient,
+    
+    /// Both inpatient and outpatient settings
+    Both,
+}
+
+/// Standard charges for an item or service in a given setting.
+/// 
+/// Contains the gross, discounted cash, minimum and maximum negotiated charges,
+/// along with the payer-specific negotiated charges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandardCharge {
+    /// Setting in which the item or service is provided
+    pub setting: HospitalSetting,
+    
+    /// The charge reflected in the hospital's chargemaster, absent any discounts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gross_charge: Option<f64>,
+    
+    /// The charge that applies to an individual who pays cash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discounted_cash: Option<f64>,
+    
+    /// The lowest payer-specific negotiated charge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    
+    /// The highest payer-specific negotiated charge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+    
+    /// Modifier codes the standard charge applies to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modifiers: Option<Vec<String>>,
+    
+    /// Payer-specific negotiated charges
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payers_information: Option<Vec<PayerInformation>>,
+    
+    /// Free-text notes that apply to the standard charges
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_generic_notes: Option<String>,
+}
+
+/// Contracting method used to establish a payer-specific negotiated charge.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StandardChargeMethodology {
+    /// A flat rate for a package of items and services
+    #[serde(rename = "case rate")]
+    CaseRate,
+    
+    /// A fixed rate from a fee schedule
+    #[serde(rename = "fee schedule")]
+    FeeSchedule,
+    
+    /// A percentage of the total billed charges
+    #[serde(rename = "percent of total billed charges")]
+    PercentOfTotalBilledCharges,
+    
+    /// A daily rate
+    #[serde(rename = "per diem")]
+    PerDiem,
+    
+    /// Any other contracting method
+    #[serde(rename = "other")]
+    #[serde(other)]
+    Other,
+}
+
+/// Payer-specific negotiated charge.
+/// 
+/// The charge a hospital has negotiated with a third-party payer for a plan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayerInformation {
+    /// Name of the third-party payer
+    pub payer_name: String,
+    
+    /// Name of the payer's plan
+    pub plan_name: String,
+    
+    /// The negotiated charge as a dollar amount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub standard_charge_dollar: Option<f64>,
+    
+    /// The negotiated charge as a percentage (e.g., 50 for 50%)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub standard_charge_percentage: Option<f64>,
+    
+    /// The algorithm used to determine the negotiated charge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub standard_charge_algorithm: Option<String>,
+    
+    /// The estimated dollar amount when the charge is a percentage or algorithm
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_amount: Option<f64>,
+    
+    /// Contracting method used to establish the negotiated charge
+    pub methodology: StandardChargeMethodology,
+    
+    /// Free-text notes specific to this payer and plan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_payer_notes: Option<String>,
+}
+
+/// Payer-specific negotiated charge flattened to a single billing code.
+/// 
+/// Mirrors the billing code and negotiated price fields of the Transparency in
+/// Coverage in-network rates so hospital and payer data can be compared directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HospitalNegotiatedCharge {
+    /// Legal business name of the hospital
+    pub hospital_name: String,
+    
+    /// Common billing code type for the item/service
+    pub billing_code_type: BillingCodeType,
+    
+    /// The code used to identify the item/service
+    pub billing_code: String,
+    
+    /// Description of the item/service
+    pub description: String,
+    
+    /// Setting in which the item or service is provided
+    pub setting: HospitalSetting,
+    
+    /// Name of the third-party payer
+    pub payer_name: String,
+    
+    /// Name of the payer's plan
+    pub plan_name: String,
+    
+    /// Type of negotiated rate
+    pub negotiated_type: NegotiatedType,
+    
+    /// The dollar amount or percentage based on `negotiated_type`
+    pub negotiated_rate: f64,
+    
+    /// The estimated dollar amount for percentage or algorithm-based charges
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_amount: Option<f64>,
+    
+    /// Modifier codes the charge applies to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billing_code_modifier: Option<Vec<String>>,
+    
+    /// Contracting method reported by the hospital
+    pub methodology: StandardChargeMethodology,
+}
+
+impl PayerInformation {
+    /// Express this payer's charge as a Transparency in Coverage negotiated type and rate.
+    /// 
+    /// Dollar amounts take precedence over percentages. Returns `None` when the charge
+    /// is only described by an algorithm.
+    pub fn negotiated_rate(&self) -> Option<(NegotiatedType, f64)> {
+        if let Some(dollar) = self.standard_charge_dollar {
+            let negotiated_type = match self.methodology {
+                StandardChargeMethodology::FeeSchedule => NegotiatedType::FeeSchedule,
+                StandardChargeMethodology::PerDiem => NegotiatedType::PerDiem,
+                _ => NegotiatedType::Negotiated,
+            };
+            Some((negotiated_type, dollar))
+        } else {
+            self.standard_charge_percentage
+                .map(|percentage| (NegotiatedType::Percentage, percentage))
+        }
+    }
+}
+
+impl HospitalStandardChargeFile {
+    /// Flatten payer-specific negotiated charges into one entry per billing code and payer plan.
+    /// 
+    /// Charges described only by an algorithm have no rate and are skipped.
+    pub fn negotiated_charges(&self) -> Vec<HospitalNegotiatedCharge> {
+        let mut charges = Vec::new();
+        
+        for item in &self.standard_charge_information {
+            for standard_charge in &item.standard_charges {
+                let Some(payers) = &standard_charge.payers_information else {
+                    continue;
+                };
+                
+                for payer in payers {
+                    let Some((negotiated_type, negotiated_rate)) = payer.negotiated_rate() else {
+                        continue;
+                    };
+                    
+                    for code in &item.code_information {
+                        charges.push(HospitalNegotiatedCharge {
+                            hospital_name: self.hospital_name.clone(),
+                            billing_code_type: code.code_type.clone(),
+                            billing_code: code.code.clone(),
+                            description: item.description.clone(),
+                            setting: standard_charge.setting.clone(),
+                            payer_name: payer.payer_name.clone(),
+                            plan_name: payer.plan_name.clone(),
+                            negotiated_type: negotiated_type.clone(),
+                            negotiated_rate,
+                            estimated_amount: payer.estimated_amount,
+                            billing_code_modifier: standard_charge.modifiers.clone(),
+                            methodology: payer.methodology.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        
+        charges
+    }
+}
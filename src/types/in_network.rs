@@ -1,6 +1,7 @@
 //! In-Network file types
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use super::common::{
     EntityType, PlanIdType, MarketType, NegotiationArrangement, 
     BillingCodeType, NegotiatedType, BillingClass, ProviderGroup
@@ -37,6 +38,14 @@ pub struct InNetworkFile {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub plan_market_type: Option<MarketType>,
     
+    /// The legal name of the issuer offering the plan (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer_name: Option<String>,
+    
+    /// The legal name of the plan sponsor (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_sponsor_name: Option<String>,
+    
     /// Array of in-network negotiated rates
     pub in_network: Vec<InNetworkRate>,
     
@@ -49,6 +58,10 @@ pub struct InNetworkFile {
     
     /// The version of the schema for the produced information
     pub version: String,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// In-network rate information.
@@ -74,6 +87,10 @@ pub struct InNetworkRate {
     /// Brief description of the item/service
     pub description: String,
     
+    /// Severity of illness for APR-DRG billing codes (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity_of_illness: Option<String>,
+    
     /// Array of negotiated rate details
     pub negotiated_rates: Vec<NegotiatedRateDetail>,
     
@@ -84,6 +101,10 @@ pub struct InNetworkRate {
     /// Array of covered services if negotiation_arrangement is "capitation"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub covered_services: Option<Vec<CoveredService>>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Negotiated rate details.
@@ -102,6 +123,10 @@ pub struct NegotiatedRateDetail {
     /// (mutually exclusive with provider_groups)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub provider_references: Option<Vec<i32>>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Negotiated price information.
@@ -120,15 +145,15 @@ pub struct NegotiatedPrice {
     /// Use "9999-12-31" for agreements with no expiration.
     pub expiration_date: String,
     
-    /// Whether the service is professional, institutional, or both
-    pub billing_class: BillingClass,
-    
     /// CMS-maintained two-digit place of service codes.
     /// Required when billing_class is "professional".
     /// Use ["CSTM-00"] when rate applies to all service codes.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub service_code: Option<Vec<String>>,
     
+    /// Whether the service is professional, institutional, or both
+    pub billing_class: BillingClass,
+    
     /// Billing code modifiers (e.g., CPT modifiers)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub billing_code_modifier: Option<Vec<String>>,
@@ -136,6 +161,10 @@ pub struct NegotiatedPrice {
     /// Additional context for negotiated arrangements that don't fit the schema
     #[serde(skip_serializing_if = "Option::is_none")]
     pub additional_information: Option<String>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Bundled code information.
@@ -154,6 +183,10 @@ pub struct BundledCode {
     
     /// Brief description of the item/service
     pub description: String,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Covered service for capitation arrangements.
@@ -172,6 +205,10 @@ pub struct CoveredService {
     
     /// Brief description of the item/service
     pub description: String,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Provider reference for deduplication.
@@ -182,6 +219,10 @@ pub struct ProviderReference {
     /// Unique primary key for the associated provider_group
     pub provider_group_id: i32,
     
+    /// Names of the networks the provider groups belong to (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_name: Option<Vec<String>>,
+    
     /// Provider groups (mutually exclusive with location)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub provider_groups: Option<Vec<ProviderGroup>>,
@@ -190,4 +231,8 @@ pub struct ProviderReference {
     /// (mutually exclusive with provider_groups)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub location: Option<String>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 } 
\ No newline at end of file
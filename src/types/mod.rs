@@ -7,6 +7,8 @@ mod provider_reference;
 mod in_network;
 mod allowed_amount;
 mod unified;
+pub mod hospital;
+mod version;
 
 // Re-export all types for convenient access
 pub use common::*;
@@ -15,3 +17,5 @@ pub use provider_reference::*;
 pub use in_network::*;
 pub use allowed_amount::*;
 pub use unified::*;
+pub use hospital::*;
+pub use version::*;
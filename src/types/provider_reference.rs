@@ -1,6 +1,7 @@
 //! Provider Reference file types
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use super::common::ProviderGroup;
 
 /// Provider Reference file structure.
@@ -16,4 +17,8 @@ pub struct ProviderReferenceFile {
     
     /// The version of the schema for the produced information
     pub version: String,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 } 
\ No newline at end of file
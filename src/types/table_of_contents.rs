@@ -1,6 +1,7 @@
 //! Table of Contents file types
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use super::common::{EntityType, PlanIdType, MarketType};
 
 /// Table of Contents file structure.
@@ -22,6 +23,14 @@ pub struct TableOfContentsFile {
     /// The version of the schema for the produced information
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    
+    /// The date in which the file was last updated (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated_on: Option<String>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Reporting structure for table of contents.
@@ -41,6 +50,10 @@ pub struct ReportingStructure {
     /// At least one of `in_network_files` or `allowed_amount_file` must be present.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed_amount_file: Option<FileLocation>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Reporting plan information.
@@ -60,6 +73,18 @@ pub struct ReportingPlan {
     
     /// Whether the plan is offered in the group or individual market
     pub plan_market_type: MarketType,
+    
+    /// The legal name of the issuer offering the plan (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer_name: Option<String>,
+    
+    /// The legal name of the plan sponsor (schema 2.0 and later)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_sponsor_name: Option<String>,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// File location information.
@@ -73,4 +98,8 @@ pub struct FileLocation {
     /// A fully qualified domain name where the file can be downloaded.
     /// Must be an HTTPS URL.
     pub location: String,
+    
+    /// Additional fields not defined by the schema
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 } 
\ No newline at end of file
@@ -0,0 +1,104 @@
+//! Schema version types
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Version of the Transparency in Coverage schema a file was produced with.
+/// 
+/// Parsed from the `version` string every MRF file carries (e.g. "1.0.0").
+/// Versions are ordered, so files can be compared against known releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SchemaVersion {
+    /// Major version number
+    pub major: u32,
+    
+    /// Minor version number
+    pub minor: u32,
+    
+    /// Patch version number
+    pub patch: u32,
+}
+
+impl SchemaVersion {
+    /// The initial Transparency in Coverage schema release
+    pub const V1_0_0: SchemaVersion = SchemaVersion::new(1, 0, 0);
+    
+    /// The 2.0 release, which adds issuer, plan sponsor and network fields
+    pub const V2_0_0: SchemaVersion = SchemaVersion::new(2, 0, 0);
+    
+    /// The newest schema release modelled by the types in this crate
+    pub const LATEST: SchemaVersion = Self::V2_0_0;
+    
+    /// Create a schema version from its components
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+    
+    /// Parse a version string such as "1.0.0", "v2.0" or "1".
+    /// 
+    /// Missing minor and patch components default to zero. Returns `None`
+    /// if the string is not a dotted numeric version.
+    pub fn parse(version: &str) -> Option<Self> {
+        let trimmed = version.trim();
+        let trimmed = trimmed
+            .strip_prefix('v')
+            .or_else(|| trimmed.strip_prefix('V'))
+            .unwrap_or(trimmed);
+        
+        let mut parts = trimmed.split('.');
+        let major = parts.next()?.trim().parse().ok()?;
+        let minor = parts.next().map(|p| p.trim().parse()).transpose().ok()?.unwrap_or(0);
+        let patch = parts.next().map(|p| p.trim().parse()).transpose().ok()?.unwrap_or(0);
+        
+        if parts.next().is_some() {
+            return None;
+        }
+        
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Record of the changes made when upgrading a file to the latest in-crate model.
+/// 
+/// Field paths use dotted notation with `[]` marking array elements,
+/// e.g. `in_network[].severity_of_illness`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpgradeReport {
+    /// The `version` string declared by the file, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_version: Option<String>,
+    
+    /// The schema version detected from the declared version.
+    /// `None` when the file has no version or it could not be parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_version: Option<SchemaVersion>,
+    
+    /// The schema version of the model the file was converted into
+    pub target_version: SchemaVersion,
+    
+    /// Fields of the target model that the source file did not provide
+    /// because they were introduced in a later release
+    pub defaulted_fields: Vec<String>,
+    
+    /// Fields renamed by a later release, as pairs of the source path and the
+    /// target path the value was moved to
+    #[serde(default)]
+    pub upgraded_fields: Vec<(String, String)>,
+    
+    /// Fields present in the source file that the target model does not define.
+    /// Their values are kept in the `extra` map of the enclosing type.
+    pub lost_fields: Vec<String>,
+}
+
+impl UpgradeReport {
+    /// Whether every field of the source file maps to a typed field of the target model
+    pub fn is_lossless(&self) -> bool {
+        self.lost_fields.is_empty()
+    }
+}
@@ -0,0 +1,176 @@
+//! Round-trip tests for canonical JSON serialization over the bundled MRF samples
+
+use mrf_rs::parser::MrfParser;
+use mrf_rs::serializer::MrfSerializer;
+use mrf_rs::types::{
+    BillingClass, BillingCodeType, EntityType, MarketType, NegotiatedType, NegotiationArrangement, PlanIdType,
+    TaxIdType,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Every JSON sample below `mrf-examples`. The XML samples are not supported by the parser.
+fn sample_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in fs::read_dir("mrf-examples").expect("mrf-examples directory") {
+        let dir = dir.unwrap().path();
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Schema spelling of an enumerated value, or `None` when `field` does not
+/// hold an enumerated value
+fn schema_spelling(field: &str, value: &Value) -> Option<Value> {
+    fn respell<T: DeserializeOwned + Serialize>(value: &Value) -> Option<Value> {
+        let parsed: T = serde_json::from_value(value.clone()).ok()?;
+        serde_json::to_value(parsed).ok()
+    }
+    
+    match field {
+        "reporting_entity_type" => respell::<EntityType>(value),
+        "plan_id_type" => respell::<PlanIdType>(value),
+        "plan_market_type" => respell::<MarketType>(value),
+        "negotiation_arrangement" => respell::<NegotiationArrangement>(value),
+        "billing_code_type" => respell::<BillingCodeType>(value),
+        "negotiated_type" => respell::<NegotiatedType>(value),
+        "billing_class" => respell::<BillingClass>(value),
+        "type" => respell::<TaxIdType>(value),
+        _ => None,
+    }
+}
+
+/// Canonical text of a number
+fn canonical_number(value: f64) -> String {
+    MrfSerializer::to_canonical_string(&value).unwrap()
+}
+
+/// Assert that `canonical` is `original` with exactly the normalizations
+/// documented for the canonical form: `null` fields omitted, numbers in their
+/// shortest form and enumerated values in the schema spelling
+fn assert_equivalent(path: &str, field: &str, original: &Value, canonical: &Value) {
+    match (original, canonical) {
+        (Value::Object(original), Value::Object(canonical)) => {
+            for (key, value) in original {
+                let child = format!("{}.{}", path, key);
+                match canonical.get(key) {
+                    Some(canonical_value) => assert_equivalent(&child, key, value, canonical_value),
+                    None => assert!(value.is_null(), "{} was dropped", child),
+                }
+            }
+            for key in canonical.keys() {
+                assert!(original.contains_key(key), "{}.{} was added", path, key);
+            }
+        }
+        (Value::Array(original), Value::Array(canonical)) => {
+            assert_eq!(original.len(), canonical.len(), "{} changed length", path);
+            for (original, canonical) in original.iter().zip(canonical) {
+                assert_equivalent(&format!("{}[]", path), field, original, canonical);
+            }
+        }
+        (Value::Number(original), Value::Number(canonical)) => {
+            let expected = canonical_number(original.as_f64().unwrap());
+            assert_eq!(MrfSerializer::to_canonical_string(canonical).unwrap(), expected, "{} changed value", path);
+        }
+        (Value::String(_), Value::String(_)) => match schema_spelling(field, original) {
+            Some(spelling) => assert_eq!(canonical, &spelling, "{} is not in the schema spelling", path),
+            None => assert_eq!(original, canonical, "{} changed", path),
+        },
+        (original, canonical) => assert_eq!(original, canonical, "{} changed", path),
+    }
+}
+
+fn check_round_trip(path: &Path) {
+    let source = fs::read_to_string(path).unwrap();
+    let original: Value = serde_json::from_str(&source).unwrap();
+    
+    let file = MrfParser::parse_str(&source)
+        .unwrap_or_else(|e| panic!("Failed to parse {}: {}", path.display(), e));
+    let canonical = MrfSerializer::to_canonical_string(&file).unwrap();
+    
+    let reparsed = MrfParser::parse_str(&canonical)
+        .unwrap_or_else(|e| panic!("Failed to re-parse canonical {}: {}", path.display(), e));
+    let again = MrfSerializer::to_canonical_string(&reparsed).unwrap();
+    assert_eq!(canonical, again, "Canonical output of {} is not stable", path.display());
+    
+    let canonical_value: Value = serde_json::from_str(&canonical).unwrap();
+    assert_equivalent(&path.display().to_string(), "", &original, &canonical_value);
+}
+
+#[test]
+fn test_canonical_round_trip_all_samples() {
+    let files = sample_files();
+    assert!(!files.is_empty(), "No samples found in mrf-examples");
+    
+    for path in &files {
+        check_round_trip(path);
+    }
+}
+
+#[test]
+fn test_canonical_preserves_unknown_fields() {
+    let source = r#"{"provider_groups":[{"npi":[1234567890],"tin":{"type":"ein","value":"12-3456789"}}],"version":"1.0.0","publisher":{"id":7}}"#;
+    
+    let file = MrfParser::parse_str(source).unwrap();
+    let canonical = MrfSerializer::to_canonical_string(&file).unwrap();
+    
+    assert_eq!(canonical, source);
+    
+    // Unknown fields of nested structures are kept as well
+    let nested = source.replace(
+        r#""12-3456789"}"#,
+        r#""12-3456789","tin_note":"primary"},"group_name":"Acme Clinic""#,
+    );
+    let file = MrfParser::parse_str(&nested).unwrap();
+    assert_eq!(MrfSerializer::to_canonical_string(&file).unwrap(), nested);
+}
+
+/// In-network file with `fields` spliced in before `in_network`
+fn in_network_file(fields: &str) -> String {
+    format!(
+        r#"{{"reporting_entity_name":"Acme","reporting_entity_type":"health insurance issuer",{}"in_network":[],"last_updated_on":"2024-01-01","version":"1.0.0"}}"#,
+        fields
+    )
+}
+
+fn canonicalize(source: &str) -> String {
+    MrfSerializer::to_canonical_string(&MrfParser::parse_str(source).unwrap()).unwrap()
+}
+
+#[test]
+fn test_canonical_omits_null_fields() {
+    assert_eq!(canonicalize(&in_network_file(r#""plan_name":null,"#)), in_network_file(""));
+}
+
+#[test]
+fn test_canonical_number_form() {
+    let source = r#"{"provider_groups":[{"npi":[1234567890],"tin":{"type":"ein","value":"1"}}],"version":"1.0.0","rates":[100.0,1e2,0.50,1.5e-7,-0.0]}"#;
+    let expected = r#"{"provider_groups":[{"npi":[1234567890],"tin":{"type":"ein","value":"1"}}],"version":"1.0.0","rates":[100,100,0.5,0.00000015,0]}"#;
+    assert_eq!(canonicalize(source), expected);
+}
+
+#[test]
+fn test_canonical_enum_spelling() {
+    let source = in_network_file(r#""plan_id_type":"hios","plan_market_type":"group","#)
+        .replace("health insurance issuer", "Health Insurance Issuer");
+    assert_eq!(
+        canonicalize(&source),
+        in_network_file(r#""plan_id_type":"HIOS","plan_market_type":"group","#)
+    );
+    
+    // Values outside the schema are written as "Other"
+    let source = in_network_file("").replace("health insurance issuer", "medicare");
+    assert_eq!(canonicalize(&source), in_network_file("").replace("health insurance issuer", "Other"));
+}
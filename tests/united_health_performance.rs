@@ -1,4 +1,4 @@
-use mrf_rs::sources::united_health::{UnitedHealthSource, UnitedHealthConfig};
+use mrf_rs::sources::united_health::UnitedHealthSource;
 use mrf_rs::sources::MrfSource;
 use std::time::Instant;
 use test_log::test;